@@ -0,0 +1,219 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use crate::folder::{DirectoryNode, RootFolder};
+use crate::persist;
+use crate::video::VideoInfo;
+
+/// 评分、收藏与备注文件名（位于应用数据目录）
+const ANNOTATIONS_FILE: &str = "annotations.json";
+/// 最高评分（星级）
+pub const MAX_RATING: u8 = 5;
+/// 备注的最大字符数
+const MAX_NOTES_CHARS: usize = 10_000;
+
+/// 用户对视频的评分、收藏与备注
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VideoAnnotation {
+    /// 星级评分（1～5），None 表示未评分
+    pub rating: Option<u8>,
+    pub favorite: bool,
+    pub notes: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl VideoAnnotation {
+    fn is_empty(&self) -> bool {
+        self.rating.is_none() && !self.favorite && self.notes.is_none()
+    }
+
+    /// 设置评分，None 表示清除
+    pub fn set_rating(&mut self, rating: Option<u8>) -> Result<(), String> {
+        if rating.is_some_and(|rating| rating == 0 || rating > MAX_RATING) {
+            return Err(format!("评分必须在 1～{} 之间", MAX_RATING));
+        }
+        self.rating = rating;
+        Ok(())
+    }
+
+    /// 设置备注，空白内容表示清除
+    pub fn set_notes(&mut self, notes: &str) -> Result<(), String> {
+        if notes.chars().count() > MAX_NOTES_CHARS {
+            return Err(format!("备注不能超过 {} 个字符", MAX_NOTES_CHARS));
+        }
+        self.notes = (!notes.trim().is_empty()).then(|| notes.to_string());
+        Ok(())
+    }
+}
+
+/// 保存的条目：记录设置时的文件指纹，文件改名或移动后可以据此找回
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AnnotationEntry {
+    #[serde(default)]
+    fingerprint: Option<String>,
+    #[serde(flatten)]
+    annotation: VideoAnnotation,
+}
+
+/// 评分、收藏与备注存储，按卷标识 + 相对路径 + 文件指纹识别视频
+pub struct AnnotationStore {
+    path: PathBuf,
+    /// 存储键（卷标识）→ 相对路径 → 条目
+    entries: BTreeMap<String, BTreeMap<String, AnnotationEntry>>,
+    /// 每次修改后递增，用于判断搜索索引等派生数据是否过期
+    revision: u64,
+}
+
+impl AnnotationStore {
+    /// 从应用数据目录加载
+    pub fn load(data_dir: &Path) -> Self {
+        let path = data_dir.join(ANNOTATIONS_FILE);
+        let entries = persist::read_json(&path).unwrap_or_else(|e| {
+            println!("加载评分与备注失败: {}", e);
+            BTreeMap::new()
+        });
+        Self { path, entries, revision: 0 }
+    }
+
+    fn save(&mut self) -> Result<(), String> {
+        self.revision += 1;
+        persist::write_json(&self.path, &self.entries)
+    }
+
+    /// 修改计数
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// 按路径获取
+    pub fn get(&self, storage_key: &str, relative_path: &str) -> Option<&VideoAnnotation> {
+        self.entries
+            .get(storage_key)
+            .and_then(|paths| paths.get(relative_path))
+            .map(|entry| &entry.annotation)
+    }
+
+    /// 按文件指纹查找条目所在的位置（存储键，相对路径）
+    pub fn find_by_fingerprint(&self, fingerprint: &str) -> Option<(String, String)> {
+        self.entries.iter().find_map(|(storage_key, paths)| {
+            paths
+                .iter()
+                .find(|(_, entry)| entry.fingerprint.as_deref() == Some(fingerprint))
+                .map(|(path, _)| (storage_key.clone(), path.clone()))
+        })
+    }
+
+    /// 将条目移动到新位置（文件改名或移动后），返回移动后的内容
+    pub fn relocate(&mut self, from: (&str, &str), to: (&str, &str)) -> Result<Option<VideoAnnotation>, String> {
+        let Some(entry) = self.entries.get_mut(from.0).and_then(|paths| paths.remove(from.1)) else {
+            return Ok(None);
+        };
+        self.entries.retain(|_, paths| !paths.is_empty());
+        let annotation = entry.annotation.clone();
+        self.entries.entry(to.0.to_string()).or_default().insert(to.1.to_string(), entry);
+        self.save()?;
+        Ok(Some(annotation))
+    }
+
+    /// 修改视频的评分、收藏或备注；内容全部清空时删除条目
+    pub fn update(
+        &mut self,
+        storage_key: &str,
+        relative_path: &str,
+        fingerprint: Option<String>,
+        edit: impl FnOnce(&mut VideoAnnotation) -> Result<(), String>,
+    ) -> Result<VideoAnnotation, String> {
+        let mut annotation = self.get(storage_key, relative_path).cloned().unwrap_or_default();
+        edit(&mut annotation)?;
+        annotation.updated_at = Some(Utc::now());
+
+        let paths = self.entries.entry(storage_key.to_string()).or_default();
+        if annotation.is_empty() {
+            paths.remove(relative_path);
+            if paths.is_empty() {
+                self.entries.remove(storage_key);
+            }
+        } else {
+            let fingerprint = fingerprint.or_else(|| paths.get(relative_path).and_then(|entry| entry.fingerprint.clone()));
+            paths.insert(
+                relative_path.to_string(),
+                AnnotationEntry {
+                    fingerprint,
+                    annotation: annotation.clone(),
+                },
+            );
+        }
+        self.save()?;
+        Ok(annotation)
+    }
+
+    /// 将根文件夹的评分、收藏与备注附加到视频上
+    pub fn apply_to_videos<'a>(&self, root: &RootFolder, videos: impl IntoIterator<Item = &'a mut VideoInfo>) {
+        let Some(paths) = self.entries.get(&root.storage_key()) else {
+            return;
+        };
+        for video in videos {
+            video.annotation = root
+                .storage_path(&video.path)
+                .ok()
+                .and_then(|key| paths.get(&key))
+                .map(|entry| entry.annotation.clone());
+        }
+    }
+
+    /// 将根文件夹的评分、收藏与备注附加到目录树中的所有视频上
+    pub fn apply_to_tree(&self, root: &RootFolder, node: &mut DirectoryNode) {
+        if !self.entries.contains_key(&root.storage_key()) {
+            return;
+        }
+        self.apply_to_videos(root, &mut node.videos);
+        for child in &mut node.children {
+            self.apply_to_tree(root, child);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotations_update_and_relocate() {
+        let dir = std::env::temp_dir().join(format!("vv-annotations-test-{}", uuid::Uuid::new_v4()));
+        let mut store = AnnotationStore::load(&dir);
+
+        let annotation = store
+            .update("custom:abc", "movies/a.mkv", Some("fp-a".to_string()), |annotation| {
+                annotation.set_rating(Some(4))?;
+                annotation.favorite = true;
+                annotation.set_notes("导演剪辑版")
+            })
+            .unwrap();
+        assert_eq!(annotation.rating, Some(4));
+        assert!(store.update("custom:abc", "movies/a.mkv", None, |a| a.set_rating(Some(6))).is_err());
+        assert_eq!(store.get("custom:abc", "movies/a.mkv").unwrap().rating, Some(4));
+
+        // 文件改名后按指纹找回
+        assert_eq!(store.find_by_fingerprint("fp-a"), Some(("custom:abc".to_string(), "movies/a.mkv".to_string())));
+        store.relocate(("custom:abc", "movies/a.mkv"), ("custom:abc", "movies/A (2021).mkv")).unwrap();
+        let reloaded = AnnotationStore::load(&dir);
+        assert!(reloaded.get("custom:abc", "movies/a.mkv").is_none());
+        assert_eq!(reloaded.get("custom:abc", "movies/A (2021).mkv").unwrap().notes.as_deref(), Some("导演剪辑版"));
+        assert_eq!(reloaded.find_by_fingerprint("fp-a").unwrap().1, "movies/A (2021).mkv");
+
+        // 清空全部内容后删除条目
+        let mut store = reloaded;
+        store
+            .update("custom:abc", "movies/A (2021).mkv", None, |annotation| {
+                annotation.set_rating(None)?;
+                annotation.favorite = false;
+                annotation.set_notes("  ")
+            })
+            .unwrap();
+        assert!(AnnotationStore::load(&dir).entries.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,390 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use crate::cover::CoverManager;
+use crate::folder::DirectoryNode;
+use crate::persist;
+use crate::tags::CatalogTags;
+use crate::video::VideoProcessor;
+
+/// 目录文件名（位于根文件夹的数据目录下）
+const CATALOG_FILE: &str = "catalog.json";
+/// 目录文件格式版本
+const CATALOG_VERSION: u32 = 1;
+/// 修改时间比较的容差（exFAT/FAT32 的时间精度为 2 秒）
+const MTIME_TOLERANCE_SECS: u64 = 2;
+
+/// 可随硬盘携带的目录文件：目录树中的路径均为相对根文件夹的 `/` 分隔路径
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CatalogFile {
+    /// 文件格式版本
+    version: u32,
+    /// 导出时间
+    exported_at: DateTime<Utc>,
+    /// 根文件夹名称
+    root_name: String,
+    /// 导出时的卷标识
+    volume_key: Option<String>,
+    /// 视频总数（用于校验目录文件是否完整）
+    video_count: usize,
+    /// 相对路径的目录树
+    tree: DirectoryNode,
+    /// 标签分配（旧版本的目录文件中没有）
+    #[serde(default)]
+    tags: CatalogTags,
+}
+
+/// 导出结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogExportInfo {
+    /// 目录文件路径
+    pub catalog_path: PathBuf,
+    /// 导出的视频数量
+    pub video_count: usize,
+}
+
+/// 导入结果及与当前文件的校验情况
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogImportReport {
+    /// 目录文件的导出时间
+    pub exported_at: DateTime<Utc>,
+    /// 导入后的视频数量
+    pub video_count: usize,
+    /// 大小与修改时间一致、直接使用目录信息的视频数
+    pub verified: usize,
+    /// 文件已变化、重新读取元数据的视频
+    pub reprobed: Vec<PathBuf>,
+    /// 已不存在、从目录树中移除的视频
+    pub missing: Vec<PathBuf>,
+    /// 导出后新增、已加入目录树的视频
+    pub added: Vec<PathBuf>,
+    /// 目录文件来自另一个卷
+    pub volume_mismatch: bool,
+    /// 从目录文件合并的标签分配数
+    pub imported_tags: usize,
+}
+
+/// 目录文件路径
+pub fn catalog_path(state_dir: &Path) -> PathBuf {
+    state_dir.join(CATALOG_FILE)
+}
+
+/// 将目录树与标签分配导出为相对路径的目录文件
+pub fn export_catalog(
+    root: &Path,
+    state_dir: &Path,
+    root_name: &str,
+    volume_key: Option<String>,
+    tree: &DirectoryNode,
+    tags: CatalogTags,
+) -> Result<CatalogExportInfo, String> {
+    let mut tree = tree.clone();
+    relativize(&mut tree, root);
+    let video_count = tree.all_videos().len();
+
+    let catalog = CatalogFile {
+        version: CATALOG_VERSION,
+        exported_at: Utc::now(),
+        root_name: root_name.to_string(),
+        volume_key,
+        video_count,
+        tree,
+        tags,
+    };
+    let catalog_path = catalog_path(state_dir);
+    persist::write_json(&catalog_path, &catalog)?;
+
+    Ok(CatalogExportInfo {
+        catalog_path,
+        video_count,
+    })
+}
+
+/// 导入时校验当前文件所需的扫描配置
+pub struct CatalogScan<'a> {
+    /// 视频扩展名（小写，不含点）
+    pub video_extensions: &'a [String],
+    /// 用于识别封面图片
+    pub cover_manager: &'a CoverManager,
+}
+
+impl CatalogScan<'_> {
+    fn is_video_file(&self, path: &Path) -> bool {
+        path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .is_some_and(|ext| self.video_extensions.contains(&ext))
+    }
+}
+
+/// 读取目录文件并还原为当前根路径下的目录树，逐个视频与当前文件比对：
+/// 未变化的直接使用，已变化的重新读取元数据，不存在的移除，新增的加入。同时返回目录文件中的标签分配。
+pub fn import_catalog(
+    root: &Path,
+    state_dir: &Path,
+    volume_key: Option<&str>,
+    scan: &CatalogScan,
+) -> Result<(DirectoryNode, CatalogTags, CatalogImportReport), String> {
+    let path = catalog_path(state_dir);
+    let catalog: Option<CatalogFile> = persist::read_json(&path)?;
+    let catalog = catalog.ok_or("根文件夹中没有目录文件".to_string())?;
+
+    if catalog.version > CATALOG_VERSION {
+        return Err(format!("目录文件版本 {} 过新，请升级 VideoVault", catalog.version));
+    }
+    if catalog.tree.all_videos().len() != catalog.video_count {
+        return Err("目录文件不完整或已损坏".to_string());
+    }
+
+    let mut tree = catalog.tree;
+    absolutize(&mut tree, root);
+
+    let mut report = CatalogImportReport {
+        exported_at: catalog.exported_at,
+        video_count: 0,
+        verified: 0,
+        reprobed: Vec::new(),
+        missing: Vec::new(),
+        added: Vec::new(),
+        volume_mismatch: matches!((catalog.volume_key.as_deref(), volume_key), (Some(a), Some(b)) if a != b),
+        imported_tags: 0,
+    };
+    verify_node(&mut tree, &VideoProcessor::new(), scan, &mut report);
+    report.video_count = recount(&mut tree);
+
+    Ok((tree, catalog.tags, report))
+}
+
+/// 将目录树中的绝对路径转换为相对路径；根目录外的封面（如缓存中的内嵌封面）无法携带，予以清除
+fn relativize(node: &mut DirectoryNode, root: &Path) {
+    node.path = persist::relative_key(root, Path::new(&node.path)).unwrap_or_default();
+    node.cover_path = node
+        .cover_path
+        .as_deref()
+        .and_then(|cover| persist::relative_key(root, cover).ok())
+        .map(PathBuf::from);
+    for video in &mut node.videos {
+        if let Ok(key) = persist::relative_key(root, &video.path) {
+            video.path = PathBuf::from(key);
+        }
+    }
+    for child in &mut node.children {
+        relativize(child, root);
+    }
+}
+
+/// 将相对路径还原为根目录下的绝对路径
+fn absolutize(node: &mut DirectoryNode, root: &Path) {
+    node.path = persist::resolve_key(root, &node.path).to_string_lossy().to_string();
+    node.cover_path = node
+        .cover_path
+        .as_deref()
+        .map(|cover| persist::resolve_key(root, &cover.to_string_lossy()));
+    for video in &mut node.videos {
+        video.path = persist::resolve_key(root, &video.path.to_string_lossy());
+    }
+    for child in &mut node.children {
+        absolutize(child, root);
+    }
+}
+
+/// 两个修改时间是否在容差范围内一致
+fn same_mtime(a: Option<SystemTime>, b: Option<SystemTime>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            let diff = a.duration_since(b).or_else(|_| b.duration_since(a)).unwrap_or_default();
+            diff.as_secs() <= MTIME_TOLERANCE_SECS
+        }
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// 校验节点中的视频，并遍历目录加入导出后新增的视频与子目录，重新计算封面
+fn verify_node(node: &mut DirectoryNode, processor: &VideoProcessor, scan: &CatalogScan, report: &mut CatalogImportReport) {
+    let videos = std::mem::take(&mut node.videos);
+    for video in videos {
+        let Ok(metadata) = std::fs::metadata(&video.path) else {
+            report.missing.push(video.path);
+            continue;
+        };
+        if metadata.len() == video.size && same_mtime(metadata.modified().ok(), video.modified_time) {
+            report.verified += 1;
+            node.videos.push(video);
+            continue;
+        }
+        report.reprobed.push(video.path.clone());
+        match processor.create_video_info(video.path.clone()) {
+            Ok(info) => node.videos.push(info),
+            Err(e) => {
+                println!("重新读取视频信息失败: {} ({})", video.path.display(), e);
+                node.videos.push(video);
+            }
+        }
+    }
+
+    let mut images = Vec::new();
+    let mut new_dirs = Vec::new();
+    for entry in std::fs::read_dir(&node.path).into_iter().flatten().flatten() {
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() && !node.children.iter().any(|child| Path::new(&child.path) == path) => {
+                new_dirs.push(path);
+            }
+            Ok(file_type) if file_type.is_file() => {
+                if scan.is_video_file(&path) {
+                    if !node.videos.iter().any(|video| video.path == path) {
+                        add_video(node, processor, path, report);
+                    }
+                } else if scan.cover_manager.is_cover_file(&path) {
+                    images.push(path);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // 已删除的子目录校验后移除（其中的视频计入缺失）
+    node.children.retain_mut(|child| {
+        verify_node(child, processor, scan, report);
+        Path::new(&child.path).is_dir()
+    });
+    for path in new_dirs {
+        let mut child = empty_dir_node(&path);
+        verify_node(&mut child, processor, scan, report);
+        node.children.push(child);
+    }
+    crate::collation::sort_videos(&mut node.videos);
+    crate::collation::sort_nodes(&mut node.children);
+
+    // 代表性封面与扫描时的规则一致：目录封面 → 第一个有封面的视频 → 子目录中的第一个封面
+    images.sort();
+    let video_paths: Vec<PathBuf> = node.videos.iter().map(|video| video.path.clone()).collect();
+    let covers = scan.cover_manager.collect_directory_covers(&images, &video_paths);
+    let folder_cover = covers.iter().find(|cover| scan.cover_manager.is_folder_cover(&cover.path));
+    let video_cover = || {
+        video_paths
+            .iter()
+            .find_map(|video| covers.iter().find(|cover| cover.associated_video.as_ref() == Some(video)))
+    };
+    node.cover_path = folder_cover
+        .or_else(video_cover)
+        .map(|cover| cover.path.clone())
+        .or_else(|| first_child_cover(&node.children));
+    node.cover_count = covers.len() + node.children.iter().map(|child| child.cover_count).sum::<usize>();
+}
+
+/// 读取新增视频的信息并加入节点
+fn add_video(node: &mut DirectoryNode, processor: &VideoProcessor, path: PathBuf, report: &mut CatalogImportReport) {
+    match processor.create_video_info(path.clone()) {
+        Ok(info) => {
+            report.added.push(path);
+            node.videos.push(info);
+        }
+        Err(e) => println!("读取新增视频信息失败: {} ({})", path.display(), e),
+    }
+}
+
+/// 导出后新增的子目录节点（内容由 verify_node 填充）
+fn empty_dir_node(path: &Path) -> DirectoryNode {
+    DirectoryNode {
+        name: path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+        path: path.to_string_lossy().to_string(),
+        is_directory: true,
+        children: Vec::new(),
+        videos: Vec::new(),
+        cover_count: 0,
+        video_count: 0,
+        cover_path: None,
+        modified_time: std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok(),
+    }
+}
+
+/// 按顺序在子目录中查找第一个封面
+fn first_child_cover(children: &[DirectoryNode]) -> Option<PathBuf> {
+    children
+        .iter()
+        .find_map(|child| child.cover_path.clone().or_else(|| first_child_cover(&child.children)))
+}
+
+/// 重新统计各级目录的视频数量（包含子目录），返回总数
+fn recount(node: &mut DirectoryNode) -> usize {
+    let children: usize = node.children.iter_mut().map(recount).sum();
+    node.video_count = node.videos.len() + children;
+    node.video_count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{dir, video};
+    use crate::video::VideoInfo;
+    use std::fs;
+
+    fn video_info(path: &Path) -> VideoInfo {
+        let metadata = fs::metadata(path).unwrap();
+        VideoInfo {
+            size: metadata.len(),
+            codec: Some("h264".to_string()),
+            modified_time: metadata.modified().ok(),
+            ..video(&path.to_string_lossy())
+        }
+    }
+
+    fn dir_node(path: &Path, children: Vec<DirectoryNode>, videos: Vec<VideoInfo>) -> DirectoryNode {
+        dir(&path.to_string_lossy(), children, videos)
+    }
+
+    #[test]
+    fn test_export_import_roundtrip_with_integrity_checks() {
+        let base = std::env::temp_dir().join(format!("vv-catalog-test-{}", uuid::Uuid::new_v4()));
+        let old_root = base.join("Movies");
+        let season = old_root.join("season1");
+        fs::create_dir_all(&season).unwrap();
+        fs::write(season.join("ep1.mkv"), b"one").unwrap();
+        fs::write(season.join("ep2.mkv"), b"two").unwrap();
+
+        let tree = dir_node(
+            &old_root,
+            vec![dir_node(&season, Vec::new(), vec![video_info(&season.join("ep1.mkv")), video_info(&season.join("ep2.mkv"))])],
+            Vec::new(),
+        );
+        let state_dir = persist::root_state_dir(&old_root);
+        let tags = CatalogTags {
+            tags: Vec::new(),
+            assignments: [("season1".to_string(), vec!["已看".to_string()])].into(),
+        };
+        let info = export_catalog(&old_root, &state_dir, "Movies", Some("custom:abc".to_string()), &tree, tags).unwrap();
+        assert_eq!(info.video_count, 2);
+        let saved = fs::read_to_string(&info.catalog_path).unwrap();
+        assert!(saved.contains("season1/ep1.mkv"));
+        assert!(!saved.contains(&*old_root.to_string_lossy()));
+
+        // 同一块硬盘挂载到另一台电脑的不同路径，其中一集已被删除，另有新增的一季和封面
+        let new_root = base.join("Movies1");
+        fs::rename(&old_root, &new_root).unwrap();
+        fs::remove_file(new_root.join("season1").join("ep2.mkv")).unwrap();
+        fs::create_dir_all(new_root.join("season2")).unwrap();
+        fs::write(new_root.join("season2").join("ep1.mkv"), b"new").unwrap();
+        fs::write(new_root.join("season2").join("poster.jpg"), b"jpg").unwrap();
+
+        let cover_manager = CoverManager::new();
+        let video_extensions = vec!["mkv".to_string()];
+        let scan = CatalogScan { video_extensions: &video_extensions, cover_manager: &cover_manager };
+        let (imported, tags, report) = import_catalog(&new_root, &persist::root_state_dir(&new_root), Some("custom:abc"), &scan).unwrap();
+        assert_eq!(report.verified, 1);
+        assert_eq!(report.missing, vec![new_root.join("season1").join("ep2.mkv")]);
+        assert_eq!(report.added, vec![new_root.join("season2").join("ep1.mkv")]);
+        let season2 = imported.find(&new_root.join("season2").to_string_lossy()).unwrap();
+        assert_eq!(season2.cover_path, Some(new_root.join("season2").join("poster.jpg")));
+        assert_eq!(imported.cover_count, 1);
+        assert_eq!(imported.cover_path, season2.cover_path);
+        assert!(!report.volume_mismatch);
+        assert_eq!(tags.assignments["season1"], vec!["已看"]);
+        assert_eq!(imported.video_count, 2);
+        assert_eq!(imported.path, new_root.to_string_lossy());
+        assert_eq!(imported.all_videos()[0].path, new_root.join("season1").join("ep1.mkv"));
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
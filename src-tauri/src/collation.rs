@@ -0,0 +1,118 @@
+use icu_collator::{Collator, CollatorOptions, Strength};
+use std::cmp::Ordering;
+use crate::folder::DirectoryNode;
+use crate::video::VideoInfo;
+
+/// 排序使用的区域：中文按拼音排序（按中文区域惯例汉字排在拉丁字母之前）
+const COLLATION_LOCALE: &str = "zh";
+
+thread_local! {
+    /// 每个线程各自持有一个排序器（rayon 扫描线程中也会用到）
+    static COLLATOR: Option<Collator> = {
+        let mut options = CollatorOptions::new();
+        // 忽略大小写差异，大小写只在最后按原始字符串区分
+        options.strength = Some(Strength::Secondary);
+        let collator = COLLATION_LOCALE
+            .parse()
+            .ok()
+            .and_then(|locale| Collator::try_new(&locale, options).ok());
+        if collator.is_none() {
+            println!("创建排序器失败，回退到按字符排序");
+        }
+        collator
+    };
+}
+
+/// 按区域规则比较文本（中文按拼音），排序器不可用时按小写字符比较
+fn compare_text(a: &str, b: &str) -> Ordering {
+    COLLATOR.with(|collator| match collator {
+        Some(collator) => collator.compare(a, b),
+        None => a.to_lowercase().cmp(&b.to_lowercase()),
+    })
+}
+
+/// 名称片段：连续的数字或连续的非数字字符
+#[derive(Debug, PartialEq)]
+enum Chunk<'a> {
+    Number(&'a str),
+    Text(&'a str),
+}
+
+/// 将名称切分为数字段与文本段
+fn chunks(s: &str) -> Vec<Chunk<'_>> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut in_number: Option<bool> = None;
+    for (index, c) in s.char_indices() {
+        let digit = c.is_ascii_digit();
+        if in_number.is_some_and(|current| current != digit) {
+            let part = &s[start..index];
+            chunks.push(if in_number == Some(true) { Chunk::Number(part) } else { Chunk::Text(part) });
+            start = index;
+        }
+        in_number = Some(digit);
+    }
+    if start < s.len() {
+        let part = &s[start..];
+        chunks.push(if in_number == Some(true) { Chunk::Number(part) } else { Chunk::Text(part) });
+    }
+    chunks
+}
+
+/// 比较两个数字串的数值（不受长度限制），数值相同时前导零少的在前
+fn compare_numbers(a: &str, b: &str) -> Ordering {
+    let (a_trimmed, b_trimmed) = (a.trim_start_matches('0'), b.trim_start_matches('0'));
+    a_trimmed
+        .len()
+        .cmp(&b_trimmed.len())
+        .then_with(|| a_trimmed.cmp(b_trimmed))
+        .then_with(|| a.len().cmp(&b.len()))
+}
+
+/// 自然排序：数字按数值比较（ep2 在 ep10 之前），文本按区域规则比较且不区分大小写；
+/// 完全相同时再按原始字符串比较，保证结果确定
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (a_chunks, b_chunks) = (chunks(a), chunks(b));
+    for (x, y) in a_chunks.iter().zip(&b_chunks) {
+        let ordering = match (x, y) {
+            (Chunk::Number(x), Chunk::Number(y)) => compare_numbers(x, y),
+            (Chunk::Number(_), Chunk::Text(_)) => Ordering::Less,
+            (Chunk::Text(_), Chunk::Number(_)) => Ordering::Greater,
+            (Chunk::Text(x), Chunk::Text(y)) => compare_text(x, y),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a_chunks.len().cmp(&b_chunks.len()).then_with(|| a.cmp(b))
+}
+
+/// 按名称自然排序同一目录下的子目录
+pub fn sort_nodes(nodes: &mut [DirectoryNode]) {
+    nodes.sort_by(|a, b| natural_cmp(&a.name, &b.name).then_with(|| a.path.cmp(&b.path)));
+}
+
+/// 按文件名自然排序同一目录下的视频
+pub fn sort_videos(videos: &mut [VideoInfo]) {
+    videos.sort_by(|a, b| natural_cmp(&a.name, &b.name).then_with(|| a.path.cmp(&b.path)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec!["ep10.mkv", "EP2.mkv", "ep1.mkv", "ep02.mkv", "extras", "ep2.mkv"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(names, vec!["ep1.mkv", "EP2.mkv", "ep2.mkv", "ep02.mkv", "ep10.mkv", "extras"]);
+        assert_eq!(natural_cmp("file99999999999999999999", "file100000000000000000000"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_natural_cmp_pinyin() {
+        let mut names = vec!["中国 第10集", "北京", "Zoo", "阿甘正传", "中国 第2集", "apple"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(names, vec!["阿甘正传", "北京", "中国 第2集", "中国 第10集", "apple", "Zoo"]);
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::persist;
+use crate::query::{Query, QuerySort};
+
+/// 智能合集文件名（位于应用数据目录）
+const COLLECTIONS_FILE: &str = "collections.json";
+
+/// 智能合集：保存的查询与排序方式，成员在目录树变化后重新计算
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmartCollection {
+    pub id: String,
+    pub name: String,
+    /// 查询表达式
+    pub query: String,
+    /// 排序方式
+    #[serde(default)]
+    pub sort: QuerySort,
+    pub created_at: DateTime<Utc>,
+    /// 最近一次计算的成员数量
+    #[serde(default)]
+    pub video_count: usize,
+}
+
+impl SmartCollection {
+    /// 解析合集的查询表达式
+    pub fn parse_query(&self) -> Result<Query, String> {
+        Query::parse(&self.query).map_err(|e| e.to_string())
+    }
+}
+
+/// 智能合集存储
+pub struct CollectionStore {
+    path: PathBuf,
+    collections: Vec<SmartCollection>,
+    /// 成员数量对应的目录树版本号
+    counts_revision: Option<u64>,
+}
+
+impl CollectionStore {
+    /// 从应用数据目录加载智能合集
+    pub fn load(data_dir: &Path) -> Self {
+        let path = data_dir.join(COLLECTIONS_FILE);
+        let collections = persist::read_json(&path).unwrap_or_else(|e| {
+            println!("加载智能合集失败: {}", e);
+            Vec::new()
+        });
+        Self {
+            path,
+            collections,
+            counts_revision: None,
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        persist::write_json(&self.path, &self.collections)
+    }
+
+    /// 所有合集（按创建时间排序）
+    pub fn list(&self) -> &[SmartCollection] {
+        &self.collections
+    }
+
+    /// 获取合集
+    pub fn get(&self, id: &str) -> Option<&SmartCollection> {
+        self.collections.iter().find(|collection| collection.id == id)
+    }
+
+    /// 创建合集：校验名称与查询表达式后保存
+    pub fn create(&mut self, name: &str, query: &str, sort: QuerySort) -> Result<SmartCollection, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("合集名称不能为空".to_string());
+        }
+        if self.collections.iter().any(|collection| collection.name == name) {
+            return Err(format!("已存在名为「{}」的合集", name));
+        }
+        Query::parse(query).map_err(|e| e.to_string())?;
+
+        let collection = SmartCollection {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            query: query.trim().to_string(),
+            sort,
+            created_at: Utc::now(),
+            video_count: 0,
+        };
+        self.collections.push(collection.clone());
+        self.save()?;
+        Ok(collection)
+    }
+
+    /// 删除合集
+    pub fn remove(&mut self, id: &str) -> Result<bool, String> {
+        let before = self.collections.len();
+        self.collections.retain(|collection| collection.id != id);
+        if self.collections.len() == before {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    /// 成员数量是否与指定版本号的目录树一致
+    pub fn counts_current(&self, revision: u64) -> bool {
+        self.counts_revision == Some(revision)
+    }
+
+    /// 更新成员数量，返回是否有变化
+    pub fn set_counts(&mut self, revision: u64, counts: &HashMap<String, usize>) -> bool {
+        self.counts_revision = Some(revision);
+        let mut changed = false;
+        for collection in &mut self.collections {
+            if let Some(&count) = counts.get(&collection.id) {
+                changed |= collection.video_count != count;
+                collection.video_count = count;
+            }
+        }
+        if changed {
+            if let Err(e) = self.save() {
+                println!("保存智能合集失败: {}", e);
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collection_store() {
+        let dir = std::env::temp_dir().join(format!("vv-collections-test-{}", uuid::Uuid::new_v4()));
+        let mut store = CollectionStore::load(&dir);
+
+        let collection = store.create(" 4K 未看 ", "height>=2160 and not tag:watched", QuerySort::default()).unwrap();
+        assert_eq!(collection.name, "4K 未看");
+        assert!(store.create("4K 未看", "h265", QuerySort::default()).is_err());
+        let error = store.create("坏查询", "height>>2160", QuerySort::default()).unwrap_err();
+        assert!(error.contains("位置"));
+
+        assert!(!store.counts_current(3));
+        assert!(store.set_counts(3, &HashMap::from([(collection.id.clone(), 5)])));
+        assert!(store.counts_current(3));
+        assert!(!store.set_counts(4, &HashMap::from([(collection.id.clone(), 5)])));
+
+        let reloaded = CollectionStore::load(&dir);
+        assert_eq!(reloaded.list().len(), 1);
+        assert_eq!(reloaded.get(&collection.id).unwrap().video_count, 5);
+
+        let mut reloaded = reloaded;
+        assert!(reloaded.remove(&collection.id).unwrap());
+        assert!(CollectionStore::load(&dir).list().is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
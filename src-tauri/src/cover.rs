@@ -2,6 +2,8 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::fs;
 use serde::{Serialize, Deserialize};
+use crate::settings::CoverSettings;
+use crate::video::{EmbeddedCover, EmbeddedCoverKind, VideoProcessor};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CoverFormat {
@@ -21,11 +23,30 @@ pub struct CoverInfo {
     pub associated_video: Option<PathBuf>,
 }
 
+/// 根据扩展名检测图片格式
+fn detect_cover_format(path: &Path) -> Option<CoverFormat> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(|ext| match ext.to_lowercase().as_str() {
+            "jpg" | "jpeg" => Some(CoverFormat::JPG),
+            "png" => Some(CoverFormat::PNG),
+            "bmp" => Some(CoverFormat::BMP),
+            "webp" => Some(CoverFormat::WebP),
+            _ => None,
+        })
+}
+
 pub struct CoverManager {
     /// 封面缓存
     covers: HashMap<PathBuf, CoverInfo>,
     /// 视频到封面的映射
     video_to_cover: HashMap<PathBuf, PathBuf>,
+    /// 视频到内嵌封面导出文件的映射（None 表示已探测但没有内嵌封面）
+    embedded_covers: HashMap<PathBuf, Option<PathBuf>>,
+    /// 封面缓存目录（内嵌封面导出位置）
+    cache_dir: Option<PathBuf>,
+    /// 封面设置（图片扩展名、目录封面文件名）
+    settings: CoverSettings,
 }
 
 impl CoverManager {
@@ -33,21 +54,39 @@ impl CoverManager {
         Self {
             covers: HashMap::new(),
             video_to_cover: HashMap::new(),
+            embedded_covers: HashMap::new(),
+            cache_dir: None,
+            settings: CoverSettings::default(),
+        }
+    }
+
+    /// 应用封面设置（对之后的扫描生效）
+    pub fn apply_settings(&mut self, settings: &CoverSettings) {
+        self.settings = settings.clone();
+    }
+
+    /// 创建带封面缓存目录的管理器
+    pub fn with_cache_dir(cache_dir: PathBuf) -> Self {
+        Self {
+            cache_dir: Some(cache_dir),
+            ..Self::new()
         }
     }
 
-    /// 检测图片格式
+    /// 检测图片格式（只识别设置中启用的扩展名）
     pub fn detect_format(&self, path: &Path) -> Option<CoverFormat> {
-        path.extension()
-            .and_then(|ext| ext.to_str())
-            .map(|ext| match ext.to_lowercase().as_str() {
-                "jpg" | "jpeg" => Some(CoverFormat::JPG),
-                "png" => Some(CoverFormat::PNG),
-                "bmp" => Some(CoverFormat::BMP),
-                "webp" => Some(CoverFormat::WebP),
-                _ => None,
-            })
-            .flatten()
+        let ext = path.extension()?.to_string_lossy().to_lowercase();
+        if !self.settings.image_extensions.contains(&ext) {
+            return None;
+        }
+        detect_cover_format(path)
+    }
+
+    /// 是否为目录封面（cover.jpg、folder.png 等）
+    pub fn is_folder_cover(&self, path: &Path) -> bool {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().to_lowercase())
+            .is_some_and(|stem| self.settings.folder_cover_names.contains(&stem))
     }
 
     /// 检查是否为封面文件
@@ -79,60 +118,40 @@ impl CoverManager {
         })
     }
 
-    /// 查找关联的视频文件
-    pub fn find_associated_video(&self, cover_path: &Path) -> Option<PathBuf> {
-        let cover_name = cover_path.file_stem()?;
-        let cover_dir = cover_path.parent()?;
-        
-        // 查找同名的视频文件
-        let video_extensions = ["mp4", "avi", "mkv", "mov", "wmv", "flv", "webm"];
-        
-        for ext in &video_extensions {
-            let video_name = format!("{}.{}", cover_name.to_str()?, ext);
-            let video_path = cover_dir.join(&video_name);
-            if video_path.exists() {
-                return Some(video_path);
-            }
-        }
-        
-        None
+    /// 为同一目录下的图片文件创建封面信息，并按同名规则关联该目录中的视频
+    pub fn collect_directory_covers(&self, images: &[PathBuf], videos: &[PathBuf]) -> Vec<CoverInfo> {
+        images
+            .iter()
+            .filter_map(|image| {
+                let mut cover_info = self.create_cover_info(image.clone()).ok()?;
+                let stem = image.file_stem();
+                cover_info.associated_video = videos
+                    .iter()
+                    .find(|video| video.file_stem() == stem)
+                    .cloned();
+                Some(cover_info)
+            })
+            .collect()
     }
 
-    /// 扫描目录中的封面文件
-    pub fn scan_covers(&mut self, dir_path: &PathBuf) -> Result<Vec<CoverInfo>, Box<dyn std::error::Error>> {
-        let mut covers = Vec::new();
-        
-        if let Ok(entries) = fs::read_dir(dir_path) {
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    let path = entry.path();
-                    
-                    if path.is_file() && self.is_cover_file(&path) {
-                        if let Ok(cover_info) = self.create_cover_info(path.clone()) {
-                            // 查找关联的视频文件
-                            let mut cover_info = cover_info;
-                            cover_info.associated_video = self.find_associated_video(&path);
-                            
-                            // 更新缓存
-                            self.covers.insert(path.clone(), cover_info.clone());
-                            
-                            // 如果找到关联视频，更新映射
-                            if let Some(ref video_path) = cover_info.associated_video {
-                                self.video_to_cover.insert(video_path.clone(), path);
-                            }
-                            
-                            covers.push(cover_info);
-                        }
-                    }
-                }
+    /// 将扫描得到的封面写入缓存与视频映射
+    pub fn index_covers(&mut self, covers: Vec<CoverInfo>) {
+        for cover_info in covers {
+            if let Some(ref video_path) = cover_info.associated_video {
+                self.video_to_cover.insert(video_path.clone(), cover_info.path.clone());
             }
+            self.covers.insert(cover_info.path.clone(), cover_info);
         }
-        
-        Ok(covers)
+    }
+
+    /// 移除某个目录（含子目录）下的封面缓存，用于重新扫描前清理旧数据
+    pub fn remove_covers_under(&mut self, dir_path: &Path) {
+        self.covers.retain(|path, _| !path.starts_with(dir_path));
+        self.video_to_cover.retain(|video, _| !video.starts_with(dir_path));
     }
 
     /// 获取封面信息
-    pub fn get_cover(&self, path: &PathBuf) -> Option<&CoverInfo> {
+    pub fn get_cover(&self, path: &Path) -> Option<&CoverInfo> {
         self.covers.get(path)
     }
 
@@ -142,34 +161,45 @@ impl CoverManager {
     }
 
     /// 根据视频路径查找封面
-    pub fn find_cover_for_video(&self, video_path: &PathBuf) -> Option<&CoverInfo> {
+    pub fn find_cover_for_video(&self, video_path: &Path) -> Option<&CoverInfo> {
         self.video_to_cover.get(video_path)
             .and_then(|cover_path| self.covers.get(cover_path))
     }
 
     /// 检查封面文件是否存在
-    pub fn cover_exists(&self, cover_path: &PathBuf) -> bool {
+    pub fn cover_exists(&self, cover_path: &Path) -> bool {
         cover_path.exists() && self.is_cover_file(cover_path)
     }
 
-    /// 获取视频的封面路径
-    pub fn get_video_cover_path(&self, video_path: &PathBuf) -> Option<PathBuf> {
-        
-        // 首先检查缓存
+    /// 不探测视频，按缓存与同名封面文件查找视频的封面
+    /// 查找顺序：同名封面文件 → 已导出的内嵌封面；都没有且尚未探测过时返回 NeedsProbe
+    pub fn lookup_video_cover(&self, video_path: &Path) -> CoverLookup {
         if let Some(cover_path) = self.video_to_cover.get(video_path) {
             if self.cover_exists(cover_path) {
-                return Some(cover_path.clone());
-            } else {
+                return CoverLookup::Found(cover_path.clone());
             }
         }
-        
-        // 如果缓存中没有，尝试查找
+
+        if let Some(cover_path) = self.find_sidecar_cover(video_path) {
+            return CoverLookup::Found(cover_path);
+        }
+
+        match self.embedded_covers.get(video_path) {
+            Some(Some(path)) if path.exists() => CoverLookup::Found(path.clone()),
+            Some(None) => CoverLookup::NotFound,
+            _ => match &self.cache_dir {
+                Some(cache_dir) => CoverLookup::NeedsProbe(cache_dir.join("covers").join("embedded")),
+                None => CoverLookup::NotFound,
+            },
+        }
+    }
+
+    /// 查找与视频同名的封面文件
+    fn find_sidecar_cover(&self, video_path: &Path) -> Option<PathBuf> {
         let video_name = video_path.file_stem()?;
         let video_dir = video_path.parent()?;
                 
-        let cover_extensions = ["jpg", "jpeg", "png", "bmp", "webp"];
-        
-        for ext in &cover_extensions {
+        for ext in &self.settings.image_extensions {
             let cover_name = format!("{}.{}", video_name.to_str()?, ext);
             let cover_path = video_dir.join(&cover_name);
             
@@ -181,10 +211,95 @@ impl CoverManager {
         None
     }
 
+    /// 记录内嵌封面的探测结果（None 表示没有内嵌封面）
+    pub fn record_embedded_cover(&mut self, video_path: &Path, cover_path: Option<PathBuf>) {
+        self.embedded_covers.insert(video_path.to_path_buf(), cover_path);
+    }
+
     /// 清除缓存
     pub fn clear_cache(&mut self) {
         self.covers.clear();
         self.video_to_cover.clear();
+        self.embedded_covers.clear();
+    }
+}
+
+/// 封面查找结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum CoverLookup {
+    Found(PathBuf),
+    NotFound,
+    /// 需要探测内嵌封面，附带导出目录
+    NeedsProbe(PathBuf),
+}
+
+/// 探测视频的内嵌封面并导出到 `cache_dir`（调用 ffprobe 与 ffmpeg，不应在持有锁时调用）
+/// 扫描时已发现的内嵌封面 `scanned` 直接导出，不再探测；探测或导出失败时返回 Err，结果不应缓存
+pub fn probe_embedded_cover(cache_dir: &Path, video_path: &Path, scanned: Option<&EmbeddedCover>) -> Result<Option<PathBuf>, String> {
+    let metadata;
+    let cover = match scanned {
+        Some(cover) => cover,
+        None => {
+            metadata = VideoProcessor::new()
+                .get_video_metadata_ffprobe(video_path)
+                .ok_or("探测视频失败".to_string())?;
+            match &metadata.embedded_cover {
+                Some(cover) => cover,
+                None => return Ok(None),
+            }
+        }
+    };
+    extract_embedded_cover(cache_dir, video_path, cover)
+        .map(Some)
+        .ok_or("导出内嵌封面失败".to_string())
+}
+
+/// 将内嵌封面导出到封面缓存，文件名使用视频指纹，已导出则直接返回
+fn extract_embedded_cover(cache_dir: &Path, video_path: &Path, cover: &EmbeddedCover) -> Option<PathBuf> {
+    let fingerprint = crate::video::compute_fingerprint(video_path).ok()?;
+    let output_path = cache_dir.join(format!("{}.{}", fingerprint, cover.extension()));
+
+    if output_path.exists() {
+        return Some(output_path);
+    }
+
+    if let Err(e) = fs::create_dir_all(cache_dir) {
+        println!("创建封面缓存目录失败: {}", e);
+        return None;
+    }
+
+    let mut cmd = crate::video::media_command("ffmpeg");
+    cmd.args(["-v", "quiet", "-y"]);
+    match cover.kind {
+        // 附件通过 -dump_attachment 原样导出（ffmpeg 会因缺少输出文件报错，但附件已写出）
+        EmbeddedCoverKind::Attachment => {
+            cmd.arg(format!("-dump_attachment:{}", cover.stream_index))
+                .arg(&output_path)
+                .arg("-i")
+                .arg(video_path);
+        }
+        // 附加图片流直接复制出单帧
+        EmbeddedCoverKind::AttachedPic => {
+            cmd.arg("-i")
+                .arg(video_path)
+                .args(["-map", &format!("0:{}", cover.stream_index)])
+                .args(["-c", "copy", "-frames:v", "1"])
+                .arg(&output_path);
+        }
+    }
+
+    if let Err(e) = cmd.output() {
+        println!("执行 ffmpeg 命令失败: {}", e);
+        return None;
+    }
+
+    let extracted = fs::metadata(&output_path).map(|m| m.len() > 0).unwrap_or(false);
+    if extracted {
+        Some(output_path)
+    } else {
+        let _ = fs::remove_file(&output_path);
+        println!("导出内嵌封面失败: {}", video_path.display());
+        None
     }
 }
 
@@ -221,4 +336,44 @@ mod tests {
         assert!(!manager.is_cover_file(&PathBuf::from("video.mp4")));
         assert!(!manager.is_cover_file(&PathBuf::from("document.pdf")));
     }
+
+    #[test]
+    fn test_collect_directory_covers_associates_videos() {
+        let dir = std::env::temp_dir().join(format!("vv-cover-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let images = vec![dir.join("ep1.jpg"), dir.join("folder.png")];
+        for image in &images {
+            fs::write(image, b"img").unwrap();
+        }
+        let videos = vec![dir.join("ep1.mkv"), dir.join("ep2.mkv")];
+
+        let covers = CoverManager::new().collect_directory_covers(&images, &videos);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(covers.len(), 2);
+        assert_eq!(covers[0].associated_video, Some(dir.join("ep1.mkv")));
+        assert_eq!(covers[1].associated_video, None);
+        assert!(CoverManager::new().is_folder_cover(&covers[1].path));
+    }
+
+    #[test]
+    fn test_index_covers_and_remove_under() {
+        let mut manager = CoverManager::new();
+        let dir = PathBuf::from("/library/show");
+        manager.index_covers(vec![CoverInfo {
+            path: dir.join("ep1.jpg"),
+            name: "ep1.jpg".to_string(),
+            size: 0,
+            format: CoverFormat::JPG,
+            dimensions: None,
+            associated_video: Some(dir.join("ep1.mp4")),
+        }]);
+
+        assert_eq!(
+            manager.find_cover_for_video(&dir.join("ep1.mp4")).map(|c| c.path.clone()),
+            Some(dir.join("ep1.jpg"))
+        );
+
+        manager.remove_covers_under(&dir);
+        assert!(manager.find_cover_for_video(&dir.join("ep1.mp4")).is_none());
+    }
 }
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use crate::folder::DirectoryNode;
+use crate::persist;
+
+/// 自定义封面文件名（位于根文件夹的数据目录下）
+const COVERS_FILE: &str = "covers.json";
+
+/// 自定义封面文件格式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CustomCoverFile {
+    /// 文件格式版本
+    version: u32,
+    /// 目标相对路径 → 封面路径（根目录内为相对路径，否则为绝对路径）
+    covers: BTreeMap<String, String>,
+}
+
+impl Default for CustomCoverFile {
+    fn default() -> Self {
+        Self {
+            version: 1,
+            covers: BTreeMap::new(),
+        }
+    }
+}
+
+/// 一条自定义封面设置（绝对路径）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomCoverEntry {
+    /// 目录或视频路径
+    pub target_path: PathBuf,
+    /// 封面图片路径
+    pub cover_path: PathBuf,
+}
+
+/// 单个根文件夹的自定义封面存储
+pub struct CustomCoverStore {
+    root: PathBuf,
+    file_path: PathBuf,
+    file: CustomCoverFile,
+}
+
+impl CustomCoverStore {
+    /// 加载根文件夹的自定义封面设置，state_dir 为根文件夹的数据目录
+    pub fn load(root: &Path, state_dir: &Path) -> Result<Self, String> {
+        let file_path = state_dir.join(COVERS_FILE);
+        let file = persist::read_json(&file_path)?;
+        Ok(Self {
+            root: root.to_path_buf(),
+            file_path,
+            file,
+        })
+    }
+
+    /// 保存到数据目录下的 `covers.json`
+    pub fn save(&self) -> Result<(), String> {
+        persist::write_json(&self.file_path, &self.file)
+    }
+
+    /// 为目录或视频设置自定义封面
+    pub fn set(&mut self, target: &Path, cover: &Path) -> Result<(), String> {
+        let key = persist::relative_key(&self.root, target)?;
+        // 封面位于根目录内时保存相对路径，使设置随硬盘迁移
+        let value = persist::relative_key(&self.root, cover)
+            .unwrap_or_else(|_| cover.to_string_lossy().to_string());
+        self.file.covers.insert(key, value);
+        Ok(())
+    }
+
+    /// 清除自定义封面，返回是否存在过设置
+    pub fn clear(&mut self, target: &Path) -> Result<bool, String> {
+        let key = persist::relative_key(&self.root, target)?;
+        Ok(self.file.covers.remove(&key).is_some())
+    }
+
+    /// 获取目录或视频的自定义封面（封面文件已不存在时返回 None）
+    pub fn get(&self, target: &Path) -> Option<PathBuf> {
+        let key = persist::relative_key(&self.root, target).ok()?;
+        self.file
+            .covers
+            .get(&key)
+            .map(|value| self.resolve_cover(value))
+            .filter(|path| path.is_file())
+    }
+
+    /// 列出所有自定义封面设置
+    pub fn list(&self) -> Vec<CustomCoverEntry> {
+        self.file
+            .covers
+            .iter()
+            .map(|(key, value)| CustomCoverEntry {
+                target_path: persist::resolve_key(&self.root, key),
+                cover_path: self.resolve_cover(value),
+            })
+            .collect()
+    }
+
+    fn resolve_cover(&self, value: &str) -> PathBuf {
+        let path = PathBuf::from(value);
+        if path.is_absolute() {
+            path
+        } else {
+            persist::resolve_key(&self.root, value)
+        }
+    }
+
+    /// 将自定义封面应用到目录树节点的 `cover_path`
+    pub fn apply_to_tree(&self, node: &mut DirectoryNode) {
+        if self.file.covers.is_empty() {
+            return;
+        }
+        if let Some(cover) = self.get(Path::new(&node.path)) {
+            node.cover_path = Some(cover);
+        }
+        for child in &mut node.children {
+            self.apply_to_tree(child);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_set_get_clear_persisted() {
+        let root = std::env::temp_dir().join(format!("vv-custom-cover-test-{}", uuid::Uuid::new_v4()));
+        let folder = root.join("season1");
+        fs::create_dir_all(&folder).unwrap();
+        let cover = folder.join("art.png");
+        fs::write(&cover, b"img").unwrap();
+
+        let state_dir = persist::root_state_dir(&root);
+        let mut store = CustomCoverStore::load(&root, &state_dir).unwrap();
+        store.set(&folder, &cover).unwrap();
+        store.save().unwrap();
+
+        let reloaded = CustomCoverStore::load(&root, &state_dir).unwrap();
+        assert_eq!(reloaded.get(&folder), Some(cover.clone()));
+        assert_eq!(reloaded.list().len(), 1);
+        assert_eq!(reloaded.file.covers.get("season1").map(String::as_str), Some("season1/art.png"));
+
+        let mut store = reloaded;
+        assert!(store.clear(&folder).unwrap());
+        assert!(store.get(&folder).is_none());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -2,14 +2,18 @@ use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use crate::video::VideoInfo;
+use crate::mount::MountType;
+use crate::video::{ProbeOptions, VideoInfo, VideoProcessor};
+use crate::cover::{CoverInfo, CoverManager};
+use crate::settings::ScanSettings;
+use crate::collation;
 use chrono::{DateTime, Utc};
 use rayon::prelude::*;
 
 /// 并行扫描配置
 #[derive(Debug, Clone)]
 pub struct ParallelScanConfig {
-    /// 最大线程数（0表示使用CPU核心数）
+    /// 最大线程数（0表示使用全局线程池，即CPU核心数）
     pub max_threads: usize,
     /// 每个目录最大文件数限制
     pub max_files_per_dir: usize,
@@ -17,19 +21,108 @@ pub struct ParallelScanConfig {
     pub max_depth: i32,
     /// 是否启用并行扫描
     pub enabled: bool,
+    /// 本地磁盘的 ffprobe 超时（秒，0 表示不限制）
+    pub probe_timeout_secs: u64,
+    /// 视频文件扩展名（小写）
+    pub video_extensions: Vec<String>,
+    /// 备用扫描（系统命令）每个目录最多收录的视频数
+    pub fallback_max_videos: usize,
+    /// 备用扫描（系统命令）每个目录最多收录的子目录数
+    pub fallback_max_children: usize,
 }
 
 impl Default for ParallelScanConfig {
     fn default() -> Self {
+        Self::from(&ScanSettings::default())
+    }
+}
+
+impl From<&ScanSettings> for ParallelScanConfig {
+    fn from(settings: &ScanSettings) -> Self {
         Self {
-            max_threads: num_cpus::get(),
-            max_files_per_dir: 10000,
-            max_depth: 100,
-            enabled: true,
+            max_threads: settings.max_threads,
+            max_files_per_dir: settings.max_files_per_dir,
+            max_depth: settings.max_depth,
+            enabled: settings.parallel,
+            probe_timeout_secs: settings.probe_timeout_secs,
+            video_extensions: settings.video_extensions.clone(),
+            fallback_max_videos: settings.fallback_max_videos,
+            fallback_max_children: settings.fallback_max_children,
         }
     }
 }
 
+/// 扫描配置档：网络共享使用更低的并发、更长的探测超时、只探测文件头，并在 I/O 错误时退避重试
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanProfile {
+    /// 扫描线程数（0 表示使用 rayon 全局线程池）
+    pub max_threads: usize,
+    /// ffprobe 超时（秒，0 表示不限制）
+    pub probe_timeout_secs: u64,
+    /// 只探测文件头
+    pub header_only_probe: bool,
+    /// I/O 错误时的重试次数
+    pub io_retries: u32,
+    /// 首次重试前的等待时间（毫秒），之后每次翻倍
+    pub retry_backoff_ms: u64,
+}
+
+impl ScanProfile {
+    /// 本地磁盘：保持原有的全并发扫描
+    pub fn local() -> Self {
+        Self {
+            max_threads: 0,
+            probe_timeout_secs: 0,
+            header_only_probe: false,
+            io_retries: 0,
+            retry_backoff_ms: 0,
+        }
+    }
+
+    /// 网络共享（SMB/NFS）
+    pub fn network() -> Self {
+        Self {
+            max_threads: 2,
+            probe_timeout_secs: 30,
+            header_only_probe: true,
+            io_retries: 3,
+            retry_backoff_ms: 500,
+        }
+    }
+
+    /// 根据挂载类型选择配置档
+    pub fn for_mount_type(mount_type: MountType) -> Self {
+        match mount_type {
+            MountType::Network => Self::network(),
+            _ => Self::local(),
+        }
+    }
+
+    /// 对应的 ffprobe 探测选项
+    pub fn probe_options(&self) -> ProbeOptions {
+        ProbeOptions {
+            timeout: (self.probe_timeout_secs > 0).then(|| std::time::Duration::from_secs(self.probe_timeout_secs)),
+            header_only: self.header_only_probe,
+        }
+    }
+}
+
+impl Default for ScanProfile {
+    fn default() -> Self {
+        Self::local()
+    }
+}
+
+/// 是否为可重试的 I/O 错误（网络共享上的 EIO、超时等）
+fn is_transient_io_error(error: &std::io::Error) -> bool {
+    // EIO = 5（仅 Unix；Windows 上 5 为拒绝访问）
+    (cfg!(unix) && error.raw_os_error() == Some(5))
+        || matches!(
+            error.kind(),
+            std::io::ErrorKind::TimedOut | std::io::ErrorKind::Interrupted | std::io::ErrorKind::WouldBlock
+        )
+}
+
 /// 根文件夹配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RootFolder {
@@ -45,10 +138,115 @@ pub struct RootFolder {
     pub max_depth: i32,
     /// 最后扫描时间
     pub last_scan: Option<DateTime<Utc>>,
+    /// 所在卷的标识
+    #[serde(default)]
+    pub volume_key: Option<String>,
+    /// 根文件夹在卷上的位置（相对挂载点的 `/` 分隔路径，自定义卷标识为空），与卷标识一起定位用户数据
+    #[serde(default)]
+    pub volume_path: String,
+    /// 所在卷是否在线（离线时仍可浏览最后一次扫描的目录树）
+    #[serde(default = "default_online")]
+    pub online: bool,
+    /// 挂载类型（本地 / 网络共享）
+    #[serde(default)]
+    pub mount_type: MountType,
+    /// 文件系统类型（ext4、ntfs3、cifs、nfs4 等）
+    #[serde(default)]
+    pub fs_type: Option<String>,
+    /// 单独的扫描选项（覆盖按挂载类型选择的配置档）
+    #[serde(default)]
+    pub scan_options: RootScanOptions,
+}
+
+impl RootFolder {
+    /// 用户数据（标签等）的存储键：优先使用卷标识，使数据在重新挂载到其他路径后仍然有效；
+    /// 尚未获取卷标识时退回到根文件夹 ID
+    pub fn storage_key(&self) -> String {
+        self.volume_key.clone().unwrap_or_else(|| format!("root:{}", self.id))
+    }
+
+    /// 路径在用户数据中的相对路径：根文件夹在卷上的位置加上相对根目录的路径，
+    /// 同一卷上的多个根文件夹互不冲突，移除后重新添加也能找回原有数据
+    pub fn storage_path(&self, path: &std::path::Path) -> Result<String, String> {
+        let relative = crate::persist::relative_key(&self.path, path)?;
+        Ok(crate::persist::join_key(self.storage_prefix(), &relative))
+    }
+
+    /// 将用户数据中的相对路径还原为绝对路径，不在该根文件夹下时返回 None
+    pub fn resolve_storage_path(&self, storage_path: &str) -> Option<PathBuf> {
+        let relative = crate::persist::strip_key_prefix(self.storage_prefix(), storage_path)?;
+        Some(crate::persist::resolve_key(&self.path, relative))
+    }
+
+    /// 用户数据中根文件夹自身的相对路径（没有卷标识时按根文件夹 ID 存储，为空）
+    pub fn storage_prefix(&self) -> &str {
+        if self.volume_key.is_some() {
+            &self.volume_path
+        } else {
+            ""
+        }
+    }
+}
+
+/// 根文件夹的扫描选项，未设置的项使用全局配置或按挂载类型选择的配置档
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RootScanOptions {
+    /// 是否并行扫描
+    pub parallel: Option<bool>,
+    /// 扫描线程数（0 表示使用全局线程池）
+    pub max_threads: Option<usize>,
+    /// ffprobe 超时（秒，0 表示不限制）
+    pub probe_timeout_secs: Option<u64>,
+    /// 只探测文件头
+    pub header_only_probe: Option<bool>,
+}
+
+impl RootScanOptions {
+    /// 将选项应用到配置档
+    pub fn apply_to(&self, profile: &mut ScanProfile) {
+        if let Some(max_threads) = self.max_threads {
+            profile.max_threads = max_threads;
+        }
+        if let Some(timeout) = self.probe_timeout_secs {
+            profile.probe_timeout_secs = timeout;
+        }
+        if let Some(header_only) = self.header_only_probe {
+            profile.header_only_probe = header_only;
+        }
+    }
+}
+
+/// 根文件夹列表文件名（位于应用数据目录）
+const ROOTS_FILE: &str = "roots.json";
+
+fn default_online() -> bool {
+    true
+}
+
+/// 新根文件夹与已有根文件夹的重叠关系
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RootOverlap {
+    /// 与已有根文件夹路径相同
+    SamePath { root_id: String },
+    /// 位于已有根文件夹内
+    InsideExisting { root_id: String },
+    /// 包含已有的根文件夹
+    ContainsExisting { root_ids: Vec<String> },
+}
+
+/// 重叠时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlapMode {
+    /// 合并：位于已有根文件夹内时直接使用已有根文件夹；包含已有根文件夹时将其并入新根文件夹
+    Merge,
+    /// 嵌套：保留各自的根文件夹，外层扫描时跳过内层根文件夹，保证每个文件只属于一个根文件夹
+    Nest,
 }
 
 /// 目录树节点
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DirectoryNode {
     /// 节点名称
     pub name: String,
@@ -70,6 +268,101 @@ pub struct DirectoryNode {
     pub modified_time: Option<std::time::SystemTime>,
 }
 
+impl DirectoryNode {
+    /// 按路径查找节点（包括自身）
+    pub fn find(&self, path: &str) -> Option<&DirectoryNode> {
+        if self.path == path {
+            return Some(self);
+        }
+        if !std::path::Path::new(path).starts_with(&self.path) {
+            return None;
+        }
+        self.children.iter().find_map(|child| child.find(path))
+    }
+
+    /// 将节点中的路径前缀从 old_root 替换为 new_root（根文件夹重新绑定时使用）
+    pub fn rebase(&mut self, old_root: &std::path::Path, new_root: &std::path::Path) {
+        let rebase_path = |path: &std::path::Path| -> Option<PathBuf> {
+            path.strip_prefix(old_root).ok().map(|rel| new_root.join(rel))
+        };
+        if let Some(path) = rebase_path(std::path::Path::new(&self.path)) {
+            self.path = path.to_string_lossy().to_string();
+        }
+        if let Some(cover) = self.cover_path.as_deref().and_then(rebase_path) {
+            self.cover_path = Some(cover);
+        }
+        for video in &mut self.videos {
+            if let Some(path) = rebase_path(&video.path) {
+                video.path = path;
+            }
+        }
+        for child in &mut self.children {
+            child.rebase(old_root, new_root);
+        }
+    }
+
+    /// 从树中移除指定路径的子目录，并更新上级目录的视频与封面数量；返回是否找到该子目录
+    pub fn remove_subtree(&mut self, path: &str) -> bool {
+        self.take_subtree(path).is_some()
+    }
+
+    fn take_subtree(&mut self, path: &str) -> Option<(usize, usize)> {
+        if !std::path::Path::new(path).starts_with(&self.path) {
+            return None;
+        }
+        let removed = match self.children.iter().position(|child| child.path == path) {
+            Some(index) => {
+                let child = self.children.remove(index);
+                Some((child.video_count, child.cover_count))
+            }
+            None => self.children.iter_mut().find_map(|child| child.take_subtree(path)),
+        }?;
+        self.video_count = self.video_count.saturating_sub(removed.0);
+        self.cover_count = self.cover_count.saturating_sub(removed.1);
+        Some(removed)
+    }
+
+    /// 收集该节点及所有子目录中的视频
+    pub fn all_videos(&self) -> Vec<&VideoInfo> {
+        let mut videos: Vec<&VideoInfo> = self.videos.iter().collect();
+        for child in &self.children {
+            videos.extend(child.all_videos());
+        }
+        videos
+    }
+
+    /// 递归按名称自然排序子目录和视频（用于整理旧版本保存的快照）
+    pub fn sort_entries(&mut self) {
+        collation::sort_nodes(&mut self.children);
+        collation::sort_videos(&mut self.videos);
+        for child in &mut self.children {
+            child.sort_entries();
+        }
+    }
+}
+
+/// 目录树扫描过程中的封面收集器
+pub struct CoverCollector<'a> {
+    /// 用于创建封面信息的封面管理器
+    cover_manager: &'a CoverManager,
+    /// 扫描过程中发现的所有封面
+    covers: Mutex<Vec<CoverInfo>>,
+}
+
+impl<'a> CoverCollector<'a> {
+    pub fn new(cover_manager: &'a CoverManager) -> Self {
+        Self {
+            cover_manager,
+            covers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// 取出收集到的封面
+    pub fn into_covers(self) -> Vec<CoverInfo> {
+        self.covers.into_inner().unwrap_or_default()
+    }
+}
+
 /// 文件夹管理器
 pub struct FolderManager {
     /// 根文件夹列表
@@ -78,6 +371,14 @@ pub struct FolderManager {
     directory_trees: HashMap<String, DirectoryNode>,
     /// 并行扫描配置
     parallel_config: ParallelScanConfig,
+    /// 应用数据目录（保存目录树快照）
+    data_dir: Option<PathBuf>,
+    /// 存储策略（只读模式）
+    storage_policy: crate::policy::StoragePolicy,
+    /// 当前扫描使用的配置档
+    scan_profile: ScanProfile,
+    /// 目录树版本号，目录树变化时递增（搜索索引等据此判断是否需要重建）
+    library_revision: u64,
 }
 
 impl FolderManager {
@@ -87,7 +388,143 @@ impl FolderManager {
             root_folders: HashMap::new(),
             directory_trees: HashMap::new(),
             parallel_config: ParallelScanConfig::default(),
+            data_dir: None,
+            storage_policy: crate::policy::StoragePolicy::default(),
+            scan_profile: ScanProfile::default(),
+            library_revision: 0,
+        }
+    }
+
+    /// 创建使用应用数据目录的文件夹管理器
+    pub fn with_data_dir(data_dir: PathBuf) -> Self {
+        let roots: Vec<RootFolder> = crate::persist::read_json(&data_dir.join(ROOTS_FILE)).unwrap_or_else(|e| {
+            println!("加载根文件夹列表失败: {}", e);
+            Vec::new()
+        });
+        Self {
+            root_folders: roots.into_iter().map(|root| (root.id.clone(), root)).collect(),
+            storage_policy: crate::policy::StoragePolicy::load(&data_dir),
+            data_dir: Some(data_dir),
+            ..Self::new()
+        }
+    }
+
+    /// 保存根文件夹列表（应用重启后恢复）
+    fn save_roots(&self) {
+        let Some(data_dir) = &self.data_dir else {
+            return;
+        };
+        let mut roots: Vec<&RootFolder> = self.root_folders.values().collect();
+        roots.sort_by(|a, b| a.path.cmp(&b.path).then_with(|| a.id.cmp(&b.id)));
+        if let Err(e) = crate::persist::write_json(&data_dir.join(ROOTS_FILE), &roots) {
+            println!("保存根文件夹列表失败: {}", e);
+        }
+    }
+
+    /// 修改根文件夹并保存，返回根文件夹是否存在
+    fn update_root(&mut self, id: &str, update: impl FnOnce(&mut RootFolder)) -> bool {
+        match self.root_folders.get_mut(id) {
+            Some(folder) => {
+                update(folder);
+                self.save_roots();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 获取存储策略
+    pub fn storage_policy(&self) -> &crate::policy::StoragePolicy {
+        &self.storage_policy
+    }
+
+    /// 修改存储策略并保存
+    pub fn update_storage_policy(&mut self, update: impl FnOnce(&mut crate::policy::StoragePolicy)) -> Result<(), String> {
+        update(&mut self.storage_policy);
+        match &self.data_dir {
+            Some(data_dir) => self.storage_policy.save(data_dir),
+            None => Ok(()),
+        }
+    }
+
+    /// 根文件夹是否为只读（不向用户目录写入任何文件）
+    pub fn is_root_read_only(&self, id: &str) -> bool {
+        self.storage_policy.is_read_only(id)
+    }
+
+    /// 只读根文件夹在应用数据目录中的数据目录
+    pub fn data_state_dir(&self, id: &str) -> Option<PathBuf> {
+        self.data_dir
+            .as_ref()
+            .map(|dir| crate::policy::data_state_dir(dir, id))
+    }
+
+    /// 根文件夹的数据目录：只读时位于应用数据目录，否则为根目录下的 `.videovault/`
+    pub fn root_state_dir(&self, id: &str) -> Option<PathBuf> {
+        if self.is_root_read_only(id) {
+            self.data_state_dir(id)
+        } else {
+            self.root_folders
+                .get(id)
+                .map(|root| crate::persist::root_state_dir(&root.path))
+        }
+    }
+
+    /// 设置根文件夹的目录树（扫描或导入目录后），同时保存快照
+    pub fn set_directory_tree(&mut self, root_id: &str, tree: DirectoryNode) {
+        if let Some(snapshot_path) = self.tree_snapshot_path(root_id) {
+            if let Err(e) = crate::persist::write_json(&snapshot_path, &tree) {
+                println!("保存目录树快照失败: {}", e);
+            }
         }
+        self.directory_trees.insert(root_id.to_string(), tree);
+        self.library_revision += 1;
+    }
+
+    /// 目录树版本号
+    pub fn library_revision(&self) -> u64 {
+        self.library_revision
+    }
+
+    /// 为内存中还没有目录树的根文件夹加载快照
+    pub fn load_all_directory_trees(&mut self) {
+        let ids: Vec<String> = self.root_folders.keys().cloned().collect();
+        for id in &ids {
+            self.get_or_load_directory_tree(id);
+        }
+    }
+
+    /// 内存中所有根文件夹的目录树，按根文件夹 ID 排序
+    pub fn directory_trees(&self) -> Vec<(&str, &DirectoryNode)> {
+        let mut trees: Vec<(&str, &DirectoryNode)> = self
+            .directory_trees
+            .iter()
+            .filter(|(id, _)| self.root_folders.contains_key(*id))
+            .map(|(id, tree)| (id.as_str(), tree))
+            .collect();
+        trees.sort_by(|a, b| a.0.cmp(b.0));
+        trees
+    }
+
+    /// 目录树快照文件路径
+    fn tree_snapshot_path(&self, root_id: &str) -> Option<PathBuf> {
+        self.data_dir
+            .as_ref()
+            .map(|dir| dir.join("trees").join(format!("{}.json", root_id)))
+    }
+
+    /// 获取目录树：优先使用内存缓存，否则加载最后一次扫描保存的快照
+    pub fn get_or_load_directory_tree(&mut self, root_id: &str) -> Option<&DirectoryNode> {
+        if !self.directory_trees.contains_key(root_id) {
+            let mut snapshot = self
+                .tree_snapshot_path(root_id)
+                .and_then(|path| crate::persist::read_json::<Option<DirectoryNode>>(&path).ok())
+                .flatten()?;
+            snapshot.sort_entries();
+            self.directory_trees.insert(root_id.to_string(), snapshot);
+            self.library_revision += 1;
+        }
+        self.directory_trees.get(root_id)
     }
 
     /// 创建空的目录节点
@@ -149,6 +586,7 @@ impl FolderManager {
                 .unwrap_or("Unknown")
                 .to_string()
         });
+        let (mount_type, fs_type) = crate::mount::detect_mount_type(&path);
 
         let root_folder = RootFolder {
             id: id.clone(),
@@ -157,9 +595,16 @@ impl FolderManager {
             enabled: true,
             max_depth: -1,
             last_scan: None,
+            volume_key: None,
+            volume_path: String::new(),
+            online: true,
+            mount_type,
+            fs_type,
+            scan_options: RootScanOptions::default(),
         };
 
         self.root_folders.insert(id.clone(), root_folder);
+        self.save_roots();
         id
     }
 
@@ -171,6 +616,7 @@ impl FolderManager {
                 .unwrap_or("Unknown")
                 .to_string()
         });
+        let (mount_type, fs_type) = crate::mount::detect_mount_type(&path);
 
         let root_folder = RootFolder {
             id: id.clone(),
@@ -179,15 +625,31 @@ impl FolderManager {
             enabled: true,
             max_depth: -1,
             last_scan: None,
+            volume_key: None,
+            volume_path: String::new(),
+            online: true,
+            mount_type,
+            fs_type,
+            scan_options: RootScanOptions::default(),
         };
 
         self.root_folders.insert(id.clone(), root_folder);
+        self.save_roots();
         id
     }
 
     /// 移除根文件夹
     pub fn remove_root_folder(&mut self, id: &str) -> bool {
-        self.root_folders.remove(id).is_some()
+        self.directory_trees.remove(id);
+        self.library_revision += 1;
+        if let Some(snapshot_path) = self.tree_snapshot_path(id) {
+            let _ = std::fs::remove_file(snapshot_path);
+        }
+        let removed = self.root_folders.remove(id).is_some();
+        if removed {
+            self.save_roots();
+        }
+        removed
     }
 
     /// 获取根文件夹
@@ -195,6 +657,148 @@ impl FolderManager {
         self.root_folders.get(id)
     }
 
+    /// 查找包含指定路径的根文件夹（存在多个时取路径最长者）
+    pub fn find_root_for_path(&self, path: &std::path::Path) -> Option<&RootFolder> {
+        self.root_folders
+            .values()
+            .filter(|root| path.starts_with(&root.path))
+            .max_by_key(|root| root.path.components().count())
+    }
+
+    /// 将根文件夹 ID 与相对路径（`/` 分隔）解析为根文件夹内已存在的视频文件。
+    /// 每一段必须是普通文件名（拒绝 `..`、`.`、盘符与嵌套分隔符），并按真实路径确认没有经符号链接指向根文件夹外
+    pub fn resolve_video_path(&self, root_id: &str, relative_path: &str) -> Result<PathBuf, String> {
+        let root = self.get_root_folder(root_id).ok_or("根文件夹不存在".to_string())?;
+        let valid = !relative_path.is_empty()
+            && relative_path.split('/').all(|part| {
+                let mut components = std::path::Path::new(part).components();
+                matches!((components.next(), components.next()), (Some(std::path::Component::Normal(_)), None))
+            });
+        if !valid || !root.path.is_absolute() {
+            return Err(format!("视频路径无效: {}", relative_path));
+        }
+        let path = crate::persist::resolve_key(&root.path, relative_path);
+        let inside = match (path.canonicalize(), root.path.canonicalize()) {
+            (Ok(real_path), Ok(real_root)) => real_path.starts_with(real_root) && real_path.is_file(),
+            _ => false,
+        };
+        if !inside {
+            return Err(format!("视频文件不存在: {}", relative_path));
+        }
+        Ok(path)
+    }
+
+    /// 检测新路径与已有根文件夹的重叠关系：要求已有根文件夹的卷当前挂载在其路径上，且路径互为前缀。
+    /// 离线根文件夹只识别路径完全相同的情况；同一挂载点换成另一块硬盘时，旧根文件夹不算重叠。
+    pub fn detect_overlap(&self, path: &std::path::Path) -> Option<RootOverlap> {
+        let normalize = |p: &std::path::Path| std::fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+        let new_path = normalize(path);
+
+        let mut inside: Option<&RootFolder> = None;
+        let mut contained = Vec::new();
+        for root in self.root_folders.values() {
+            if !root.path.is_dir() {
+                if root.path == path {
+                    return Some(RootOverlap::SamePath { root_id: root.id.clone() });
+                }
+                continue;
+            }
+            if !self.volume_present(root) {
+                continue;
+            }
+            let root_path = normalize(&root.path);
+            if root_path == new_path {
+                return Some(RootOverlap::SamePath { root_id: root.id.clone() });
+            }
+            if new_path.starts_with(&root_path) {
+                // 多层嵌套时取最近的上级根文件夹
+                if inside.map_or(true, |current| root.path.components().count() > current.path.components().count()) {
+                    inside = Some(root);
+                }
+            } else if root_path.starts_with(&new_path) {
+                contained.push(root.id.clone());
+            }
+        }
+
+        if let Some(root) = inside {
+            return Some(RootOverlap::InsideExisting { root_id: root.id.clone() });
+        }
+        if contained.is_empty() {
+            return None;
+        }
+        contained.sort();
+        Some(RootOverlap::ContainsExisting { root_ids: contained })
+    }
+
+    /// 根文件夹路径上当前挂载的是否为它记录的卷（未记录或旧格式的卷标识只能按路径判断）
+    fn volume_present(&self, root: &RootFolder) -> bool {
+        let Some(volume_key) = root.volume_key.as_deref().filter(|key| !crate::volume::is_legacy_unix_key(key)) else {
+            return true;
+        };
+        crate::volume::read_volume_key(&root.path, self.data_state_dir(&root.id).as_deref())
+            .map_or(true, |current| current == volume_key)
+    }
+
+    /// 按指定方式处理重叠。返回 Some(已有根文件夹 ID) 表示无需再添加新根文件夹。
+    /// 未指定处理方式时，除路径相同外均返回错误，由前端询问用户后重试。
+    pub fn resolve_overlap(&mut self, path: &std::path::Path, overlap: RootOverlap, mode: Option<OverlapMode>) -> Result<Option<String>, String> {
+        match (overlap, mode) {
+            (RootOverlap::SamePath { root_id }, _) => Ok(Some(root_id)),
+            (RootOverlap::InsideExisting { root_id }, Some(OverlapMode::Merge)) => Ok(Some(root_id)),
+            (RootOverlap::InsideExisting { root_id }, Some(OverlapMode::Nest)) => {
+                // 从外层根文件夹的目录树中移除该子目录，避免重复统计
+                let path = path.to_string_lossy().to_string();
+                if let Some(mut tree) = self.get_or_load_directory_tree(&root_id).cloned() {
+                    if tree.remove_subtree(&path) {
+                        self.set_directory_tree(&root_id, tree);
+                    }
+                }
+                Ok(None)
+            }
+            (RootOverlap::ContainsExisting { root_ids }, Some(OverlapMode::Merge)) => {
+                for root_id in root_ids {
+                    self.remove_root_folder(&root_id);
+                }
+                Ok(None)
+            }
+            (RootOverlap::ContainsExisting { .. }, Some(OverlapMode::Nest)) => Ok(None),
+            (RootOverlap::InsideExisting { root_id }, None) => {
+                let name = self.get_root_folder(&root_id).map(|root| root.name.clone()).unwrap_or_default();
+                Err(format!("该路径位于已有根文件夹「{}」内，请选择合并或嵌套", name))
+            }
+            (RootOverlap::ContainsExisting { root_ids }, None) => {
+                Err(format!("该路径包含 {} 个已有根文件夹，请选择合并或嵌套", root_ids.len()))
+            }
+        }
+    }
+
+    /// 路径是否为其他根文件夹（扫描外层根文件夹时跳过嵌套的根文件夹）
+    fn is_root_path(&self, path: &std::path::Path) -> bool {
+        self.root_folders.values().any(|root| root.path == path)
+    }
+
+    /// 按卷标识查找根文件夹
+    pub fn find_root_by_volume_key(&self, volume_key: &str) -> Option<&RootFolder> {
+        self.root_folders
+            .values()
+            .find(|root| root.volume_key.as_deref() == Some(volume_key))
+    }
+
+    /// 将根文件夹重新绑定到新路径：保留 ID 与相关数据，并重写已缓存目录树中的路径
+    pub fn relocate_root(&mut self, id: &str, new_path: PathBuf) -> Option<PathBuf> {
+        let folder = self.root_folders.get_mut(id)?;
+        let old_path = std::mem::replace(&mut folder.path, new_path.clone());
+        self.save_roots();
+
+        if let Some(tree) = self.get_or_load_directory_tree(id).cloned() {
+            let mut tree = tree;
+            tree.rebase(&old_path, &new_path);
+            self.set_directory_tree(id, tree);
+        }
+
+        Some(old_path)
+    }
+
     /// 获取所有根文件夹
     pub fn get_all_root_folders(&self) -> Vec<RootFolder> {
         self.root_folders.values().cloned().collect()
@@ -202,26 +806,50 @@ impl FolderManager {
 
     /// 启用/禁用根文件夹
     pub fn set_folder_enabled(&mut self, id: &str, enabled: bool) -> bool {
-        if let Some(folder) = self.root_folders.get_mut(id) {
-            folder.enabled = enabled;
-            true
-        } else {
-            false
+        self.update_root(id, |folder| folder.enabled = enabled)
+    }
+
+    /// 重命名根文件夹（搜索结果与合集中带有根文件夹名称，需要递增版本号）
+    pub fn rename_root_folder(&mut self, id: &str, name: String) -> bool {
+        let updated = self.update_root(id, |folder| folder.name = name);
+        if updated {
+            self.library_revision += 1;
+        }
+        updated
+    }
+
+    /// 设置根文件夹的扫描选项
+    pub fn set_scan_options(&mut self, id: &str, options: RootScanOptions) -> bool {
+        self.update_root(id, |folder| folder.scan_options = options)
+    }
+
+    /// 设置根文件夹所在卷的标识与在卷上的位置（变化会影响派生数据，需要递增版本号）
+    pub fn set_volume_key(&mut self, id: &str, volume_key: String, volume_path: String) -> bool {
+        let updated = self.update_root(id, |folder| {
+            folder.volume_key = Some(volume_key);
+            folder.volume_path = volume_path;
+        });
+        if updated {
+            self.library_revision += 1;
+        }
+        updated
+    }
+
+    /// 设置根文件夹在线状态，返回状态是否发生变化
+    pub fn set_root_online(&mut self, id: &str, online: bool) -> bool {
+        match self.root_folders.get(id) {
+            Some(folder) if folder.online != online => self.update_root(id, |folder| folder.online = online),
+            _ => false,
         }
     }
 
     /// 设置扫描深度限制
     pub fn set_max_depth(&mut self, id: &str, max_depth: i32) -> bool {
-        if let Some(folder) = self.root_folders.get_mut(id) {
-            folder.max_depth = max_depth;
-            true
-        } else {
-            false
-        }
+        self.update_root(id, |folder| folder.max_depth = max_depth)
     }
 
-    /// 构建目录树
-    pub fn build_directory_tree(&mut self, root_id: &str) -> Result<DirectoryNode, Box<dyn std::error::Error>> {
+    /// 构建目录树，同时索引树中所有目录的封面
+    pub fn build_directory_tree(&mut self, root_id: &str, cover_manager: &mut CoverManager) -> Result<DirectoryNode, Box<dyn std::error::Error>> {
         let root_folder = self.get_root_folder(root_id)
             .ok_or("根文件夹不存在")?;
 
@@ -229,21 +857,69 @@ impl FolderManager {
             return Err("根文件夹已禁用".into());
         }
 
-        let tree = if self.parallel_config.enabled {
+        // 卷不在线时不扫描，避免用空目录树覆盖快照
+        if !root_folder.path.is_dir() {
+            return Err("根文件夹所在的卷不在线".into());
+        }
+
+        let mut root_folder = root_folder.clone();
+        let root_path = root_folder.path.clone();
+
+        // 每次扫描前重新判断挂载类型，网络共享使用单独的扫描配置档
+        (root_folder.mount_type, root_folder.fs_type) = crate::mount::detect_mount_type(&root_path);
+        let mut profile = ScanProfile::for_mount_type(root_folder.mount_type);
+        if root_folder.mount_type != MountType::Network {
+            profile.max_threads = self.parallel_config.max_threads;
+            profile.probe_timeout_secs = self.parallel_config.probe_timeout_secs;
+        }
+        root_folder.scan_options.apply_to(&mut profile);
+        self.scan_profile = profile;
+        self.root_folders.insert(root_id.to_string(), root_folder.clone());
+
+        let collector = CoverCollector::new(cover_manager);
+        let parallel = root_folder.scan_options.parallel.unwrap_or(self.parallel_config.enabled);
+        let tree = if parallel {
+            self.build_tree_parallel_with_profile(&root_folder, &collector)?
+        } else {
+            self.build_tree_recursive(&root_folder.path, 0, root_folder.max_depth, &collector)?
+        };
+
+        let covers = collector.into_covers();
+        cover_manager.remove_covers_under(&root_path);
+        cover_manager.index_covers(covers);
+
+        self.set_directory_tree(root_id, tree.clone());
+        self.update_scan_time(root_id);
+        
+        Ok(tree)
+    }
+
+    /// 按当前配置档并行构建目录树：配置档限制线程数时使用独立的线程池
+    fn build_tree_parallel_with_profile(&self, root_folder: &RootFolder, collector: &CoverCollector) -> Result<DirectoryNode, Box<dyn std::error::Error>> {
+        if self.scan_profile.max_threads == 0 {
             // 日志：并行扫描信息
             println!(
                 "[FolderManager] 并行扫描已启用，rayon 线程池大小: {}，根目录: {}",
                 rayon::current_num_threads(),
                 root_folder.path.display()
             );
-            self.build_tree_recursive_parallel(&root_folder.path, 0, root_folder.max_depth)?
-        } else {
-            self.build_tree_recursive(&root_folder.path, 0, root_folder.max_depth)?
-        };
-        
-        self.directory_trees.insert(root_id.to_string(), tree.clone());
-        
-        Ok(tree)
+            return self.build_tree_recursive_parallel(&root_folder.path, 0, root_folder.max_depth, collector);
+        }
+
+        println!(
+            "[FolderManager] 使用独立线程池扫描（{}），线程数: {}，根目录: {}",
+            root_folder.fs_type.as_deref().unwrap_or("未知"),
+            self.scan_profile.max_threads,
+            root_folder.path.display()
+        );
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.scan_profile.max_threads)
+            .build()?;
+        pool.install(|| {
+            self.build_tree_recursive_parallel(&root_folder.path, 0, root_folder.max_depth, collector)
+                .map_err(|e| e.to_string())
+        })
+        .map_err(Into::into)
     }
 
     /// 并行构建目录树
@@ -252,6 +928,7 @@ impl FolderManager {
         path: &PathBuf,
         current_depth: i32,
         max_depth: i32,
+        collector: &CoverCollector,
     ) -> Result<DirectoryNode, Box<dyn std::error::Error>> {
         // 安全检查：防止无限递归
         if current_depth > self.parallel_config.max_depth {
@@ -267,30 +944,32 @@ impl FolderManager {
         let entries = self.scan_directory_entries(path)?;
         
         // 分离文件和目录
-        let (files, subdirs): (Vec<_>, Vec<_>) = entries
+        let (files, mut subdirs): (Vec<_>, Vec<_>) = entries
             .into_iter()
             .partition(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false));
+        // 嵌套的根文件夹由其自身扫描
+        subdirs.retain(|entry| !self.is_root_path(&entry.path()));
 
         // 并行处理视频文件
-        let videos = self.process_video_files_parallel(&files);
+        let mut videos = self.process_video_files_parallel(&files);
+        collation::sort_videos(&mut videos);
         let video_count = videos.len();
 
-        // 并行扫描子目录
-        let children = self.scan_subdirectories_parallel(&subdirs, current_depth + 1, max_depth);
+        // 当前目录中的图片文件
+        let mut images: Vec<PathBuf> = files
+            .iter()
+            .map(|entry| entry.path())
+            .filter(|file_path| collector.cover_manager.is_cover_file(file_path))
+            .collect();
+        images.sort();
 
-        // 计算总的视频和封面数量
+        // 并行扫描子目录，完成顺序不确定，统一按名称排序
+        let mut children = self.scan_subdirectories_parallel(&subdirs, current_depth + 1, max_depth, collector);
+        collation::sort_nodes(&mut children);
+
+        // 计算总的视频和封面数量，确定当前目录的封面路径
         let total_video_count = video_count + children.iter().map(|c| c.video_count).sum::<usize>();
-        let total_cover_count = children.iter().map(|c| c.cover_count).sum::<usize>();
-
-        // 确定当前目录的封面路径
-        let cover_path = if !videos.is_empty() {
-            crate::cover::CoverManager::new().find_cover_for_video(&videos[0].path)
-                .map(|cover_info| cover_info.path.clone())
-        } else if !children.is_empty() {
-            self.find_first_cover_in_children(&children)
-        } else {
-            None
-        };
+        let (total_cover_count, cover_path) = self.collect_covers(&images, &videos, &children, collector);
 
         // 获取目录的修改时间
         let modified_time = std::fs::metadata(path)
@@ -308,11 +987,33 @@ impl FolderManager {
         ))
     }
 
+    /// 按当前配置档创建视频处理器
+    fn video_processor(&self) -> VideoProcessor {
+        VideoProcessor::with_probe_options(self.scan_profile.probe_options())
+    }
+
+    /// 读取目录，遇到可重试的 I/O 错误时按配置档退避重试
+    fn read_dir_with_backoff(&self, path: &std::path::Path) -> std::io::Result<std::fs::ReadDir> {
+        let mut delay = std::time::Duration::from_millis(self.scan_profile.retry_backoff_ms);
+        let mut attempt = 0;
+        loop {
+            match std::fs::read_dir(path) {
+                Err(e) if attempt < self.scan_profile.io_retries && is_transient_io_error(&e) => {
+                    println!("读取目录失败，{}ms 后重试: {} ({})", delay.as_millis(), path.display(), e);
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
     /// 扫描目录条目
     fn scan_directory_entries(&self, path: &PathBuf) -> Result<Vec<std::fs::DirEntry>, Box<dyn std::error::Error>> {
         let mut entries = Vec::new();
         
-        match std::fs::read_dir(path) {
+        match self.read_dir_with_backoff(path) {
             Ok(read_dir) => {
                 for entry in read_dir {
                     match entry {
@@ -392,21 +1093,14 @@ impl FolderManager {
 
     /// 并行处理视频文件
     fn process_video_files_parallel(&self, files: &[std::fs::DirEntry]) -> Vec<VideoInfo> {
-        let video_extensions = ["mp4", "avi", "mov", "mkv", "wmv", "flv", "webm"];
-        
         files
             .par_iter()
             .filter_map(|entry| {
                 let path = entry.path();
-                if let Some(extension) = path.extension() {
-                    let ext_str = extension.to_string_lossy().to_lowercase();
-                    if video_extensions.contains(&ext_str.as_str()) {
-                        crate::video::VideoProcessor::new()
-                            .create_video_info(path)
-                            .ok()
-                    } else {
-                        None
-                    }
+                if self.is_video_file(&path) {
+                    self.video_processor()
+                        .create_video_info(path)
+                        .ok()
                 } else {
                     None
                 }
@@ -414,12 +1108,20 @@ impl FolderManager {
             .collect()
     }
 
+    /// 按配置的扩展名判断是否为视频文件
+    fn is_video_file(&self, path: &std::path::Path) -> bool {
+        path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .is_some_and(|ext| self.parallel_config.video_extensions.contains(&ext))
+    }
+
     /// 并行扫描子目录
     fn scan_subdirectories_parallel(
         &self,
         subdirs: &[std::fs::DirEntry],
         current_depth: i32,
         max_depth: i32,
+        collector: &CoverCollector,
     ) -> Vec<DirectoryNode> {
         let results: Arc<Mutex<Vec<DirectoryNode>>> = Arc::new(Mutex::new(Vec::new()));
         
@@ -441,7 +1143,7 @@ impl FolderManager {
                             thread.name().unwrap_or("unnamed")
                         );
                     }
-                    if let Ok(child_node) = self.build_tree_recursive_parallel(&subdir_path, current_depth, max_depth) {
+                    if let Ok(child_node) = self.build_tree_recursive_parallel(&subdir_path, current_depth, max_depth, collector) {
                         if let Ok(mut results) = results.lock() {
                             results.push(child_node);
                         }
@@ -473,9 +1175,10 @@ impl FolderManager {
         path: &PathBuf,
         current_depth: i32,
         max_depth: i32,
+        collector: &CoverCollector,
     ) -> Result<DirectoryNode, Box<dyn std::error::Error>> {
         // 安全检查：防止无限递归
-        if current_depth > 100 {
+        if current_depth > self.parallel_config.max_depth {
             // 获取目录的修改时间
             let modified_time = std::fs::metadata(path)
                 .ok()
@@ -522,11 +1225,11 @@ impl FolderManager {
 
         let mut children = Vec::new();
         let mut videos = Vec::new();
-        let mut cover_count = 0;
+        let mut images = Vec::new();
         let mut video_count = 0;
 
         // 读取目录内容
-        let entries_result = std::fs::read_dir(path);
+        let entries_result = self.read_dir_with_backoff(path);
         match entries_result {
             Ok(entries) => {
                 for entry in entries {
@@ -536,12 +1239,16 @@ impl FolderManager {
                             let file_type = entry.file_type();
                             
                             if file_type.as_ref().map(|t| t.is_dir()).unwrap_or_else(|_| entry_path.is_dir()) {
+                                // 嵌套的根文件夹由其自身扫描
+                                if self.is_root_path(&entry_path) {
+                                    continue;
+                                }
                                 // 递归构建子目录
-                                match self.build_tree_recursive(&entry_path, current_depth + 1, max_depth) {
+                                match self.build_tree_recursive(&entry_path, current_depth + 1, max_depth, collector) {
                                     Ok(child_node) => {
                                         children.push(child_node);
                                     }
-                                    Err(e) => {
+                                    Err(_) => {
                                         // 即使子目录失败，也创建一个空的目录节点，避免完全跳过
                                         // 获取目录的修改时间
                                         let modified_time = std::fs::metadata(&entry_path)
@@ -566,30 +1273,28 @@ impl FolderManager {
                                     }
                                 }
                             } else if file_type.as_ref().map(|t| t.is_file()).unwrap_or_else(|_| entry_path.is_file()) {
+                                // 图片文件留待汇总封面
+                                if collector.cover_manager.is_cover_file(&entry_path) {
+                                    images.push(entry_path);
+                                    continue;
+                                }
+
                                 // 检查是否为视频文件
-                                if let Some(extension) = entry_path.extension() {
-                                    let ext_str = extension.to_string_lossy().to_lowercase();
-                                    if ["mp4", "avi", "mov", "mkv", "wmv", "flv", "webm"].contains(&ext_str.as_str()) {
-                                        // 创建视频信息
-                                        match crate::video::VideoProcessor::new().create_video_info(entry_path.clone()) {
-                                            Ok(video_info) => {
-                                                videos.push(video_info);
-                                                video_count += 1;
-                                                
-                                                // 检查是否有对应的封面文件
-                                                if let Some(_cover_path) = crate::cover::CoverManager::new().find_cover_for_video(&entry_path) {
-                                                    cover_count += 1;
-                                                }
-                                            }
-                                            Err(e) => {
-                                                // 静默处理错误
-                                            }
+                                if self.is_video_file(&entry_path) {
+                                    // 创建视频信息
+                                    match self.video_processor().create_video_info(entry_path.clone()) {
+                                        Ok(video_info) => {
+                                            videos.push(video_info);
+                                            video_count += 1;
+                                        }
+                                        Err(_) => {
+                                            // 静默处理错误
                                         }
                                     }
                                 }
                             }
                         }
-                        Err(e) => {
+                        Err(_) => {
                             // 尝试使用系统命令读取目录
                             use std::process::Command;
                             
@@ -614,8 +1319,8 @@ impl FolderManager {
                                     
                                     let mut line_count = 0;
                                     let max_lines = 10000; // 限制最大行数，防止内存溢出
-                                    let max_videos = 1000; // 限制最大视频数量
-                                    let max_children = 1000; // 限制最大子目录数量
+                                    let max_videos = self.parallel_config.fallback_max_videos; // 限制最大视频数量
+                                    let max_children = self.parallel_config.fallback_max_children; // 限制最大子目录数量
                                     
                                     for line in output_str.lines().skip(1) { // 跳过第一行 "total ..."
                                         line_count += 1;
@@ -630,17 +1335,14 @@ impl FolderManager {
                                             if parts.len() >= 9 { // ls -la 输出至少有9列
                                                 let filename = parts[8..].join(" "); // 第9列开始是文件名
                                                 
-                                                if filename.contains('.') {
-                                                    let ext = filename.split('.').last().unwrap_or("").to_lowercase();
-                                                    if ["mp4", "avi", "mov", "mkv", "wmv", "flv", "webm"].contains(&ext.as_str()) {
-                                                        if fallback_videos.len() >= max_videos {
-                                                            continue;
-                                                        }
-                                                        let file_path = path.join(&filename);
-                                                        if let Ok(video_info) = crate::video::VideoProcessor::new().create_video_info(file_path) {
-                                                            fallback_videos.push(video_info);
-                                                            video_count += 1;
-                                                        }
+                                                let file_path = path.join(&filename);
+                                                if self.is_video_file(&file_path) {
+                                                    if fallback_videos.len() >= max_videos {
+                                                        continue;
+                                                    }
+                                                    if let Ok(video_info) = self.video_processor().create_video_info(file_path) {
+                                                        fallback_videos.push(video_info);
+                                                        video_count += 1;
                                                     }
                                                 }
                                             }
@@ -655,7 +1357,7 @@ impl FolderManager {
                                                     }
                                                     let dir_path = path.join(&dirname);
                                                     // 递归构建子目录
-                                                    if let Ok(child_node) = self.build_tree_recursive(&dir_path, current_depth + 1, max_depth) {
+                                                    if let Ok(child_node) = self.build_tree_recursive(&dir_path, current_depth + 1, max_depth, collector) {
                                                         fallback_children.push(child_node);
                                                     }
                                                 }
@@ -666,9 +1368,6 @@ impl FolderManager {
                                     // 使用备用方案的结果
                                     children.extend(fallback_children);
                                     videos.extend(fallback_videos);
-                                } else {
-                                    let error_str = String::from_utf8_lossy(&output.stderr);
-                                    // 静默处理错误
                                 }
                             } else {
                                 // 静默处理错误
@@ -727,21 +1426,14 @@ impl FolderManager {
             }
         }
 
-        // 计算总的视频和封面数量（包括子目录）
+        // 目录读取顺序与文件系统有关，统一按名称排序
+        collation::sort_nodes(&mut children);
+        collation::sort_videos(&mut videos);
+        images.sort();
+
+        // 计算总的视频和封面数量（包括子目录），确定当前目录的封面路径
         let total_video_count = video_count + children.iter().map(|c| c.video_count).sum::<usize>();
-        let total_cover_count = cover_count + children.iter().map(|c| c.cover_count).sum::<usize>();
-
-        // 确定当前目录的封面路径
-        let cover_path = if !videos.is_empty() {
-            // 如果当前目录有视频，使用第一个视频的封面
-            crate::cover::CoverManager::new().find_cover_for_video(&videos[0].path)
-                .map(|cover_info| cover_info.path.clone())
-        } else if !children.is_empty() {
-            // 否则递归查找子目录中的第一个封面
-            self.find_first_cover_in_children(&children)
-        } else {
-            None
-        };
+        let (total_cover_count, cover_path) = self.collect_covers(&images, &videos, &children, collector);
 
         // 获取目录的修改时间
         let modified_time = std::fs::metadata(path)
@@ -764,6 +1456,43 @@ impl FolderManager {
         })
     }
 
+    /// 收集当前目录的封面，返回（含子目录的封面总数，代表性封面路径）。
+    /// 代表性封面优先级：目录封面（cover/folder/poster）→ 第一个有封面的视频 → 子目录中的第一个封面
+    fn collect_covers(
+        &self,
+        images: &[PathBuf],
+        videos: &[VideoInfo],
+        children: &[DirectoryNode],
+        collector: &CoverCollector,
+    ) -> (usize, Option<PathBuf>) {
+        let video_paths: Vec<PathBuf> = videos.iter().map(|v| v.path.clone()).collect();
+        let covers = collector.cover_manager.collect_directory_covers(images, &video_paths);
+
+        let folder_cover = covers
+            .iter()
+            .find(|cover| collector.cover_manager.is_folder_cover(&cover.path))
+            .map(|cover| cover.path.clone());
+        let video_cover = || {
+            videos.iter().find_map(|video| {
+                covers
+                    .iter()
+                    .find(|cover| cover.associated_video.as_ref() == Some(&video.path))
+                    .map(|cover| cover.path.clone())
+            })
+        };
+        let cover_path = folder_cover
+            .or_else(video_cover)
+            .or_else(|| self.find_first_cover_in_children(children));
+
+        let total_cover_count = covers.len() + children.iter().map(|c| c.cover_count).sum::<usize>();
+
+        if let Ok(mut collected) = collector.covers.lock() {
+            collected.extend(covers);
+        }
+
+        (total_cover_count, cover_path)
+    }
+
     /// 在子目录中查找第一个封面
     fn find_first_cover_in_children(&self, children: &[DirectoryNode]) -> Option<PathBuf> {
         // 按顺序遍历子目录，找到第一个有封面的
@@ -790,22 +1519,24 @@ impl FolderManager {
     /// 清除目录树缓存
     pub fn clear_directory_tree(&mut self, root_id: &str) {
         self.directory_trees.remove(root_id);
+        self.library_revision += 1;
         // 已清除根文件夹的目录树缓存
     }
 
     /// 清除目录树缓存
     pub fn clear_cache(&mut self) {
         self.directory_trees.clear();
+        self.library_revision += 1;
     }
 
     /// 更新最后扫描时间
     pub fn update_scan_time(&mut self, root_id: &str) -> bool {
-        if let Some(folder) = self.root_folders.get_mut(root_id) {
-            folder.last_scan = Some(Utc::now());
-            true
-        } else {
-            false
-        }
+        self.update_root(root_id, |folder| folder.last_scan = Some(Utc::now()))
+    }
+
+    /// 应用全局扫描设置（对之后的扫描生效）
+    pub fn apply_scan_settings(&mut self, settings: &ScanSettings) {
+        self.parallel_config = ParallelScanConfig::from(settings);
     }
 
     /// 获取并行扫描配置
@@ -843,7 +1574,7 @@ impl FolderManager {
     }
 
     /// 强制使用并行扫描构建目录树
-    pub fn build_directory_tree_parallel(&mut self, root_id: &str) -> Result<DirectoryNode, Box<dyn std::error::Error>> {
+    pub fn build_directory_tree_parallel(&mut self, root_id: &str, cover_manager: &mut CoverManager) -> Result<DirectoryNode, Box<dyn std::error::Error>> {
         let root_folder = self.get_root_folder(root_id)
             .ok_or("根文件夹不存在")?;
 
@@ -851,8 +1582,17 @@ impl FolderManager {
             return Err("根文件夹已禁用".into());
         }
 
-        let tree = self.build_tree_recursive_parallel(&root_folder.path, 0, root_folder.max_depth)?;
+        let root_path = root_folder.path.clone();
+        let collector = CoverCollector::new(cover_manager);
+        let tree = self.build_tree_recursive_parallel(&root_folder.path, 0, root_folder.max_depth, &collector)?;
+
+        let covers = collector.into_covers();
+        cover_manager.remove_covers_under(&root_path);
+        cover_manager.index_covers(covers);
+
         self.directory_trees.insert(root_id.to_string(), tree.clone());
+        self.library_revision += 1;
+        self.update_scan_time(root_id);
         
         Ok(tree)
     }
@@ -875,6 +1615,140 @@ mod tests {
         assert_eq!(folder.unwrap().name, "Test Folder");
     }
 
+    #[test]
+    fn test_storage_path_includes_location_on_volume() {
+        let mut manager = FolderManager::new();
+        let id = manager.add_root_folder(PathBuf::from("/media/disk/shows"), None);
+        let root = manager.get_root_folder(&id).unwrap().clone();
+        // 没有卷标识时按根文件夹 ID 存储，路径相对根目录
+        assert_eq!(root.storage_key(), format!("root:{}", id));
+        assert_eq!(root.storage_path(&PathBuf::from("/media/disk/shows/a.mkv")).unwrap(), "a.mkv");
+
+        manager.set_volume_key(&id, "uuid:disk".to_string(), "shows".to_string());
+        let root = manager.get_root_folder(&id).unwrap();
+        assert_eq!(root.storage_key(), "uuid:disk");
+        assert_eq!(root.storage_path(&PathBuf::from("/media/disk/shows/s1/a.mkv")).unwrap(), "shows/s1/a.mkv");
+        assert_eq!(root.storage_path(&PathBuf::from("/media/disk/shows")).unwrap(), "shows");
+        assert_eq!(root.resolve_storage_path("shows/s1/a.mkv"), Some(PathBuf::from("/media/disk/shows/s1/a.mkv")));
+        assert_eq!(root.resolve_storage_path("movies/a.mkv"), None);
+    }
+
+    #[test]
+    fn test_resolve_video_path_stays_inside_root() {
+        let dir = std::env::temp_dir().join(format!("vv-folder-test-{}", uuid::Uuid::new_v4()));
+        let root_path = dir.join("movies");
+        std::fs::create_dir_all(root_path.join("series")).unwrap();
+        std::fs::write(root_path.join("series").join("ep1.mkv"), b"").unwrap();
+        std::fs::write(dir.join("secret.mkv"), b"").unwrap();
+        let mut manager = FolderManager::new();
+        let id = manager.add_root_folder(root_path.clone(), None);
+
+        assert_eq!(manager.resolve_video_path(&id, "series/ep1.mkv").unwrap(), root_path.join("series").join("ep1.mkv"));
+        for invalid in ["", "../secret.mkv", "series/../../secret.mkv", "./series/ep1.mkv", "series//ep1.mkv", "series", "missing.mkv"] {
+            assert!(manager.resolve_video_path(&id, invalid).is_err(), "{}", invalid);
+        }
+        assert!(manager.resolve_video_path("missing", "series/ep1.mkv").is_err());
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.join("secret.mkv"), root_path.join("link.mkv")).unwrap();
+            assert!(manager.resolve_video_path(&id, "link.mkv").is_err());
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_directory_node_find_and_all_videos() {
+        let manager = FolderManager::new();
+        let video = crate::test_support::video;
+        let child = manager.create_directory_node(
+            &PathBuf::from("/lib/a"), Vec::new(), vec![video("/lib/a/2.mp4")], 0, 1, None, None,
+        );
+        let root = manager.create_directory_node(
+            &PathBuf::from("/lib"), vec![child], vec![video("/lib/1.mp4")], 0, 2, None, None,
+        );
+
+        assert_eq!(root.find("/lib/a").map(|n| n.name.as_str()), Some("a"));
+        assert!(root.find("/other").is_none());
+        assert_eq!(root.all_videos().len(), 2);
+        assert_eq!(root.find("/lib/a").unwrap().all_videos().len(), 1);
+    }
+
+    #[test]
+    fn test_detect_and_resolve_overlap() {
+        let base = std::env::temp_dir().join(format!("vv-overlap-test-{}", uuid::Uuid::new_v4()));
+        let nested = base.join("anime");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let mut manager = FolderManager::new();
+        let outer = manager.add_root_folder(base.clone(), None);
+        assert_eq!(manager.detect_overlap(&base), Some(RootOverlap::SamePath { root_id: outer.clone() }));
+
+        let overlap = manager.detect_overlap(&nested).unwrap();
+        assert_eq!(overlap, RootOverlap::InsideExisting { root_id: outer.clone() });
+        assert!(manager.resolve_overlap(&nested, overlap.clone(), None).is_err());
+        assert_eq!(manager.resolve_overlap(&nested, overlap, Some(OverlapMode::Merge)), Ok(Some(outer.clone())));
+
+        manager.remove_root_folder(&outer);
+        let inner = manager.add_root_folder(nested.clone(), None);
+        let overlap = manager.detect_overlap(&base).unwrap();
+        assert_eq!(overlap, RootOverlap::ContainsExisting { root_ids: vec![inner.clone()] });
+        assert_eq!(manager.resolve_overlap(&base, overlap, Some(OverlapMode::Merge)), Ok(None));
+        assert!(manager.get_root_folder(&inner).is_none());
+
+        // 同一路径上挂载的是另一块硬盘时，旧根文件夹不算重叠
+        std::fs::write(base.join(crate::volume::VOLUME_ID_FILE), "disk-b").unwrap();
+        let other = manager.add_root_folder(base.clone(), None);
+        let revision = manager.library_revision();
+        manager.set_volume_key(&other, "custom:disk-a".to_string(), String::new());
+        manager.rename_root_folder(&other, "Disk A".to_string());
+        assert_eq!(manager.library_revision(), revision + 2);
+        assert_eq!(manager.detect_overlap(&nested), None);
+        manager.set_volume_key(&other, "custom:disk-b".to_string(), String::new());
+        assert_eq!(manager.detect_overlap(&nested), Some(RootOverlap::InsideExisting { root_id: other.clone() }));
+        manager.remove_root_folder(&other);
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn test_directory_node_remove_subtree() {
+        let manager = FolderManager::new();
+        let leaf = manager.create_directory_node(&PathBuf::from("/lib/a/b"), Vec::new(), Vec::new(), 1, 3, None, None);
+        let child = manager.create_directory_node(&PathBuf::from("/lib/a"), vec![leaf], Vec::new(), 2, 4, None, None);
+        let mut root = manager.create_directory_node(&PathBuf::from("/lib"), vec![child], Vec::new(), 2, 5, None, None);
+
+        assert!(root.remove_subtree("/lib/a/b"));
+        assert_eq!(root.video_count, 2);
+        assert_eq!(root.cover_count, 1);
+        assert_eq!(root.find("/lib/a").unwrap().video_count, 1);
+        assert!(!root.remove_subtree("/lib/missing"));
+    }
+
+    #[test]
+    fn test_root_settings_persisted() {
+        let data_dir = std::env::temp_dir().join(format!("vv-roots-test-{}", uuid::Uuid::new_v4()));
+        let mut manager = FolderManager::with_data_dir(data_dir.clone());
+        let id = manager.add_root_folder(std::env::temp_dir(), None);
+        assert!(manager.rename_root_folder(&id, "Movies".to_string()));
+        assert!(manager.set_max_depth(&id, 3));
+        assert!(manager.set_scan_options(&id, RootScanOptions { parallel: Some(false), ..Default::default() }));
+        assert!(manager.update_scan_time(&id));
+
+        let reloaded = FolderManager::with_data_dir(data_dir.clone());
+        let root = reloaded.get_root_folder(&id).unwrap();
+        assert_eq!(root.name, "Movies");
+        assert_eq!(root.max_depth, 3);
+        assert_eq!(root.scan_options.parallel, Some(false));
+        assert!(root.last_scan.is_some());
+
+        let mut reloaded = reloaded;
+        assert!(reloaded.remove_root_folder(&id));
+        assert!(FolderManager::with_data_dir(data_dir.clone()).get_root_folder(&id).is_none());
+
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+
     #[test]
     fn test_remove_root_folder() {
         let mut manager = FolderManager::new();
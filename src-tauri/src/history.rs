@@ -0,0 +1,356 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use crate::persist;
+use crate::video::VideoInfo;
+
+/// 观看记录文件名（位于应用数据目录）
+const HISTORY_FILE: &str = "history.json";
+/// 每个视频保留的最近播放次数
+const MAX_PLAYS_PER_VIDEO: usize = 20;
+/// 播放进度达到时长的该比例即视为已看完
+const WATCHED_RATIO: f64 = 0.9;
+/// 剩余时间不超过该秒数也视为已看完（片尾字幕）
+const WATCHED_REMAINING_SECS: f64 = 120.0;
+/// 播放进度小于该秒数时不记录续播位置
+const MIN_RESUME_SECS: f64 = 10.0;
+/// 播放进度只保存在内存中，距上次写入超过该间隔才写入磁盘
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 一次播放
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayEvent {
+    pub played_at: DateTime<Utc>,
+    /// 实际观看的秒数（播放器回报时才有）
+    #[serde(default)]
+    pub watched_secs: Option<f64>,
+}
+
+/// 视频的观看状态
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchState {
+    /// 是否已看完
+    pub watched: bool,
+    /// 续播位置（秒），None 表示从头播放
+    pub position_secs: Option<f64>,
+    /// 播放器回报的时长（秒）
+    pub duration_secs: Option<f64>,
+    pub last_played_at: Option<DateTime<Utc>>,
+    pub play_count: u32,
+    /// 最近的播放记录（新的在后）
+    pub plays: Vec<PlayEvent>,
+}
+
+impl WatchState {
+    fn is_empty(&self) -> bool {
+        !self.watched && self.position_secs.is_none() && self.plays.is_empty()
+    }
+
+    /// 更新播放进度：接近结尾时标记为已看完并清除续播位置
+    fn set_progress(&mut self, position_secs: f64, duration_secs: Option<f64>) {
+        if let Some(duration) = duration_secs.filter(|duration| *duration > 0.0) {
+            self.duration_secs = Some(duration);
+        }
+        let finished = self
+            .duration_secs
+            .is_some_and(|duration| position_secs >= duration * WATCHED_RATIO || duration - position_secs <= WATCHED_REMAINING_SECS);
+        if finished {
+            self.watched = true;
+            self.position_secs = None;
+        } else {
+            self.position_secs = (position_secs >= MIN_RESUME_SECS).then_some(position_secs);
+        }
+    }
+}
+
+/// 保存的条目：记录文件指纹，文件改名或移动后可以据此找回
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HistoryEntry {
+    #[serde(default)]
+    fingerprint: Option<String>,
+    #[serde(flatten)]
+    state: WatchState,
+}
+
+/// 最近播放、继续观看列表中的视频
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryVideo {
+    pub path: PathBuf,
+    pub root_id: String,
+    /// 目录树中的视频信息（尚未扫描或已删除时为 None）
+    pub video: Option<VideoInfo>,
+    pub state: WatchState,
+}
+
+/// 观看记录中的一项（存储键，相对路径，观看状态）
+pub type HistoryItem<'a> = (&'a str, &'a str, &'a WatchState);
+
+/// 观看记录与续播位置存储，按卷标识 + 相对路径 + 文件指纹识别视频
+pub struct HistoryStore {
+    path: PathBuf,
+    /// 存储键（卷标识）→ 相对路径 → 条目
+    entries: BTreeMap<String, BTreeMap<String, HistoryEntry>>,
+    /// 已看标记变化时递增，用于判断智能合集等派生数据是否过期（播放进度变化不影响）
+    revision: u64,
+    /// 内存中有尚未写入磁盘的修改
+    dirty: bool,
+    last_saved: Instant,
+}
+
+impl HistoryStore {
+    /// 从应用数据目录加载
+    pub fn load(data_dir: &Path) -> Self {
+        let path = data_dir.join(HISTORY_FILE);
+        let entries = persist::read_json(&path).unwrap_or_else(|e| {
+            println!("加载观看记录失败: {}", e);
+            BTreeMap::new()
+        });
+        Self {
+            path,
+            entries,
+            revision: 0,
+            dirty: false,
+            last_saved: Instant::now(),
+        }
+    }
+
+    fn save(&mut self) -> Result<(), String> {
+        persist::write_json(&self.path, &self.entries)?;
+        self.dirty = false;
+        self.last_saved = Instant::now();
+        Ok(())
+    }
+
+    /// 将内存中的播放进度写入磁盘（播放结束或退出时调用）
+    pub fn flush(&mut self) -> Result<(), String> {
+        if self.dirty {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// 修改计数
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// 按路径获取
+    pub fn get(&self, storage_key: &str, relative_path: &str) -> Option<&WatchState> {
+        self.entries
+            .get(storage_key)
+            .and_then(|paths| paths.get(relative_path))
+            .map(|entry| &entry.state)
+    }
+
+    /// 按文件指纹查找条目所在的位置（存储键，相对路径）
+    pub fn find_by_fingerprint(&self, fingerprint: &str) -> Option<(String, String)> {
+        self.entries.iter().find_map(|(storage_key, paths)| {
+            paths
+                .iter()
+                .find(|(_, entry)| entry.fingerprint.as_deref() == Some(fingerprint))
+                .map(|(path, _)| (storage_key.clone(), path.clone()))
+        })
+    }
+
+    /// 将条目移动到新位置（文件改名或移动后）
+    pub fn relocate(&mut self, from: (&str, &str), to: (&str, &str)) -> Result<bool, String> {
+        let Some(entry) = self.entries.get_mut(from.0).and_then(|paths| paths.remove(from.1)) else {
+            return Ok(false);
+        };
+        self.entries.retain(|_, paths| !paths.is_empty());
+        self.entries.entry(to.0.to_string()).or_default().insert(to.1.to_string(), entry);
+        self.revision += 1;
+        self.save()?;
+        Ok(true)
+    }
+
+    /// 在内存中修改视频的观看状态（不写入磁盘）；状态全部清空时删除条目，已看标记变化时递增修改计数
+    fn update(
+        &mut self,
+        storage_key: &str,
+        relative_path: &str,
+        fingerprint: Option<String>,
+        edit: impl FnOnce(&mut WatchState),
+    ) -> WatchState {
+        let paths = self.entries.entry(storage_key.to_string()).or_default();
+        let mut entry = paths.remove(relative_path).unwrap_or(HistoryEntry {
+            fingerprint: None,
+            state: WatchState::default(),
+        });
+        let was_watched = entry.state.watched;
+        edit(&mut entry.state);
+        let state = entry.state.clone();
+        if fingerprint.is_some() {
+            entry.fingerprint = fingerprint;
+        }
+        if state.is_empty() {
+            if paths.is_empty() {
+                self.entries.remove(storage_key);
+            }
+        } else {
+            paths.insert(relative_path.to_string(), entry);
+        }
+        if state.watched != was_watched {
+            self.revision += 1;
+        }
+        self.dirty = true;
+        state
+    }
+
+    /// 记录一次播放
+    pub fn record_play(&mut self, storage_key: &str, relative_path: &str, fingerprint: Option<String>) -> Result<WatchState, String> {
+        let state = self.update(storage_key, relative_path, fingerprint, |state| {
+            let now = Utc::now();
+            state.last_played_at = Some(now);
+            state.play_count += 1;
+            state.plays.push(PlayEvent {
+                played_at: now,
+                watched_secs: None,
+            });
+            if state.plays.len() > MAX_PLAYS_PER_VIDEO {
+                state.plays.remove(0);
+            }
+        });
+        self.save()?;
+        Ok(state)
+    }
+
+    /// 播放器回报的播放进度：更新续播位置，并把观看秒数记到最近一次播放上。
+    /// 进度只更新内存，已看标记变化或距上次写入超过 [`FLUSH_INTERVAL`] 时才写入磁盘
+    pub fn report_progress(
+        &mut self,
+        storage_key: &str,
+        relative_path: &str,
+        position_secs: f64,
+        duration_secs: Option<f64>,
+        watched_secs: Option<f64>,
+    ) -> Result<WatchState, String> {
+        if !position_secs.is_finite() || position_secs < 0.0 {
+            return Err("播放位置无效".to_string());
+        }
+        let revision = self.revision;
+        let state = self.update(storage_key, relative_path, None, |state| {
+            state.set_progress(position_secs, duration_secs);
+            if let (Some(play), Some(watched_secs)) = (state.plays.last_mut(), watched_secs) {
+                play.watched_secs = Some(watched_secs.max(0.0));
+            }
+        });
+        if self.revision != revision || self.last_saved.elapsed() >= FLUSH_INTERVAL {
+            self.save()?;
+        }
+        Ok(state)
+    }
+
+    /// 手动标记为已看或未看，同时清除续播位置；按（存储键 → 相对路径）批量修改，只写入一次
+    pub fn set_watched(&mut self, groups: &BTreeMap<String, Vec<String>>, watched: bool) -> Result<(), String> {
+        for (storage_key, relative_paths) in groups {
+            for relative_path in relative_paths {
+                self.update(storage_key, relative_path, None, |state| {
+                    state.watched = watched;
+                    state.position_secs = None;
+                });
+            }
+        }
+        self.save()
+    }
+
+    /// 清空全部观看记录（保留已看标记）
+    pub fn clear_plays(&mut self) -> Result<(), String> {
+        for paths in self.entries.values_mut() {
+            for entry in paths.values_mut() {
+                entry.state.plays.clear();
+                entry.state.play_count = 0;
+                entry.state.last_played_at = None;
+                entry.state.position_secs = None;
+            }
+            paths.retain(|_, entry| !entry.state.is_empty());
+        }
+        self.entries.retain(|_, paths| !paths.is_empty());
+        self.save()
+    }
+
+    fn items(&self) -> impl Iterator<Item = HistoryItem<'_>> {
+        self.entries.iter().flat_map(|(storage_key, paths)| {
+            paths
+                .iter()
+                .map(move |(relative_path, entry)| (storage_key.as_str(), relative_path.as_str(), &entry.state))
+        })
+    }
+
+    /// 最近播放过的视频，按最后播放时间从新到旧
+    pub fn recently_played(&self) -> Vec<HistoryItem<'_>> {
+        let mut items: Vec<_> = self.items().filter(|(_, _, state)| state.last_played_at.is_some()).collect();
+        items.sort_by_key(|(_, _, state)| std::cmp::Reverse(state.last_played_at));
+        items
+    }
+
+    /// 可以继续观看的视频（有续播位置，包括重看中的已看视频），按最后播放时间从新到旧
+    pub fn continue_watching(&self) -> Vec<HistoryItem<'_>> {
+        let mut items: Vec<_> = self.items().filter(|(_, _, state)| state.position_secs.is_some()).collect();
+        items.sort_by_key(|(_, _, state)| std::cmp::Reverse(state.last_played_at));
+        items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_progress_and_lists() {
+        let dir = std::env::temp_dir().join(format!("vv-history-test-{}", uuid::Uuid::new_v4()));
+        let mut store = HistoryStore::load(&dir);
+
+        store.record_play("custom:abc", "a.mkv", Some("fp-a".to_string())).unwrap();
+        let state = store.report_progress("custom:abc", "a.mkv", 600.0, Some(3600.0), Some(590.0)).unwrap();
+        assert_eq!(state.position_secs, Some(600.0));
+        assert!(!state.watched);
+        // 播放进度只在内存中更新，也不影响修改计数
+        assert_eq!(store.revision(), 0);
+        assert!(HistoryStore::load(&dir).get("custom:abc", "a.mkv").unwrap().position_secs.is_none());
+        store.flush().unwrap();
+        assert_eq!(HistoryStore::load(&dir).get("custom:abc", "a.mkv").unwrap().position_secs, Some(600.0));
+        assert_eq!(state.plays[0].watched_secs, Some(590.0));
+        assert!(store.report_progress("custom:abc", "a.mkv", f64::NAN, None, None).is_err());
+
+        store.record_play("custom:abc", "b.mkv", None).unwrap();
+        // 只剩片尾时视为已看完
+        let state = store.report_progress("custom:abc", "b.mkv", 1700.0, Some(1800.0), None).unwrap();
+        assert!(state.watched);
+        assert_eq!(state.position_secs, None);
+        assert_eq!(store.revision(), 1);
+
+        let recent: Vec<&str> = store.recently_played().iter().map(|(_, path, _)| *path).collect();
+        assert_eq!(recent, vec!["b.mkv", "a.mkv"]);
+        let continuing: Vec<&str> = store.continue_watching().iter().map(|(_, path, _)| *path).collect();
+        assert_eq!(continuing, vec!["a.mkv"]);
+
+        // 文件改名后按指纹找回，续播位置从磁盘重新加载后仍然保留
+        assert_eq!(store.find_by_fingerprint("fp-a").unwrap().1, "a.mkv");
+        store.relocate(("custom:abc", "a.mkv"), ("custom:abc", "A.mkv")).unwrap();
+        let mut store = HistoryStore::load(&dir);
+        assert_eq!(store.get("custom:abc", "A.mkv").unwrap().position_secs, Some(600.0));
+
+        let group = |paths: &[&str]| BTreeMap::from([("custom:abc".to_string(), paths.iter().map(|p| p.to_string()).collect())]);
+        store.set_watched(&group(&["A.mkv", "c.mkv"]), true).unwrap();
+        assert!(store.continue_watching().is_empty());
+        assert!(store.get("custom:abc", "c.mkv").unwrap().watched);
+        store.set_watched(&group(&["c.mkv"]), false).unwrap();
+        assert!(store.get("custom:abc", "c.mkv").is_none());
+
+        // 重看已看完的视频时仍出现在继续观看中
+        store.record_play("custom:abc", "b.mkv", None).unwrap();
+        store.report_progress("custom:abc", "b.mkv", 300.0, Some(1800.0), None).unwrap();
+        let continuing: Vec<&str> = store.continue_watching().iter().map(|(_, path, _)| *path).collect();
+        assert_eq!(continuing, vec!["b.mkv"]);
+
+        store.clear_plays().unwrap();
+        assert!(store.recently_played().is_empty());
+        assert!(store.get("custom:abc", "b.mkv").unwrap().watched);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,33 +1,125 @@
 pub mod video;
 mod cover;
+mod custom_cover;
+mod persist;
+mod preview;
+mod storyboard;
 mod volume;
+mod mount;
+mod policy;
+mod catalog;
+mod storage_report;
+mod settings;
+mod search;
+mod query;
+mod collections;
+mod collation;
+mod listing;
+mod tags;
+mod annotations;
+mod history;
+mod mpv;
+mod player;
 mod folder;
+#[cfg(test)]
+mod test_support;
 
 use std::path::PathBuf;
-use std::sync::Mutex;
-use tauri::State;
-use crate::folder::{FolderManager, RootFolder, DirectoryNode};
+use std::sync::{Arc, Mutex};
+use tauri::{Emitter, Manager, State};
+use crate::folder::{FolderManager, RootFolder, DirectoryNode, OverlapMode, RootOverlap, RootScanOptions};
 use crate::video::VideoInfo;
-use crate::cover::CoverInfo;
+use crate::cover::{CoverInfo, CoverLookup};
+use crate::custom_cover::{CustomCoverEntry, CustomCoverStore};
+use crate::storyboard::{StoryboardGenerator, StoryboardInfo};
+use crate::preview::{PreviewEvent, PreviewJobStatus, PreviewManager};
+use crate::catalog::{CatalogExportInfo, CatalogImportReport};
+use crate::storage_report::StorageReport;
+use crate::policy::{StateMigrationReport, StoragePolicy};
+use crate::settings::{AppSettings, PlaybackBackend, SettingsStore};
+use crate::search::{SearchIndex, SearchResults};
+use crate::query::{Query, QuerySort, VideoContext};
+use crate::collections::{CollectionStore, SmartCollection};
+use crate::listing::{DirectoryListing, TreeOutline};
+use crate::tags::{Tag, TagStore};
+use crate::annotations::{AnnotationStore, VideoAnnotation};
+use crate::search::DocumentExtras;
+use crate::history::{HistoryStore, HistoryVideo, WatchState};
+use crate::mpv::{MpvBackend, MpvChapter, PlaybackStatus};
+use crate::player::PlayOptions;
+use crate::volume::{RootRelocatedEvent, VolumeInfo, VolumeRecord, VolumeRegistry, VolumeStatusEvent};
+
+/// 卷在线状态检查间隔
+const VOLUME_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
 
 // 全局状态结构
 pub struct AppState {
     folder_manager: Mutex<FolderManager>,
     cover_manager: Mutex<crate::cover::CoverManager>,
+    preview_manager: PreviewManager,
+    volume_registry: Mutex<VolumeRegistry>,
+    /// 全局设置
+    settings: Mutex<SettingsStore>,
+    /// 全文搜索索引
+    search_index: Mutex<SearchIndex>,
+    /// 智能合集
+    collections: Mutex<CollectionStore>,
+    /// 用户标签
+    tags: Mutex<TagStore>,
+    /// 评分、收藏与备注
+    annotations: Mutex<AnnotationStore>,
+    /// 观看记录与续播位置
+    history: Mutex<HistoryStore>,
+    /// 目录列表中子目录的总大小与总时长
+    listing_totals: Mutex<crate::listing::TotalsCache>,
+    /// mpv 播放后端
+    mpv: MpvBackend,
+    /// 应用缓存目录（封面、故事板等生成文件）
+    cache_dir: PathBuf,
 }
 
 impl AppState {
-    fn new() -> Self {
+    fn new(data_dir: PathBuf, config_dir: PathBuf, cache_dir: PathBuf) -> Self {
+        let settings = SettingsStore::load(&config_dir);
+        let mut folder_manager = FolderManager::with_data_dir(data_dir.clone());
+        folder_manager.apply_scan_settings(&settings.get().scan);
+        let mut cover_manager = crate::cover::CoverManager::with_cache_dir(cache_dir.clone());
+        cover_manager.apply_settings(&settings.get().covers);
         Self {
-            folder_manager: Mutex::new(FolderManager::new()),
-            cover_manager: Mutex::new(crate::cover::CoverManager::new()),
+            folder_manager: Mutex::new(folder_manager),
+            volume_registry: Mutex::new(VolumeRegistry::load(&data_dir)),
+            cover_manager: Mutex::new(cover_manager),
+            preview_manager: PreviewManager::new(&cache_dir, settings.get().preview.max_concurrency),
+            settings: Mutex::new(settings),
+            search_index: Mutex::new(SearchIndex::default()),
+            collections: Mutex::new(CollectionStore::load(&data_dir)),
+            tags: Mutex::new(TagStore::load(&data_dir)),
+            annotations: Mutex::new(AnnotationStore::load(&data_dir)),
+            history: Mutex::new(HistoryStore::load(&data_dir)),
+            listing_totals: Mutex::new(crate::listing::TotalsCache::default()),
+            mpv: MpvBackend::default(),
+            cache_dir,
         }
     }
+
+    /// 将设置应用到扫描器、封面管理器与预览任务池
+    fn apply_settings(&self, settings: &AppSettings) -> Result<(), String> {
+        self.folder_manager
+            .lock()
+            .map_err(|_| "无法获取文件夹管理器锁".to_string())?
+            .apply_scan_settings(&settings.scan);
+        self.cover_manager
+            .lock()
+            .map_err(|_| "无法获取封面管理器锁".to_string())?
+            .apply_settings(&settings.covers);
+        self.preview_manager.set_max_concurrency(settings.preview.max_concurrency);
+        Ok(())
+    }
 }
 
 // Tauri命令：添加根文件夹
 #[tauri::command]
-fn add_root_folder(state: State<AppState>, path: String, name: Option<String>) -> Result<String, String> {
+fn add_root_folder(state: State<AppState>, path: String, name: Option<String>, overlap: Option<OverlapMode>) -> Result<String, String> {
     println!("添加根文件夹: {} (名称: {:?})", path, name);
     
     let absolute_path = if path.starts_with('/') {
@@ -37,7 +129,7 @@ fn add_root_folder(state: State<AppState>, path: String, name: Option<String>) -
     };
     
     if !absolute_path.exists() {
-        return Err("路径不存在".to_string());
+        return restore_offline_root(&state, absolute_path, name, false);
     }
     
     if !absolute_path.is_dir() {
@@ -45,43 +137,167 @@ fn add_root_folder(state: State<AppState>, path: String, name: Option<String>) -
     }
     
     let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
-    let result = folder_manager.add_root_folder(absolute_path, name);
+    if let Some(existing_id) = resolve_root_overlap(&mut folder_manager, &absolute_path, overlap)? {
+        return Ok(existing_id);
+    }
+    let result = folder_manager.add_root_folder(absolute_path.clone(), name);
+    match root_volume_key(&folder_manager, &result, &absolute_path) {
+        Ok(volume_key) => register_root_volume(&state, &mut folder_manager, &result, volume_key, &absolute_path),
+        Err(e) => println!("无法获取卷标识: {}", e),
+    }
     Ok(result)
 }
 
+// 处理新根文件夹与已有根文件夹的重叠，返回 Some(已有 ID) 时不再添加
+fn resolve_root_overlap(folder_manager: &mut FolderManager, path: &std::path::Path, mode: Option<OverlapMode>) -> Result<Option<String>, String> {
+    match folder_manager.detect_overlap(path) {
+        Some(overlap) => {
+            println!("根文件夹重叠: {} {:?} (处理方式: {:?})", path.display(), overlap, mode);
+            folder_manager.resolve_overlap(path, overlap, mode)
+        }
+        None => Ok(None),
+    }
+}
+
+// Tauri命令：检查路径与已有根文件夹的重叠关系（供前端在添加前询问合并或嵌套）
+#[tauri::command]
+fn check_root_overlap(state: State<AppState>, path: String) -> Result<Option<RootOverlap>, String> {
+    let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    Ok(folder_manager.detect_overlap(&PathBuf::from(path)))
+}
+
+// 获取根文件夹的卷标识：只读模式下不向用户目录写入卷标识文件
+fn root_volume_key(folder_manager: &FolderManager, root_id: &str, path: &std::path::Path) -> Result<String, String> {
+    if folder_manager.is_root_read_only(root_id) {
+        let state_dir = folder_manager.data_state_dir(root_id);
+        crate::volume::read_volume_key(path, state_dir.as_deref())
+    } else {
+        crate::volume::get_or_create_volume_key(path)
+    }
+}
+
 // 根据卷标识与标准化路径生成幂等 rootId（UUID v5）
-fn generate_deterministic_root_id(root_path: &PathBuf) -> Result<String, String> {
+fn deterministic_root_id(volume_key: &str, root_path: &std::path::Path) -> String {
     let norm = root_path
         .to_string_lossy()
         .replace('\\', "/")
         .trim_end_matches('/')
         .to_string();
-    let vol = crate::volume::get_or_create_volume_key(root_path)
-        .map_err(|e| format!("无法获取卷标识: {}", e))?;
-    let key = format!("{}::{}", vol, norm);
+    let key = format!("{}::{}", volume_key, norm);
     let uuid = uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_URL, key.as_bytes());
-    Ok(uuid.to_string())
+    uuid.to_string()
+}
+
+// 登记根文件夹所在的卷，并记录到根文件夹上
+fn register_root_volume(state: &State<AppState>, folder_manager: &mut FolderManager, root_id: &str, volume_key: String, path: &std::path::Path) {
+    let volume_path = crate::volume::volume_path(&volume_key, path).unwrap_or_default();
+    folder_manager.set_volume_key(root_id, volume_key.clone(), volume_path);
+    if let Ok(mut registry) = state.volume_registry.lock() {
+        registry.mark_seen(&volume_key, path);
+        if let Err(e) = registry.save() {
+            println!("保存卷登记表失败: {}", e);
+        }
+    }
 }
 
 // Tauri命令：添加根文件夹（幂等ID版本）
 #[tauri::command]
-fn add_root_folder_deterministic(state: State<AppState>, path: String, name: Option<String>) -> Result<String, String> {
+fn add_root_folder_deterministic(state: State<AppState>, path: String, name: Option<String>, overlap: Option<OverlapMode>) -> Result<String, String> {
     let absolute_path = if path.starts_with('/') { PathBuf::from(path) } else { std::env::current_dir().unwrap().join(path) };
-    if !absolute_path.exists() { return Err("路径不存在".into()); }
+
+    // 路径不存在时，若属于已知的离线卷，则以离线状态恢复根文件夹
+    if !absolute_path.exists() {
+        return restore_offline_root(&state, absolute_path, name, true);
+    }
     if !absolute_path.is_dir() { return Err("路径不是目录".into()); }
 
     let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
-    let id = generate_deterministic_root_id(&absolute_path)?;
-    let result = folder_manager.add_root_folder_with_id(id, absolute_path, name);
+    // 自定义卷标识随根目录携带：已有离线根文件夹使用同一标识时，说明硬盘换了挂载路径，沿用原 ID
+    if let Some(volume_key) = crate::volume::read_volume_key(&absolute_path, None).ok().filter(|key| key.starts_with("custom:")) {
+        let relocated = folder_manager
+            .find_root_by_volume_key(&volume_key)
+            .filter(|root| root.path != absolute_path && !root.path.is_dir())
+            .map(|root| root.id.clone());
+        if let Some(id) = relocated {
+            if let Some(old_path) = folder_manager.relocate_root(&id, absolute_path.clone()) {
+                println!("根文件夹已重新绑定: {} -> {}", old_path.display(), absolute_path.display());
+            }
+            folder_manager.set_root_online(&id, true);
+            register_root_volume(&state, &mut folder_manager, &id, volume_key, &absolute_path);
+            return Ok(id);
+        }
+    }
+
+    // 先检查重叠再创建卷标识文件，被拒绝的路径上不会留下标识文件
+    if let Some(existing_id) = resolve_root_overlap(&mut folder_manager, &absolute_path, overlap)? {
+        return Ok(existing_id);
+    }
+
+    // rootId 由卷标识生成，此时只能按全局策略决定是否写入卷标识文件
+    let volume_key = if folder_manager.storage_policy().read_only {
+        crate::volume::read_volume_key(&absolute_path, None)
+    } else {
+        crate::volume::get_or_create_volume_key(&absolute_path)
+    }
+    .map_err(|e| format!("无法获取卷标识: {}", e))?;
+
+    let id = deterministic_root_id(&volume_key, &absolute_path);
+    let result = folder_manager.add_root_folder_with_id(id, absolute_path.clone(), name);
+    register_root_volume(&state, &mut folder_manager, &result, volume_key, &absolute_path);
     Ok(result)
 }
 
+// 路径不存在且卷登记表中记录的已知路径也不存在（硬盘未插入）时，以离线状态添加根文件夹；
+// deterministic 为 true 时 rootId 由卷标识生成，否则沿用同一路径上已有的根文件夹
+fn restore_offline_root(state: &State<AppState>, absolute_path: PathBuf, name: Option<String>, deterministic: bool) -> Result<String, String> {
+    let (volume_key, anchor) = state
+        .volume_registry
+        .lock()
+        .map_err(|_| "无法获取卷登记表锁".to_string())?
+        .find_offline_by_path(&absolute_path)
+        .map(|record| (record.key.clone(), record.anchor.clone()))
+        .ok_or("路径不存在".to_string())?;
+    // 按卷最后一次在线时的基准目录推算根文件夹在卷上的位置
+    let volume_path = anchor
+        .and_then(|anchor| crate::persist::relative_key(&anchor, &absolute_path).ok())
+        .unwrap_or_default();
+
+    println!("根文件夹所在的卷不在线，以离线状态添加: {}", absolute_path.display());
+    let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let id = if deterministic {
+        let id = deterministic_root_id(&volume_key, &absolute_path);
+        if folder_manager.get_root_folder(&id).is_none() {
+            folder_manager.add_root_folder_with_id(id.clone(), absolute_path, name);
+        }
+        id
+    } else {
+        let existing = folder_manager
+            .get_all_root_folders()
+            .into_iter()
+            .find(|root| root.path == absolute_path && root.volume_key.as_deref() == Some(volume_key.as_str()))
+            .map(|root| root.id);
+        existing.unwrap_or_else(|| folder_manager.add_root_folder(absolute_path, name))
+    };
+    folder_manager.set_volume_key(&id, volume_key, volume_path);
+    folder_manager.set_root_online(&id, false);
+    Ok(id)
+}
+
 // Tauri命令：移除根文件夹
 #[tauri::command]
-fn remove_root_folder(state: State<AppState>, id: String) -> Result<bool, String> {
+fn remove_root_folder(app: tauri::AppHandle, state: State<AppState>, id: String) -> Result<bool, String> {
     println!("移除根文件夹: {}", id);
-    let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
-    Ok(folder_manager.remove_root_folder(&id))
+    let removed = state
+        .folder_manager
+        .lock()
+        .map_err(|_| "无法获取文件夹管理器锁".to_string())?
+        .remove_root_folder(&id);
+    // 清理为该根文件夹指定的播放器配置
+    let mut settings = state.settings.lock().map_err(|_| "无法获取设置锁".to_string())?;
+    if removed && settings.remove_root_profile(&id)? {
+        let _ = app.emit("settings-changed", settings.get());
+    }
+    Ok(removed)
 }
 
 // Tauri命令：获取所有根文件夹
@@ -92,45 +308,199 @@ fn get_root_folders(state: State<AppState>) -> Result<Vec<RootFolder>, String> {
     Ok(folder_manager.get_all_root_folders())
 }
 
+// 修改根文件夹设置后返回最新的根文件夹信息
+fn edit_root_folder(
+    state: &State<AppState>,
+    root_id: &str,
+    edit: impl FnOnce(&mut FolderManager) -> bool,
+) -> Result<RootFolder, String> {
+    let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    if !edit(&mut folder_manager) {
+        return Err("根文件夹不存在".to_string());
+    }
+    folder_manager
+        .get_root_folder(root_id)
+        .cloned()
+        .ok_or("根文件夹不存在".to_string())
+}
+
+// Tauri命令：重命名根文件夹
+#[tauri::command]
+fn rename_root_folder(state: State<AppState>, root_id: String, name: String) -> Result<RootFolder, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("名称不能为空".to_string());
+    }
+    edit_root_folder(&state, &root_id, |folder_manager| folder_manager.rename_root_folder(&root_id, name))
+}
+
+// Tauri命令：启用或停用根文件夹
+#[tauri::command]
+fn set_root_enabled(state: State<AppState>, root_id: String, enabled: bool) -> Result<RootFolder, String> {
+    edit_root_folder(&state, &root_id, |folder_manager| folder_manager.set_folder_enabled(&root_id, enabled))
+}
+
+// Tauri命令：设置根文件夹的最大扫描深度（-1 表示不限制）
+#[tauri::command]
+fn set_root_max_depth(state: State<AppState>, root_id: String, max_depth: i32) -> Result<RootFolder, String> {
+    if max_depth < -1 {
+        return Err("扫描深度无效".to_string());
+    }
+    edit_root_folder(&state, &root_id, |folder_manager| folder_manager.set_max_depth(&root_id, max_depth))
+}
+
+// Tauri命令：设置根文件夹的扫描选项（下次扫描时生效）
+#[tauri::command]
+fn set_root_scan_options(state: State<AppState>, root_id: String, options: RootScanOptions) -> Result<RootFolder, String> {
+    edit_root_folder(&state, &root_id, |folder_manager| folder_manager.set_scan_options(&root_id, options))
+}
+
 // Tauri命令：扫描目录
 #[tauri::command]
-fn scan_directory(state: State<AppState>, root_id: String) -> Result<DirectoryNode, String> {
+fn scan_directory(app: tauri::AppHandle, state: State<AppState>, root_id: String) -> Result<DirectoryNode, String> {
     println!("开始扫描目录，root_id: {}", root_id);
     
     let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
     let mut cover_manager = state.cover_manager.lock().map_err(|_| "无法获取封面管理器锁".to_string())?;
     
-    // 从根目录开始扫描
-    if let Some(root_folder) = folder_manager.get_root_folder(&root_id) {
-        
-        // 先扫描封面文件
-        if let Err(e) = cover_manager.scan_covers(&root_folder.path) {
-            println!("扫描封面文件失败: {}", e);
-        } else {
-            println!("封面文件扫描完成");
+    let root_folder = match folder_manager.get_root_folder(&root_id) {
+        Some(root_folder) => root_folder.clone(),
+        None => {
+            println!("未找到根文件夹: {}", root_id);
+            return Err("未找到根文件夹".to_string());
         }
-        
-        // 构建目录树
-        match folder_manager.build_directory_tree(&root_id) {
-            Ok(directory_tree) => {
-                Ok(directory_tree)
+    };
+
+    // 卷不在线：标记离线并返回最后一次扫描的目录树
+    if !root_folder.path.is_dir() {
+        println!("根文件夹所在的卷不在线: {}", root_folder.path.display());
+        folder_manager.set_root_online(&root_id, false);
+        if let (Some(volume_key), Ok(mut registry)) = (&root_folder.volume_key, state.volume_registry.lock()) {
+            if registry.mark_offline(volume_key) {
+                let _ = registry.save();
             }
-            Err(e) => {
-                println!("构建目录树失败: {}", e);
-                Err(format!("构建目录树失败: {}", e))
+        }
+        let mut tree = folder_manager
+            .get_or_load_directory_tree(&root_id)
+            .cloned()
+            .ok_or("根文件夹所在的卷不在线，且没有可用的目录树".to_string())?;
+        apply_custom_covers(&folder_manager, &root_id, &mut tree);
+        apply_annotations(&state, &folder_manager, &root_id, &mut tree);
+        return Ok(tree);
+    }
+
+    // 构建目录树，同时索引各级目录中的封面文件
+    match folder_manager.build_directory_tree(&root_id, &mut cover_manager) {
+        Ok(mut directory_tree) => {
+            apply_custom_covers(&folder_manager, &root_id, &mut directory_tree);
+            apply_annotations(&state, &folder_manager, &root_id, &mut directory_tree);
+            drop(cover_manager);
+            if let (Ok(tags), Ok(annotations), Ok(history), Ok(mut collections)) =
+                (state.tags.lock(), state.annotations.lock(), state.history.lock(), state.collections.lock())
+            {
+                let user_data = UserData { tags: &tags, annotations: &annotations, history: &history };
+                if refresh_collection_counts(&mut folder_manager, &user_data, &mut collections) {
+                    let _ = app.emit("collections-updated", collections.list());
+                }
             }
+            Ok(directory_tree)
+        }
+        Err(e) => {
+            println!("构建目录树失败: {}", e);
+            Err(format!("构建目录树失败: {}", e))
         }
-    } else {
-        println!("未找到根文件夹: {}", root_id);
-        Err("未找到根文件夹".to_string())
     }
 }
 
 // Tauri命令：获取目录树
 #[tauri::command]
 fn get_directory_tree(state: State<AppState>, root_id: String) -> Result<Option<DirectoryNode>, String> {
-    let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
-    Ok(folder_manager.get_directory_tree(&root_id).cloned())
+    let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let mut tree = folder_manager.get_or_load_directory_tree(&root_id).cloned();
+    if let Some(tree) = tree.as_mut() {
+        apply_custom_covers(&folder_manager, &root_id, tree);
+        apply_annotations(&state, &folder_manager, &root_id, tree);
+    }
+    Ok(tree)
+}
+
+// 将根文件夹的自定义封面设置应用到目录树
+fn apply_custom_covers(folder_manager: &FolderManager, root_id: &str, tree: &mut DirectoryNode) {
+    let (Some(root), Some(state_dir)) = (folder_manager.get_root_folder(root_id), folder_manager.root_state_dir(root_id)) else {
+        return;
+    };
+    match CustomCoverStore::load(&root.path, &state_dir) {
+        Ok(store) => store.apply_to_tree(tree),
+        Err(e) => println!("加载自定义封面失败: {}", e),
+    }
+}
+
+// 将评分、收藏与备注附加到目录树中的视频上
+fn apply_annotations(state: &State<AppState>, folder_manager: &FolderManager, root_id: &str, tree: &mut DirectoryNode) {
+    let (Some(root), Ok(annotations)) = (folder_manager.get_root_folder(root_id), state.annotations.lock()) else {
+        return;
+    };
+    annotations.apply_to_tree(root, tree);
+}
+
+// Tauri命令：分页列出目录的一层内容（子目录在前、视频在后），rel_path 为空表示根目录
+#[tauri::command]
+fn list_directory(
+    state: State<AppState>,
+    root_id: String,
+    rel_path: String,
+    sort: Option<QuerySort>,
+    offset: usize,
+    limit: usize,
+) -> Result<DirectoryListing, String> {
+    let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let root = folder_manager
+        .get_root_folder(&root_id)
+        .cloned()
+        .ok_or("根文件夹不存在".to_string())?;
+    let root_path = root.path.clone();
+    let target = crate::persist::resolve_key(&root_path, &rel_path);
+    let annotations = state.annotations.lock().map_err(|_| "无法获取评分与备注锁".to_string())?;
+    folder_manager.get_or_load_directory_tree(&root_id);
+    let revision = folder_manager.library_revision();
+    let tree = folder_manager
+        .get_directory_tree(&root_id)
+        .ok_or("目录树尚未扫描".to_string())?;
+    let node = tree.find(&target.to_string_lossy()).ok_or("目录不存在".to_string())?;
+    let mut listing_totals = state.listing_totals.lock().map_err(|_| "无法获取目录统计锁".to_string())?;
+    let totals = listing_totals.get(&root_id, revision, tree);
+    let mut listing = crate::listing::list_directory(&root_path, node, totals, sort.unwrap_or_default(), offset, limit, |videos| {
+        annotations.apply_to_videos(&root, videos.iter_mut())
+    });
+    drop(listing_totals);
+    drop(annotations);
+
+    // 自定义封面优先
+    match load_custom_cover_store(&folder_manager, &root_id) {
+        Ok(store) => {
+            for directory in &mut listing.directories {
+                if let Some(cover) = store.get(&crate::persist::resolve_key(&root_path, &directory.rel_path)) {
+                    directory.cover_path = Some(cover);
+                }
+            }
+        }
+        Err(e) => println!("加载自定义封面失败: {}", e),
+    }
+    Ok(listing)
+}
+
+// Tauri命令：获取目录树大纲（只含数量），max_depth 限制展开层数
+#[tauri::command]
+fn get_tree_outline(state: State<AppState>, root_id: String, max_depth: Option<u32>) -> Result<Option<TreeOutline>, String> {
+    let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let root_path = folder_manager
+        .get_root_folder(&root_id)
+        .ok_or("根文件夹不存在".to_string())?
+        .path
+        .clone();
+    Ok(folder_manager
+        .get_or_load_directory_tree(&root_id)
+        .map(|tree| crate::listing::outline(&root_path, tree, max_depth)))
 }
 
 // Tauri命令：获取视频信息
@@ -153,28 +523,120 @@ fn get_cover_info(state: State<AppState>, cover_path: String) -> Result<CoverInf
         .ok_or_else(|| "封面不存在".to_string())
 }
 
-// Tauri命令：播放视频
+// Tauri命令：播放视频，并记录到观看记录；可指定起始位置（秒），未指定时按设置从上次的位置继续
 #[tauri::command]
-fn open_video(path: String) -> Result<(), String> {
+async fn open_video(app: tauri::AppHandle, root_id: String, relative_path: String, start_secs: Option<f64>) -> Result<(), String> {
+    play_video(app, root_id, relative_path, None, Some(PlayOptions { start_secs, subtitle: None })).await
+}
+
+// Tauri命令：使用播放器配置播放视频，视频由根文件夹 ID 与相对路径（`/` 分隔）指定。
+// profile 为空时依次按根文件夹、容器格式与默认配置选择，都未指定时使用播放后端（系统默认程序或 mpv）
+#[tauri::command]
+async fn play_video(
+    app: tauri::AppHandle,
+    root_id: String,
+    relative_path: String,
+    profile: Option<String>,
+    options: Option<PlayOptions>,
+) -> Result<(), String> {
+    // 启动 mpv 需要等待 IPC 套接字就绪，记录播放需要计算指纹，放到阻塞线程池中执行，避免阻塞主线程
+    tauri::async_runtime::spawn_blocking(move || {
+        launch_video(&app, &root_id, &relative_path, profile.as_deref(), options.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| format!("播放视频失败: {}", e))?
+}
+
+// 按播放器配置或播放后端启动播放，并记录到观看记录
+fn launch_video(app: &tauri::AppHandle, root_id: &str, relative_path: &str, profile: Option<&str>, options: PlayOptions) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    options.validate()?;
+    let path = state
+        .folder_manager
+        .lock()
+        .map_err(|_| "无法获取文件夹管理器锁".to_string())?
+        .resolve_video_path(root_id, relative_path)?;
+    let playback = state.settings.lock().map_err(|_| "无法获取设置锁".to_string())?.get().playback.clone();
+    // 先按指纹找回改名或移动前的观看记录，再读取续播位置
+    let location = locate_play_history(&state, &path);
+    let start_secs = options.start_secs.or_else(|| {
+        if playback.resume {
+            resume_position(&state, location.as_ref()?)
+        } else {
+            None
+        }
+    });
+
+    match crate::player::select_profile(&playback, profile, Some(root_id), &path)? {
+        Some(player) => {
+            let subtitle = options.subtitle.or_else(|| crate::player::find_subtitle(&path));
+            let args = player.render_args(&path, start_secs, subtitle.as_deref())?;
+            player.launch(&args)?;
+        }
+        None if playback.backend == PlaybackBackend::Mpv => {
+            let app = app.clone();
+            let callback = Arc::new(move |status: &PlaybackStatus| report_mpv_progress(&app, status));
+            state.mpv.play(&playback.mpv_path, &path, start_secs, callback)?;
+        }
+        None => open_with_system(&path.to_string_lossy())?,
+    }
+    if let Some(location) = location {
+        record_play(&state, location);
+    }
+    Ok(())
+}
+
+// 视频的续播位置
+fn resume_position(state: &State<AppState>, location: &PlayLocation) -> Option<f64> {
+    let history = state.history.lock().ok()?;
+    history.get(&location.0, &location.1)?.position_secs
+}
+
+// mpv 回报的播放进度写入观看记录，并发出 playback-progress 事件
+fn report_mpv_progress(app: &tauri::AppHandle, status: &PlaybackStatus) {
+    let state = app.state::<AppState>();
+    if let Some(position_secs) = status.position_secs {
+        let location = match state.folder_manager.lock() {
+            Ok(folder_manager) => locate_in_root(&folder_manager, &status.video_path).ok(),
+            Err(_) => None,
+        };
+        if let (Some((storage_key, relative_path)), Ok(mut history)) = (location, state.history.lock()) {
+            let watched_secs = (status.watched_secs > 0.0).then_some(status.watched_secs);
+            if let Err(e) = history.report_progress(&storage_key, &relative_path, position_secs, status.duration_secs, watched_secs) {
+                println!("记录播放进度失败: {}", e);
+            }
+        }
+    }
+    // mpv 退出后立即写入最后的播放进度
+    if status.finished {
+        flush_history(&state);
+    }
+    if let Err(e) = app.emit("playback-progress", status) {
+        println!("发送播放进度事件失败: {}", e);
+    }
+}
+
+// 使用系统默认程序打开视频
+fn open_with_system(path: &str) -> Result<(), String> {
     use std::process::Command;
     
     #[cfg(target_os = "macos")]
     let output = Command::new("open")
-        .arg(&path)
+        .arg(path)
         .output();
     
     #[cfg(target_os = "windows")]
     let output = {
         use std::os::windows::process::CommandExt;
         Command::new("cmd")
-            .args(&["/C", "start", &path])
+            .args(&["/C", "start", path])
             .creation_flags(0x08000000) // CREATE_NO_WINDOW
             .output()
     };
     
     #[cfg(not(any(target_os = "macos", target_os = "windows")))]
     let output = Command::new("xdg-open")
-        .arg(&path)
+        .arg(path)
         .output();
 
     match output {
@@ -183,6 +645,47 @@ fn open_video(path: String) -> Result<(), String> {
     }
 }
 
+// Tauri命令：当前 mpv 播放状态（没有正在播放的视频时为空）
+#[tauri::command]
+fn get_playback_status(state: State<AppState>) -> Result<Option<PlaybackStatus>, String> {
+    state.mpv.status()
+}
+
+// Tauri命令：将正在播放的视频跳转到指定位置（秒）
+#[tauri::command]
+fn playback_seek(state: State<AppState>, position_secs: f64) -> Result<(), String> {
+    state.mpv.seek(position_secs)
+}
+
+// Tauri命令：正在播放的视频的章节列表
+#[tauri::command]
+fn get_playback_chapters(state: State<AppState>) -> Result<Vec<MpvChapter>, String> {
+    state.mpv.chapters()
+}
+
+// Tauri命令：将正在播放的视频跳转到指定章节（从 0 开始）
+#[tauri::command]
+fn playback_seek_chapter(state: State<AppState>, index: usize) -> Result<(), String> {
+    state.mpv.seek_chapter(index)
+}
+
+// Tauri命令：停止 mpv 播放
+#[tauri::command]
+fn stop_playback(state: State<AppState>) -> Result<(), String> {
+    state.mpv.stop()?;
+    flush_history(&state);
+    Ok(())
+}
+
+// 将内存中的播放进度写入磁盘
+fn flush_history(state: &AppState) {
+    if let Ok(mut history) = state.history.lock() {
+        if let Err(e) = history.flush() {
+            println!("保存观看记录失败: {}", e);
+        }
+    }
+}
+
 // Tauri命令：执行系统命令
 #[tauri::command]
 fn execute_command(command: String, args: Vec<String>) -> Result<(), String> {
@@ -206,22 +709,305 @@ fn execute_command(command: String, args: Vec<String>) -> Result<(), String> {
     }
 }
 
-// Tauri命令：查找视频的封面
+// Tauri命令：查找视频的封面（探测内嵌封面需要运行 ffprobe/ffmpeg，在后台线程中执行）
 #[tauri::command]
-fn find_cover_for_video(state: State<AppState>, video_path: String) -> Result<Option<String>, String> {
-    
-    let path = PathBuf::from(&video_path);
-    let cover_manager = state.cover_manager.lock().map_err(|_| "无法获取封面管理器锁".to_string())?;
-    
-    // 使用动态查找方法，不依赖缓存
-    match cover_manager.get_video_cover_path(&path) {
-        Some(cover_path) => {
-            Ok(Some(cover_path.to_string_lossy().to_string()))
+async fn find_cover_for_video(app: tauri::AppHandle, video_path: String) -> Result<Option<String>, String> {
+    tauri::async_runtime::spawn_blocking(move || find_cover(&app.state::<AppState>(), &PathBuf::from(video_path)))
+        .await
+        .map_err(|e| format!("查找封面失败: {}", e))?
+}
+
+// 查找视频的封面：自定义封面 → 同名封面 → 内嵌封面；探测与导出内嵌封面时不持有封面管理器锁
+fn find_cover(state: &State<AppState>, path: &std::path::Path) -> Result<Option<String>, String> {
+    // 自定义封面优先；同时取出扫描时发现的内嵌封面，避免重复探测
+    let scanned_cover = {
+        let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+        let Some(root) = folder_manager.find_root_for_path(path) else {
+            return find_video_cover(state, path, None);
+        };
+        let store = folder_manager
+            .root_state_dir(&root.id)
+            .and_then(|state_dir| CustomCoverStore::load(&root.path, &state_dir).ok());
+        if let Some(cover_path) = store.and_then(|store| store.get(path)) {
+            return Ok(Some(cover_path.to_string_lossy().to_string()));
         }
-        None => {
-            println!("❌ 未找到封面");
-            Ok(None)
+        folder_manager
+            .get_directory_tree(&root.id)
+            .and_then(|tree| tree.find(&path.parent()?.to_string_lossy()))
+            .and_then(|node| node.videos.iter().find(|video| video.path == path))
+            .and_then(|video| video.embedded_cover.clone())
+    };
+    find_video_cover(state, path, scanned_cover.as_ref())
+}
+
+// 按同名封面 → 内嵌封面查找视频的封面；探测失败的结果不缓存，下次重新探测
+fn find_video_cover(
+    state: &State<AppState>,
+    path: &std::path::Path,
+    scanned_cover: Option<&crate::video::EmbeddedCover>,
+) -> Result<Option<String>, String> {
+
+    let lookup = state
+        .cover_manager
+        .lock()
+        .map_err(|_| "无法获取封面管理器锁".to_string())?
+        .lookup_video_cover(path);
+    let cover_path = match lookup {
+        CoverLookup::Found(cover_path) => Some(cover_path),
+        CoverLookup::NotFound => None,
+        CoverLookup::NeedsProbe(cache_dir) => match crate::cover::probe_embedded_cover(&cache_dir, path, scanned_cover) {
+            Ok(cover_path) => {
+                state
+                    .cover_manager
+                    .lock()
+                    .map_err(|_| "无法获取封面管理器锁".to_string())?
+                    .record_embedded_cover(path, cover_path.clone());
+                cover_path
+            }
+            Err(e) => {
+                println!("查找内嵌封面失败: {}", e);
+                None
+            }
+        },
+    };
+    if cover_path.is_none() {
+        println!("❌ 未找到封面");
+    }
+    Ok(cover_path.map(|cover_path| cover_path.to_string_lossy().to_string()))
+}
+
+// 加载根文件夹的自定义封面存储
+fn load_custom_cover_store(folder_manager: &FolderManager, root_id: &str) -> Result<CustomCoverStore, String> {
+    let root = folder_manager
+        .get_root_folder(root_id)
+        .ok_or("根文件夹不存在".to_string())?;
+    let state_dir = folder_manager
+        .root_state_dir(root_id)
+        .ok_or("无法确定根文件夹的数据目录".to_string())?;
+    CustomCoverStore::load(&root.path, &state_dir)
+}
+
+// 加载根文件夹的自定义封面存储，并校验目标路径位于该根文件夹内
+fn resolve_target_in_root(state: &State<AppState>, root_id: &str, target_path: &str) -> Result<(CustomCoverStore, PathBuf), String> {
+    let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let root = folder_manager
+        .get_root_folder(root_id)
+        .ok_or("根文件夹不存在".to_string())?;
+    let target = PathBuf::from(target_path);
+    // 按字面比较前缀前先拒绝 `..` 等成分，否则 root/../../etc 也能通过检查
+    let lexically_inside = target.is_absolute()
+        && target.components().all(|component| matches!(component, std::path::Component::Normal(_) | std::path::Component::RootDir | std::path::Component::Prefix(_)))
+        && target.starts_with(&root.path);
+    // 目标存在时再按真实路径检查，排除指向根文件夹外的符号链接
+    let really_inside = match (target.canonicalize(), root.path.canonicalize()) {
+        (Ok(target), Ok(root_path)) => target.starts_with(root_path),
+        _ => true,
+    };
+    if !lexically_inside || !really_inside {
+        return Err("路径不在根文件夹内".to_string());
+    }
+    Ok((load_custom_cover_store(&folder_manager, root_id)?, target))
+}
+
+// Tauri命令：设置目录或视频的自定义封面
+#[tauri::command]
+fn set_custom_cover(state: State<AppState>, root_id: String, target_path: String, cover_path: String) -> Result<(), String> {
+    let (mut store, target) = resolve_target_in_root(&state, &root_id, &target_path)?;
+    if !target.exists() {
+        return Err("目标路径不存在".to_string());
+    }
+
+    let cover = PathBuf::from(cover_path);
+    if !cover.is_file() || crate::cover::CoverManager::new().detect_format(&cover).is_none() {
+        return Err("封面文件不存在或格式不支持".to_string());
+    }
+
+    store.set(&target, &cover)?;
+    store.save()
+}
+
+// Tauri命令：清除目录或视频的自定义封面
+#[tauri::command]
+fn clear_custom_cover(state: State<AppState>, root_id: String, target_path: String) -> Result<bool, String> {
+    let (mut store, target) = resolve_target_in_root(&state, &root_id, &target_path)?;
+    let removed = store.clear(&target)?;
+    if removed {
+        store.save()?;
+    }
+    Ok(removed)
+}
+
+// Tauri命令：列出根文件夹的所有自定义封面
+#[tauri::command]
+fn list_custom_covers(state: State<AppState>, root_id: String) -> Result<Vec<CustomCoverEntry>, String> {
+    let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    Ok(load_custom_cover_store(&folder_manager, &root_id)?.list())
+}
+
+// Tauri命令：将根文件夹的目录树导出为随硬盘携带的相对路径目录文件（`.videovault/catalog.json`）
+#[tauri::command]
+fn export_catalog(state: State<AppState>, root_id: String) -> Result<CatalogExportInfo, String> {
+    let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let root = folder_manager
+        .get_root_folder(&root_id)
+        .cloned()
+        .ok_or("根文件夹不存在".to_string())?;
+    if !root.path.is_dir() {
+        return Err("根文件夹所在的卷不在线".to_string());
+    }
+    let state_dir = folder_manager
+        .root_state_dir(&root_id)
+        .ok_or("无法确定根文件夹的数据目录".to_string())?;
+    let tree = folder_manager
+        .get_or_load_directory_tree(&root_id)
+        .ok_or("请先扫描根文件夹".to_string())?;
+
+    let tags = state
+        .tags
+        .lock()
+        .map_err(|_| "无法获取标签锁".to_string())?
+        .export_root(&root.storage_key(), root.storage_prefix());
+    let info = crate::catalog::export_catalog(&root.path, &state_dir, &root.name, root.volume_key.clone(), tree, tags)?;
+    println!("已导出目录: {} ({} 个视频)", info.catalog_path.display(), info.video_count);
+    Ok(info)
+}
+
+// Tauri命令：导入根文件夹中的目录文件，无需重新探测即可浏览；导入时与当前文件逐一校验
+// （遍历目录、探测视频与计算指纹耗时较长，在后台线程中执行）
+#[tauri::command]
+async fn import_catalog(app: tauri::AppHandle, root_id: String) -> Result<CatalogImportReport, String> {
+    tauri::async_runtime::spawn_blocking(move || import_root_catalog(&app.state::<AppState>(), &root_id))
+        .await
+        .map_err(|e| format!("导入目录失败: {}", e))?
+}
+
+// 导入根文件夹中的目录文件并替换目录树与标签
+fn import_root_catalog(state: &State<AppState>, root_id: &str) -> Result<CatalogImportReport, String> {
+    let (root, state_dir) = {
+        let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+        let root = folder_manager
+            .get_root_folder(root_id)
+            .cloned()
+            .ok_or("根文件夹不存在".to_string())?;
+        let state_dir = folder_manager
+            .root_state_dir(root_id)
+            .ok_or("无法确定根文件夹的数据目录".to_string())?;
+        (root, state_dir)
+    };
+    if !root.path.is_dir() {
+        return Err("根文件夹所在的卷不在线".to_string());
+    }
+
+    let (video_extensions, cover_settings) = {
+        let settings = state.settings.lock().map_err(|_| "无法获取设置锁".to_string())?;
+        (settings.get().scan.video_extensions.clone(), settings.get().covers.clone())
+    };
+    let mut cover_manager = crate::cover::CoverManager::new();
+    cover_manager.apply_settings(&cover_settings);
+
+    // 校验过程可能需要重新探测视频，不持有锁
+    let scan = crate::catalog::CatalogScan {
+        video_extensions: &video_extensions,
+        cover_manager: &cover_manager,
+    };
+    let (tree, catalog_tags, mut report) = crate::catalog::import_catalog(&root.path, &state_dir, root.volume_key.as_deref(), &scan)?;
+    println!(
+        "已导入目录: {} 个视频（{} 个重新探测，{} 个缺失，{} 个新增）",
+        report.video_count,
+        report.reprobed.len(),
+        report.missing.len(),
+        report.added.len()
+    );
+
+    let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    folder_manager.set_directory_tree(root_id, tree);
+    let mut tags = state.tags.lock().map_err(|_| "无法获取标签锁".to_string())?;
+    report.imported_tags = tags.import_root(&root.storage_key(), root.storage_prefix(), &catalog_tags)?;
+    Ok(report)
+}
+
+// Tauri命令：生成视频故事板（联系表 + WebVTT 精灵图映射）
+#[tauri::command]
+async fn generate_storyboard(state: State<'_, AppState>, video_path: String, columns: u32, rows: u32) -> Result<StoryboardInfo, String> {
+    let path = PathBuf::from(&video_path);
+    if !path.is_file() {
+        return Err("视频文件不存在".to_string());
+    }
+    println!("生成故事板: {} ({}x{})", video_path, columns, rows);
+    // 逐帧运行 ffmpeg 耗时较长，放到阻塞线程池中执行，避免占用异步运行时的工作线程
+    let generator = StoryboardGenerator::new(&state.cache_dir);
+    tauri::async_runtime::spawn_blocking(move || generator.generate(&path, columns, rows))
+        .await
+        .map_err(|e| format!("生成故事板失败: {}", e))?
+}
+
+// Tauri命令：为根文件夹（或其中某个子目录）的视频后台生成悬停预览
+#[tauri::command]
+fn generate_previews(app: tauri::AppHandle, state: State<AppState>, root_id: String, sub_path: Option<String>) -> Result<usize, String> {
+    let videos: Vec<PathBuf> = {
+        let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+        let tree = folder_manager
+            .get_directory_tree(&root_id)
+            .ok_or("目录树不存在，请先扫描根文件夹".to_string())?;
+        let node = match &sub_path {
+            Some(sub_path) => tree.find(sub_path).ok_or("目录不存在".to_string())?,
+            None => tree,
+        };
+        node.all_videos().into_iter().map(|video| video.path.clone()).collect()
+    };
+
+    let callback = Arc::new(move |event: PreviewEvent| {
+        if let Err(e) = app.emit("preview-generated", &event) {
+            println!("发送预览事件失败: {}", e);
         }
+    });
+    let added = state.preview_manager.enqueue(videos, callback);
+    println!("已加入预览生成队列: {} 个视频", added);
+    Ok(added)
+}
+
+// Tauri命令：获取视频的悬停预览文件（前端通过 vvpreview 协议加载）
+#[tauri::command]
+fn get_preview_path(state: State<AppState>, video_path: String) -> Result<Option<String>, String> {
+    Ok(state
+        .preview_manager
+        .get_preview_path(&PathBuf::from(video_path))
+        .map(|path| path.to_string_lossy().to_string()))
+}
+
+// Tauri命令：获取预览生成任务状态
+#[tauri::command]
+fn get_preview_job_status(state: State<AppState>) -> Result<PreviewJobStatus, String> {
+    Ok(state.preview_manager.status())
+}
+
+// Tauri命令：取消等待中的预览生成任务
+#[tauri::command]
+fn cancel_preview_jobs(state: State<AppState>) -> Result<usize, String> {
+    Ok(state.preview_manager.cancel_pending())
+}
+
+// vvpreview 协议：按文件名从预览缓存目录读取 WebM
+fn handle_preview_protocol<R: tauri::Runtime>(
+    ctx: tauri::UriSchemeContext<'_, R>,
+    request: tauri::http::Request<Vec<u8>>,
+) -> tauri::http::Response<Vec<u8>> {
+    let file_name = request.uri().path().trim_start_matches('/').to_string();
+    let file = ctx
+        .app_handle()
+        .try_state::<AppState>()
+        .and_then(|state| state.preview_manager.resolve_cached_file(&file_name))
+        .and_then(|path| std::fs::read(path).ok());
+
+    match file {
+        Some(data) => tauri::http::Response::builder()
+            .header("Content-Type", "video/webm")
+            .body(data)
+            .unwrap_or_default(),
+        None => tauri::http::Response::builder()
+            .status(404)
+            .body(Vec::new())
+            .unwrap_or_default(),
     }
 }
 
@@ -256,7 +1042,781 @@ fn get_volume_key(state: State<AppState>, root_id: String) -> Result<String, Str
         .get_root_folder(&root_id)
         .ok_or("根文件夹不存在".to_string())?;
 
-    crate::volume::get_or_create_volume_key(&root.path)
+    // 离线时使用登记时记录的卷标识
+    match (&root.volume_key, root.path.is_dir()) {
+        (Some(volume_key), false) => Ok(volume_key.clone()),
+        _ => root_volume_key(&folder_manager, &root_id, &root.path),
+    }
+}
+
+// Tauri命令：获取存储策略（只读模式）
+#[tauri::command]
+fn get_storage_policy(state: State<AppState>) -> Result<StoragePolicy, String> {
+    let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    Ok(folder_manager.storage_policy().clone())
+}
+
+// Tauri命令：设置全局只读模式（只影响未单独设置的根文件夹，已有数据需通过 migrate_root_state 迁移）
+#[tauri::command]
+fn set_read_only_mode(state: State<AppState>, read_only: bool) -> Result<StoragePolicy, String> {
+    let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    folder_manager.update_storage_policy(|policy| policy.read_only = read_only)?;
+    Ok(folder_manager.storage_policy().clone())
+}
+
+// Tauri命令：切换根文件夹的只读模式，并在根目录与应用数据目录之间迁移其数据（卷标识、自定义封面等）
+#[tauri::command]
+fn migrate_root_state(state: State<AppState>, root_id: String, read_only: bool) -> Result<StateMigrationReport, String> {
+    let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let root = folder_manager
+        .get_root_folder(&root_id)
+        .cloned()
+        .ok_or("根文件夹不存在".to_string())?;
+    let data_state = folder_manager
+        .data_state_dir(&root_id)
+        .ok_or("无法确定应用数据目录".to_string())?;
+
+    let report = crate::policy::migrate_root_state(&root.path, &data_state, read_only)?;
+    folder_manager.update_storage_policy(|policy| {
+        policy.roots.insert(root_id.clone(), read_only);
+    })?;
+    println!("根文件夹数据已迁移: {} -> {} ({} 个文件)", root.path.display(), report.state_dir.display(), report.moved_files);
+    Ok(report)
+}
+
+// 查询与搜索用到的用户数据
+struct UserData<'a> {
+    tags: &'a TagStore,
+    annotations: &'a AnnotationStore,
+    history: &'a HistoryStore,
+}
+
+impl UserData<'_> {
+    // 目录树与用户数据的整体版本号：各计数都只增不减，任一变化时其和都会增大
+    fn revision(&self, folder_manager: &FolderManager) -> u64 {
+        folder_manager.library_revision() + self.tags.revision() + self.annotations.revision() + self.history.revision()
+    }
+
+    // 需要索引的标签（包括上级目录的标签）与备注
+    fn document_extras(&self, folder_manager: &FolderManager, path: &std::path::Path) -> DocumentExtras {
+        let Some((storage_key, relative_path)) = locate_in_root(folder_manager, path).ok() else {
+            return DocumentExtras::default();
+        };
+        DocumentExtras {
+            tags: self.tags.effective_names(&storage_key, &relative_path),
+            notes: self
+                .annotations
+                .get(&storage_key, &relative_path)
+                .and_then(|annotation| annotation.notes.clone()),
+        }
+    }
+
+    // 将评分、收藏与备注附加到（可能来自多个根文件夹的）视频上
+    fn annotate(&self, folder_manager: &FolderManager, videos: &mut [VideoInfo]) {
+        for video in videos {
+            video.annotation = locate_in_root(folder_manager, &video.path)
+                .ok()
+                .and_then(|(storage_key, relative_path)| self.annotations.get(&storage_key, &relative_path).cloned());
+        }
+    }
+}
+
+// Tauri命令：跨所有根文件夹搜索视频（文件名、目录名、内嵌标题、标签、备注），按相关度分页返回；
+// tags 非空时只返回带有这些标签的视频
+#[tauri::command]
+fn search_videos(state: State<AppState>, query: String, tags: Option<Vec<String>>, limit: usize, offset: usize) -> Result<SearchResults, String> {
+    let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let tag_store = state.tags.lock().map_err(|_| "无法获取标签锁".to_string())?;
+    let annotations = state.annotations.lock().map_err(|_| "无法获取评分与备注锁".to_string())?;
+    let history = state.history.lock().map_err(|_| "无法获取观看记录锁".to_string())?;
+    let user_data = UserData { tags: &tag_store, annotations: &annotations, history: &history };
+    let mut index = state.search_index.lock().map_err(|_| "无法获取搜索索引锁".to_string())?;
+    folder_manager.load_all_directory_trees();
+    let revision = user_data.revision(&folder_manager);
+    if !index.is_current(revision) {
+        index.rebuild(
+            revision,
+            folder_manager.directory_trees().into_iter().map(|(_, tree)| tree),
+            |video| user_data.document_extras(&folder_manager, &video.path),
+        );
+    }
+    let mut results = index.search(&query, &tags.unwrap_or_default(), limit, offset);
+    user_data.annotate(&folder_manager, &mut results.videos);
+    Ok(results)
+}
+
+// 在所有根文件夹的目录树中筛选满足查询的视频，并按指定方式排序
+fn select_videos(folder_manager: &mut FolderManager, user_data: &UserData, query: &Query, sort: QuerySort) -> Vec<VideoInfo> {
+    let mut videos = Vec::new();
+    visit_matching_videos(folder_manager, user_data, query, |video, annotation| {
+        let mut video = video.clone();
+        video.annotation = annotation.cloned();
+        videos.push(video);
+    });
+    videos.sort_by(|a, b| sort.compare(a, b));
+    videos
+}
+
+// 统计符合查询的视频数量（不复制视频信息，也不排序）
+fn count_videos(folder_manager: &mut FolderManager, user_data: &UserData, query: &Query) -> usize {
+    let mut count = 0;
+    visit_matching_videos(folder_manager, user_data, query, |_, _| count += 1);
+    count
+}
+
+// 遍历所有根文件夹中符合查询的视频
+fn visit_matching_videos(
+    folder_manager: &mut FolderManager,
+    user_data: &UserData,
+    query: &Query,
+    mut visit: impl FnMut(&VideoInfo, Option<&VideoAnnotation>),
+) {
+    folder_manager.load_all_directory_trees();
+    for (root_id, tree) in folder_manager.directory_trees() {
+        let root = folder_manager.get_root_folder(root_id);
+        let root_name = root.map(|root| root.name.as_str()).unwrap_or_default();
+        let storage_key = root.map(|root| root.storage_key()).unwrap_or_default();
+        let storage_prefix = root.map(|root| root.storage_prefix()).unwrap_or_default();
+        let root_path = std::path::Path::new(&tree.path);
+        for video in tree.all_videos() {
+            let relative_path = crate::persist::relative_key(root_path, &video.path).unwrap_or_default();
+            let storage_path = crate::persist::join_key(storage_prefix, &relative_path);
+            let video_tags = user_data.tags.effective_names(&storage_key, &storage_path);
+            let annotation = user_data.annotations.get(&storage_key, &storage_path);
+            let context = VideoContext {
+                video,
+                root_id,
+                root_name,
+                relative_path: &relative_path,
+                tags: &video_tags,
+                annotation,
+                watched: user_data.history.get(&storage_key, &storage_path).is_some_and(|state| state.watched),
+            };
+            if query.matches(&context) {
+                visit(video, annotation);
+            }
+        }
+    }
+}
+
+// Tauri命令：按查询表达式筛选视频（如 "h265 and height>=2160 and duration>90m and not tag:watched"）
+#[tauri::command]
+fn query_videos(state: State<AppState>, expr: String, sort: Option<QuerySort>) -> Result<Vec<VideoInfo>, String> {
+    let query = Query::parse(&expr).map_err(|e| e.to_string())?;
+    let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let tags = state.tags.lock().map_err(|_| "无法获取标签锁".to_string())?;
+    let annotations = state.annotations.lock().map_err(|_| "无法获取评分与备注锁".to_string())?;
+    let history = state.history.lock().map_err(|_| "无法获取观看记录锁".to_string())?;
+    let user_data = UserData { tags: &tags, annotations: &annotations, history: &history };
+    Ok(select_videos(&mut folder_manager, &user_data, &query, sort.unwrap_or_default()))
+}
+
+// 目录树或用户数据变化后重新计算智能合集的成员数量，返回是否有变化
+fn refresh_collection_counts(folder_manager: &mut FolderManager, user_data: &UserData, collections: &mut CollectionStore) -> bool {
+    folder_manager.load_all_directory_trees();
+    let revision = user_data.revision(folder_manager);
+    if collections.counts_current(revision) {
+        return false;
+    }
+    let counts = collections
+        .list()
+        .iter()
+        .filter_map(|collection| {
+            let query = collection.parse_query().ok()?;
+            Some((collection.id.clone(), count_videos(folder_manager, user_data, &query)))
+        })
+        .collect();
+    collections.set_counts(revision, &counts)
+}
+
+// Tauri命令：创建智能合集（保存查询表达式与排序方式）
+#[tauri::command]
+fn create_collection(state: State<AppState>, name: String, query: String, sort: Option<QuerySort>) -> Result<SmartCollection, String> {
+    let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let tags = state.tags.lock().map_err(|_| "无法获取标签锁".to_string())?;
+    let annotations = state.annotations.lock().map_err(|_| "无法获取评分与备注锁".to_string())?;
+    let history = state.history.lock().map_err(|_| "无法获取观看记录锁".to_string())?;
+    let user_data = UserData { tags: &tags, annotations: &annotations, history: &history };
+    let mut collections = state.collections.lock().map_err(|_| "无法获取智能合集锁".to_string())?;
+    refresh_collection_counts(&mut folder_manager, &user_data, &mut collections);
+    let mut collection = collections.create(&name, &query, sort.unwrap_or_default())?;
+    collection.video_count = count_videos(&mut folder_manager, &user_data, &collection.parse_query()?);
+    let revision = user_data.revision(&folder_manager);
+    collections.set_counts(revision, &std::collections::HashMap::from([(collection.id.clone(), collection.video_count)]));
+    println!("创建智能合集: {} ({} 个视频)", collection.name, collection.video_count);
+    Ok(collection)
+}
+
+// Tauri命令：获取所有智能合集（成员数量已按当前目录树更新）
+#[tauri::command]
+fn list_collections(state: State<AppState>) -> Result<Vec<SmartCollection>, String> {
+    let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let tags = state.tags.lock().map_err(|_| "无法获取标签锁".to_string())?;
+    let annotations = state.annotations.lock().map_err(|_| "无法获取评分与备注锁".to_string())?;
+    let history = state.history.lock().map_err(|_| "无法获取观看记录锁".to_string())?;
+    let user_data = UserData { tags: &tags, annotations: &annotations, history: &history };
+    let mut collections = state.collections.lock().map_err(|_| "无法获取智能合集锁".to_string())?;
+    refresh_collection_counts(&mut folder_manager, &user_data, &mut collections);
+    Ok(collections.list().to_vec())
+}
+
+// Tauri命令：获取智能合集的成员（按合集的排序方式）
+#[tauri::command]
+fn get_collection_items(state: State<AppState>, collection_id: String) -> Result<Vec<VideoInfo>, String> {
+    let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let collection = state
+        .collections
+        .lock()
+        .map_err(|_| "无法获取智能合集锁".to_string())?
+        .get(&collection_id)
+        .cloned()
+        .ok_or("智能合集不存在".to_string())?;
+    let tags = state.tags.lock().map_err(|_| "无法获取标签锁".to_string())?;
+    let annotations = state.annotations.lock().map_err(|_| "无法获取评分与备注锁".to_string())?;
+    let history = state.history.lock().map_err(|_| "无法获取观看记录锁".to_string())?;
+    let user_data = UserData { tags: &tags, annotations: &annotations, history: &history };
+    Ok(select_videos(&mut folder_manager, &user_data, &collection.parse_query()?, collection.sort))
+}
+
+// Tauri命令：删除智能合集
+#[tauri::command]
+fn delete_collection(state: State<AppState>, collection_id: String) -> Result<bool, String> {
+    let mut collections = state.collections.lock().map_err(|_| "无法获取智能合集锁".to_string())?;
+    collections.remove(&collection_id)
+}
+
+// Tauri命令：获取所有标签
+#[tauri::command]
+fn list_tags(state: State<AppState>) -> Result<Vec<Tag>, String> {
+    let tags = state.tags.lock().map_err(|_| "无法获取标签锁".to_string())?;
+    Ok(tags.list().to_vec())
+}
+
+// Tauri命令：创建标签（颜色为 #rrggbb，可省略）
+#[tauri::command]
+fn create_tag(state: State<AppState>, name: String, color: Option<String>) -> Result<Tag, String> {
+    let mut tags = state.tags.lock().map_err(|_| "无法获取标签锁".to_string())?;
+    tags.create(&name, color.as_deref())
+}
+
+// Tauri命令：修改标签名称或颜色
+#[tauri::command]
+fn update_tag(state: State<AppState>, tag_id: String, name: Option<String>, color: Option<String>) -> Result<Tag, String> {
+    let mut tags = state.tags.lock().map_err(|_| "无法获取标签锁".to_string())?;
+    tags.update(&tag_id, name.as_deref(), color.as_deref())
+}
+
+// Tauri命令：删除标签及其所有分配
+#[tauri::command]
+fn delete_tag(state: State<AppState>, tag_id: String) -> Result<bool, String> {
+    let mut tags = state.tags.lock().map_err(|_| "无法获取标签锁".to_string())?;
+    tags.delete(&tag_id)
+}
+
+// 视频或目录在根文件夹中的位置（存储键，相对路径）
+fn locate_in_root(folder_manager: &FolderManager, path: &std::path::Path) -> Result<(String, String), String> {
+    let root = folder_manager
+        .find_root_for_path(path)
+        .ok_or(format!("路径不在任何根文件夹内: {}", path.display()))?;
+    Ok((root.storage_key(), root.storage_path(path)?))
+}
+
+// 将视频或目录的绝对路径按所在根文件夹分组为（存储键 → 相对路径）
+fn group_paths_by_root(folder_manager: &FolderManager, paths: &[String]) -> Result<std::collections::BTreeMap<String, Vec<String>>, String> {
+    let mut groups: std::collections::BTreeMap<String, Vec<String>> = std::collections::BTreeMap::new();
+    for path in paths {
+        let (storage_key, relative_path) = locate_in_root(folder_manager, std::path::Path::new(path))?;
+        groups.entry(storage_key).or_default().push(relative_path);
+    }
+    Ok(groups)
+}
+
+// Tauri命令：为多个视频或目录批量添加标签（目录的标签对其中所有视频生效），返回新增的分配数
+#[tauri::command]
+fn assign_tags(state: State<AppState>, paths: Vec<String>, tag_ids: Vec<String>) -> Result<usize, String> {
+    let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let mut tags = state.tags.lock().map_err(|_| "无法获取标签锁".to_string())?;
+    let mut added = 0;
+    for (storage_key, relative_paths) in group_paths_by_root(&folder_manager, &paths)? {
+        added += tags.assign(&storage_key, &relative_paths, &tag_ids)?;
+    }
+    Ok(added)
+}
+
+// Tauri命令：从多个视频或目录批量移除标签，返回移除的分配数
+#[tauri::command]
+fn unassign_tags(state: State<AppState>, paths: Vec<String>, tag_ids: Vec<String>) -> Result<usize, String> {
+    let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let mut tags = state.tags.lock().map_err(|_| "无法获取标签锁".to_string())?;
+    let mut removed = 0;
+    for (storage_key, relative_paths) in group_paths_by_root(&folder_manager, &paths)? {
+        removed += tags.unassign(&storage_key, &relative_paths, &tag_ids)?;
+    }
+    Ok(removed)
+}
+
+// Tauri命令：获取根文件夹内所有路径的标签分配（相对路径 → 标签 ID，不含继承的标签）
+#[tauri::command]
+fn get_tag_assignments(state: State<AppState>, root_id: String) -> Result<std::collections::BTreeMap<String, Vec<String>>, String> {
+    let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let root = folder_manager
+        .get_root_folder(&root_id)
+        .ok_or("根文件夹不存在".to_string())?;
+    let tags = state.tags.lock().map_err(|_| "无法获取标签锁".to_string())?;
+    Ok(tags.root_assignments(&root.storage_key(), root.storage_prefix()))
+}
+
+// 按指纹找到的旧位置是否确实是改名或移动：原文件所在的卷必须在线且原文件已不存在，否则视为另一份副本
+fn was_moved(folder_manager: &FolderManager, storage_key: &str, relative_path: &str) -> bool {
+    folder_manager
+        .get_all_root_folders()
+        .iter()
+        .filter(|root| root.storage_key() == storage_key)
+        .find_map(|root| Some((root.path.is_dir(), root.resolve_storage_path(relative_path)?)))
+        .is_some_and(|(online, path)| online && !path.exists())
+}
+
+// 路径上没有记录时按文件指纹查找改名或移动前的记录；原文件已不存在时迁移到新路径
+fn recover_annotation(
+    folder_manager: &FolderManager,
+    annotations: &mut AnnotationStore,
+    location: (&str, &str),
+    fingerprint: &str,
+) -> Result<Option<VideoAnnotation>, String> {
+    let Some((old_key, old_path)) = annotations.find_by_fingerprint(fingerprint) else {
+        return Ok(None);
+    };
+    if !was_moved(folder_manager, &old_key, &old_path) {
+        return Ok(None);
+    }
+    println!("视频已改名或移动，迁移评分与备注: {} -> {}", old_path, location.1);
+    annotations.relocate((&old_key, &old_path), location)
+}
+
+// Tauri命令：获取视频的评分、收藏与备注（文件改名或移动后按指纹找回）
+#[tauri::command]
+fn get_video_annotation(state: State<AppState>, video_path: String) -> Result<Option<VideoAnnotation>, String> {
+    let path = PathBuf::from(&video_path);
+    let (storage_key, relative_path) = {
+        let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+        locate_in_root(&folder_manager, &path)?
+    };
+    {
+        let annotations = state.annotations.lock().map_err(|_| "无法获取评分与备注锁".to_string())?;
+        if let Some(annotation) = annotations.get(&storage_key, &relative_path) {
+            return Ok(Some(annotation.clone()));
+        }
+    }
+
+    // 计算指纹需要读取文件，不持有锁
+    let Ok(fingerprint) = crate::video::compute_fingerprint(&path) else {
+        return Ok(None);
+    };
+    let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let mut annotations = state.annotations.lock().map_err(|_| "无法获取评分与备注锁".to_string())?;
+    recover_annotation(&folder_manager, &mut annotations, (&storage_key, &relative_path), &fingerprint)
+}
+
+// 修改视频的评分、收藏或备注，返回修改后的内容
+fn edit_annotation(
+    state: &State<AppState>,
+    video_path: &str,
+    edit: impl FnOnce(&mut VideoAnnotation) -> Result<(), String>,
+) -> Result<VideoAnnotation, String> {
+    let path = PathBuf::from(video_path);
+    if !path.is_file() {
+        return Err("视频文件不存在".to_string());
+    }
+    let (storage_key, relative_path) = {
+        let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+        locate_in_root(&folder_manager, &path)?
+    };
+    let fingerprint = crate::video::compute_fingerprint(&path).ok();
+
+    let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let mut annotations = state.annotations.lock().map_err(|_| "无法获取评分与备注锁".to_string())?;
+    if let (None, Some(fingerprint)) = (annotations.get(&storage_key, &relative_path), fingerprint.as_deref()) {
+        recover_annotation(&folder_manager, &mut annotations, (&storage_key, &relative_path), fingerprint)?;
+    }
+    annotations.update(&storage_key, &relative_path, fingerprint, edit)
+}
+
+// Tauri命令：设置视频评分（1～5），rating 为空表示清除
+#[tauri::command]
+fn set_video_rating(state: State<AppState>, video_path: String, rating: Option<u8>) -> Result<VideoAnnotation, String> {
+    edit_annotation(&state, &video_path, |annotation| annotation.set_rating(rating))
+}
+
+// Tauri命令：收藏或取消收藏视频
+#[tauri::command]
+fn set_video_favorite(state: State<AppState>, video_path: String, favorite: bool) -> Result<VideoAnnotation, String> {
+    edit_annotation(&state, &video_path, |annotation| {
+        annotation.favorite = favorite;
+        Ok(())
+    })
+}
+
+// Tauri命令：设置视频备注，空白内容表示清除
+#[tauri::command]
+fn set_video_notes(state: State<AppState>, video_path: String, notes: String) -> Result<VideoAnnotation, String> {
+    edit_annotation(&state, &video_path, |annotation| annotation.set_notes(&notes))
+}
+
+// 路径上没有观看记录时按文件指纹找回改名或移动前的记录
+fn recover_history(folder_manager: &FolderManager, history: &mut HistoryStore, location: (&str, &str), fingerprint: &str) -> Result<(), String> {
+    if history.get(location.0, location.1).is_some() {
+        return Ok(());
+    }
+    let Some((old_key, old_path)) = history.find_by_fingerprint(fingerprint) else {
+        return Ok(());
+    };
+    if was_moved(folder_manager, &old_key, &old_path) {
+        println!("视频已改名或移动，迁移观看记录: {} -> {}", old_path, location.1);
+        history.relocate((&old_key, &old_path), location)?;
+    }
+    Ok(())
+}
+
+// 视频在观看记录中的位置（存储键，相对路径，文件指纹）
+type PlayLocation = (String, String, Option<String>);
+
+// 定位待播放视频的观看记录，并按文件指纹找回改名或移动前的记录；失败只打印日志，不影响播放
+fn locate_play_history(state: &State<AppState>, path: &std::path::Path) -> Option<PlayLocation> {
+    let (storage_key, relative_path) = locate_in_root(&*state.folder_manager.lock().ok()?, path).ok()?;
+    // 计算指纹需要读取文件，不持有锁
+    let fingerprint = crate::video::compute_fingerprint(path).ok();
+    if let (Some(fingerprint), Ok(folder_manager), Ok(mut history)) =
+        (fingerprint.as_deref(), state.folder_manager.lock(), state.history.lock())
+    {
+        if let Err(e) = recover_history(&folder_manager, &mut history, (&storage_key, &relative_path), fingerprint) {
+            println!("找回观看记录失败: {}", e);
+        }
+    }
+    Some((storage_key, relative_path, fingerprint))
+}
+
+// 记录一次播放；失败只打印日志，不影响播放
+fn record_play(state: &State<AppState>, (storage_key, relative_path, fingerprint): PlayLocation) {
+    let Ok(mut history) = state.history.lock() else {
+        return;
+    };
+    if let Err(e) = history.record_play(&storage_key, &relative_path, fingerprint) {
+        println!("记录播放失败: {}", e);
+    }
+}
+
+// Tauri命令：获取视频的观看状态（文件改名或移动后按指纹找回）
+#[tauri::command]
+fn get_watch_state(state: State<AppState>, video_path: String) -> Result<Option<WatchState>, String> {
+    let path = PathBuf::from(&video_path);
+    let (storage_key, relative_path) = {
+        let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+        locate_in_root(&folder_manager, &path)?
+    };
+    {
+        let history = state.history.lock().map_err(|_| "无法获取观看记录锁".to_string())?;
+        if let Some(watch_state) = history.get(&storage_key, &relative_path) {
+            return Ok(Some(watch_state.clone()));
+        }
+    }
+
+    let Ok(fingerprint) = crate::video::compute_fingerprint(&path) else {
+        return Ok(None);
+    };
+    let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let mut history = state.history.lock().map_err(|_| "无法获取观看记录锁".to_string())?;
+    recover_history(&folder_manager, &mut history, (&storage_key, &relative_path), &fingerprint)?;
+    Ok(history.get(&storage_key, &relative_path).cloned())
+}
+
+// Tauri命令：播放器回报播放进度（秒），更新续播位置；接近结尾时自动标记为已看，stopped 表示播放已结束
+#[tauri::command]
+fn report_playback(
+    state: State<AppState>,
+    video_path: String,
+    position_secs: f64,
+    duration_secs: Option<f64>,
+    watched_secs: Option<f64>,
+    stopped: Option<bool>,
+) -> Result<WatchState, String> {
+    let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let (storage_key, relative_path) = locate_in_root(&folder_manager, std::path::Path::new(&video_path))?;
+    let mut history = state.history.lock().map_err(|_| "无法获取观看记录锁".to_string())?;
+    let watch_state = history.report_progress(&storage_key, &relative_path, position_secs, duration_secs, watched_secs)?;
+    // 播放结束（关闭播放器）时立即写入
+    if stopped.unwrap_or(false) {
+        history.flush()?;
+    }
+    Ok(watch_state)
+}
+
+// Tauri命令：批量标记视频为已看或未看
+#[tauri::command]
+fn set_watched(state: State<AppState>, video_paths: Vec<String>, watched: bool) -> Result<(), String> {
+    let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let groups = group_paths_by_root(&folder_manager, &video_paths)?;
+    let mut history = state.history.lock().map_err(|_| "无法获取观看记录锁".to_string())?;
+    history.set_watched(&groups, watched)
+}
+
+// 将观看记录解析为视频：根文件夹已移除的记录跳过，目录树中找不到的视频只返回路径
+fn history_videos<'a>(
+    folder_manager: &mut FolderManager,
+    annotations: &AnnotationStore,
+    items: impl IntoIterator<Item = crate::history::HistoryItem<'a>>,
+    limit: usize,
+) -> Vec<HistoryVideo> {
+    folder_manager.load_all_directory_trees();
+    let roots = folder_manager.get_all_root_folders();
+    let trees = folder_manager.directory_trees();
+    items
+        .into_iter()
+        .filter_map(|(storage_key, relative_path, watch_state)| {
+            let (root, path) = roots
+                .iter()
+                .filter(|root| root.storage_key() == storage_key)
+                .find_map(|root| Some((root, root.resolve_storage_path(relative_path)?)))?;
+            let mut video = trees
+                .iter()
+                .find(|(root_id, _)| *root_id == root.id)
+                .and_then(|(_, tree)| tree.find(&path.parent()?.to_string_lossy()))
+                .and_then(|node| node.videos.iter().find(|video| video.path == path))
+                .cloned();
+            if let Some(video) = video.as_mut() {
+                video.annotation = annotations.get(storage_key, relative_path).cloned();
+            }
+            Some(HistoryVideo {
+                path,
+                root_id: root.id.clone(),
+                video,
+                state: watch_state.clone(),
+            })
+        })
+        .take(limit)
+        .collect()
+}
+
+// Tauri命令：最近播放的视频
+#[tauri::command]
+fn get_recently_played(state: State<AppState>, limit: usize) -> Result<Vec<HistoryVideo>, String> {
+    let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let annotations = state.annotations.lock().map_err(|_| "无法获取评分与备注锁".to_string())?;
+    let history = state.history.lock().map_err(|_| "无法获取观看记录锁".to_string())?;
+    Ok(history_videos(&mut folder_manager, &annotations, history.recently_played(), limit))
+}
+
+// Tauri命令：可以继续观看的视频（有续播位置且未看完）
+#[tauri::command]
+fn get_continue_watching(state: State<AppState>, limit: usize) -> Result<Vec<HistoryVideo>, String> {
+    let mut folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+    let annotations = state.annotations.lock().map_err(|_| "无法获取评分与备注锁".to_string())?;
+    let history = state.history.lock().map_err(|_| "无法获取观看记录锁".to_string())?;
+    Ok(history_videos(&mut folder_manager, &annotations, history.continue_watching(), limit))
+}
+
+// Tauri命令：清空播放记录与续播位置（保留已看标记）
+#[tauri::command]
+fn clear_watch_history(state: State<AppState>) -> Result<(), String> {
+    let mut history = state.history.lock().map_err(|_| "无法获取观看记录锁".to_string())?;
+    history.clear_plays()
+}
+
+// Tauri命令：获取全局设置
+#[tauri::command]
+fn get_settings(state: State<AppState>) -> Result<AppSettings, String> {
+    let settings = state.settings.lock().map_err(|_| "无法获取设置锁".to_string())?;
+    Ok(settings.get().clone())
+}
+
+// Tauri命令：更新全局设置（可只传需要修改的部分），校验通过后立即生效并发出 settings-changed 事件
+#[tauri::command]
+fn update_settings(app: tauri::AppHandle, state: State<AppState>, patch: serde_json::Value) -> Result<AppSettings, String> {
+    let root_ids: Vec<String> = state
+        .folder_manager
+        .lock()
+        .map_err(|_| "无法获取文件夹管理器锁".to_string())?
+        .get_all_root_folders()
+        .into_iter()
+        .map(|root| root.id)
+        .collect();
+    let settings = state
+        .settings
+        .lock()
+        .map_err(|_| "无法获取设置锁".to_string())?
+        .update(patch, &root_ids)?;
+    state.apply_settings(&settings)?;
+    println!("设置已更新");
+    let _ = app.emit("settings-changed", &settings);
+    Ok(settings)
+}
+
+// Tauri命令：获取根文件夹所在卷的信息（挂载点、文件系统类型与剩余空间）
+#[tauri::command]
+fn get_volume_info(state: State<AppState>, root_id: String) -> Result<VolumeInfo, String> {
+    load_volume_info(&state, &root_id)
+}
+
+// 获取根文件夹所在卷的信息，离线时使用登记表中的记录
+fn load_volume_info(state: &State<AppState>, root_id: &str) -> Result<VolumeInfo, String> {
+    let (root, state_dir) = {
+        let folder_manager = state.folder_manager.lock().map_err(|_| "无法获取文件夹管理器锁".to_string())?;
+        let root = folder_manager
+            .get_root_folder(root_id)
+            .cloned()
+            .ok_or("根文件夹不存在".to_string())?;
+        (root, folder_manager.data_state_dir(root_id))
+    };
+
+    if root.path.is_dir() {
+        return crate::volume::get_volume_info(&root.path, state_dir.as_deref());
+    }
+
+    // 离线时返回登记表中最后一次记录的信息
+    let volume_key = root.volume_key.ok_or("根文件夹所在的卷不在线".to_string())?;
+    let registry = state.volume_registry.lock().map_err(|_| "无法获取卷登记表锁".to_string())?;
+    Ok(registry.offline_info(&volume_key))
+}
+
+// Tauri命令：获取根文件夹的存储报告（目录占用、按编码/分辨率/容器分组、最大文件与卷剩余空间）
+#[tauri::command]
+fn get_storage_report(state: State<AppState>, root_id: String) -> Result<StorageReport, String> {
+    let tree = state
+        .folder_manager
+        .lock()
+        .map_err(|_| "无法获取文件夹管理器锁".to_string())?
+        .get_or_load_directory_tree(&root_id)
+        .cloned()
+        .ok_or("请先扫描根文件夹".to_string())?;
+
+    let volume = match load_volume_info(&state, &root_id) {
+        Ok(volume) => Some(volume),
+        Err(e) => {
+            println!("获取卷信息失败: {}", e);
+            None
+        }
+    };
+    Ok(crate::storage_report::build_report(&root_id, &tree, volume))
+}
+
+// Tauri命令：列出已知卷（含离线卷的最后在线时间与容量）
+#[tauri::command]
+fn list_volumes(state: State<AppState>) -> Result<Vec<VolumeRecord>, String> {
+    let registry = state.volume_registry.lock().map_err(|_| "无法获取卷登记表锁".to_string())?;
+    Ok(registry.list())
+}
+
+// Tauri命令：立即检查卷状态，并重新绑定挂载路径发生变化的根文件夹
+#[tauri::command]
+fn relocate_roots(app: tauri::AppHandle) -> Result<Vec<RootRelocatedEvent>, String> {
+    Ok(check_volumes(&app))
+}
+
+// 检查所有根文件夹所在卷的在线状态，状态变化时发送事件；
+// 离线的根文件夹若在其他挂载点找到相同的卷标识文件，则重新绑定到新路径
+fn check_volumes(app: &tauri::AppHandle) -> Vec<RootRelocatedEvent> {
+    let state = app.state::<AppState>();
+    let Ok(mut folder_manager) = state.folder_manager.lock() else {
+        return Vec::new();
+    };
+    let Ok(mut registry) = state.volume_registry.lock() else {
+        return Vec::new();
+    };
+
+    let mut roots = folder_manager.get_all_root_folders();
+    // 只有存在离线根文件夹时才读取挂载表
+    let mounts = if roots.iter().any(|root| !root.path.is_dir()) {
+        crate::mount::list_mounts()
+    } else {
+        Vec::new()
+    };
+
+    let mut events = Vec::new();
+    let mut relocations = Vec::new();
+    for root in roots.iter_mut() {
+        if !root.path.is_dir() {
+            // 硬盘可能挂载到了新路径（如 /media/user/Movies1），根据卷标识文件重新绑定
+            let new_path = root
+                .volume_key
+                .as_deref()
+                .and_then(|key| crate::volume::find_relocated_root(key, &root.path, &mounts));
+            if let Some(new_path) = new_path {
+                if let Some(old_path) = folder_manager.relocate_root(&root.id, new_path.clone()) {
+                    println!("根文件夹已重新绑定: {} -> {}", old_path.display(), new_path.display());
+                    relocations.push(RootRelocatedEvent {
+                        root_id: root.id.clone(),
+                        old_path,
+                        new_path: new_path.clone(),
+                    });
+                    root.path = new_path;
+                }
+            }
+        }
+
+        let available = root.path.is_dir();
+        if available == root.online {
+            continue;
+        }
+
+        if available {
+            // 同一路径可能挂载了另一块硬盘，卷标识一致才视为重新上线
+            let state_dir = folder_manager.data_state_dir(&root.id);
+            let current_key = crate::volume::read_volume_key(&root.path, state_dir.as_deref()).ok();
+            let legacy = root.volume_key.as_deref().is_some_and(crate::volume::is_legacy_unix_key);
+            if root.volume_key.is_some() && current_key != root.volume_key && !legacy {
+                continue;
+            }
+            // 旧版本的设备号标识每次挂载都会变化，重新上线时升级为当前标识
+            if let (true, Some(volume_key)) = (legacy, &current_key) {
+                let volume_path = crate::volume::volume_path(volume_key, &root.path).unwrap_or_default();
+                folder_manager.set_volume_key(&root.id, volume_key.clone(), volume_path);
+                root.volume_key = Some(volume_key.clone());
+            }
+            if let Some(volume_key) = &current_key {
+                registry.mark_seen(volume_key, &root.path);
+            }
+        } else if let Some(volume_key) = &root.volume_key {
+            registry.mark_offline(volume_key);
+        }
+
+        folder_manager.set_root_online(&root.id, available);
+        events.push(VolumeStatusEvent {
+            root_id: root.id.clone(),
+            volume_key: root.volume_key.clone(),
+            path: root.path.clone(),
+            online: available,
+        });
+    }
+
+    if events.is_empty() && relocations.is_empty() {
+        return relocations;
+    }
+    if let Err(e) = registry.save() {
+        println!("保存卷登记表失败: {}", e);
+    }
+    drop(registry);
+    drop(folder_manager);
+
+    for event in &relocations {
+        if let Err(e) = app.emit("root-relocated", event) {
+            println!("发送根文件夹重新绑定事件失败: {}", e);
+        }
+    }
+    for event in events {
+        println!("卷状态变化: {} -> {}", event.path.display(), if event.online { "在线" } else { "离线" });
+        let name = if event.online { "volume-online" } else { "volume-offline" };
+        if let Err(e) = app.emit(name, &event) {
+            println!("发送卷状态事件失败: {}", e);
+        }
+    }
+    relocations
+}
+
+// 启动后台线程，定期检查卷的在线状态
+fn start_volume_monitor(app: tauri::AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(VOLUME_CHECK_INTERVAL);
+        check_volumes(&app);
+    });
 }
 
 // Tauri命令：获取相对路径
@@ -412,8 +1972,13 @@ pub fn run() {
                 .build(),
         )
         .plugin(tauri_plugin_dialog::init())
-        .manage(AppState::new())
-        .setup(|_app| {
+        .register_uri_scheme_protocol("vvpreview", handle_preview_protocol)
+        .setup(|app| {
+            let data_dir = app.path().app_data_dir()?;
+            let config_dir = app.path().app_config_dir()?;
+            let cache_dir = app.path().app_cache_dir()?;
+            app.manage(AppState::new(data_dir, config_dir, cache_dir));
+            start_volume_monitor(app.handle().clone());
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -421,21 +1986,82 @@ pub fn run() {
             add_root_folder_deterministic,
             remove_root_folder,
             get_root_folders,
+            rename_root_folder,
+            set_root_enabled,
+            set_root_max_depth,
+            set_root_scan_options,
             scan_directory,
             get_directory_tree,
+            list_directory,
+            get_tree_outline,
             get_video_info,
             get_cover_info,
             open_video,
+            play_video,
             execute_command,
             find_cover_for_video,
+            set_custom_cover,
+            clear_custom_cover,
+            list_custom_covers,
+            generate_storyboard,
+            generate_previews,
+            get_preview_path,
+            get_preview_job_status,
+            cancel_preview_jobs,
             get_absolute_path,
             get_volume_key,
+            list_volumes,
+            get_volume_info,
+            check_root_overlap,
+            export_catalog,
+            import_catalog,
+            get_storage_report,
+            get_settings,
+            update_settings,
+            search_videos,
+            query_videos,
+            create_collection,
+            list_collections,
+            get_collection_items,
+            delete_collection,
+            list_tags,
+            create_tag,
+            update_tag,
+            delete_tag,
+            assign_tags,
+            unassign_tags,
+            get_tag_assignments,
+            get_video_annotation,
+            set_video_rating,
+            set_video_favorite,
+            set_video_notes,
+            get_watch_state,
+            report_playback,
+            set_watched,
+            get_recently_played,
+            get_continue_watching,
+            clear_watch_history,
+            get_playback_status,
+            playback_seek,
+            get_playback_chapters,
+            playback_seek_chapter,
+            stop_playback,
+            get_storage_policy,
+            set_read_only_mode,
+            migrate_root_state,
+            relocate_roots,
             to_relative_path,
             read_image_as_base64,
             check_file_exists,
             rescan_directory,
             open_folder,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            // 退出前写入内存中的播放进度
+            if let tauri::RunEvent::Exit = event {
+                flush_history(&app.state::<AppState>());
+            }
+        });
 }
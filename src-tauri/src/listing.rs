@@ -0,0 +1,226 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::collation::natural_cmp;
+use crate::folder::DirectoryNode;
+use crate::persist;
+use crate::query::{QuerySort, SortKey};
+use crate::video::VideoInfo;
+
+/// 单页最多返回的条目数
+const MAX_PAGE_SIZE: usize = 1000;
+
+/// 子目录摘要（不含其中的视频）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectorySummary {
+    pub name: String,
+    /// 相对根文件夹的 `/` 分隔路径
+    pub rel_path: String,
+    /// 视频数量（包含子目录）
+    pub video_count: usize,
+    /// 封面数量（包含子目录）
+    pub cover_count: usize,
+    pub cover_path: Option<PathBuf>,
+    pub modified_time: Option<std::time::SystemTime>,
+    /// 视频总大小（包含子目录）
+    pub total_bytes: u64,
+    /// 直接子目录数量
+    pub child_count: usize,
+}
+
+/// 单层目录内容：子目录在前、视频在后，分页作用于两者合并后的序列
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryListing {
+    pub name: String,
+    pub rel_path: String,
+    /// 当前页的子目录
+    pub directories: Vec<DirectorySummary>,
+    /// 当前页的视频
+    pub videos: Vec<VideoInfo>,
+    /// 子目录总数
+    pub total_directories: usize,
+    /// 当前目录下的视频总数（不含子目录）
+    pub total_videos: usize,
+    pub offset: usize,
+}
+
+/// 目录树大纲：只包含数量，不含视频信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeOutline {
+    pub name: String,
+    pub rel_path: String,
+    pub video_count: usize,
+    pub cover_count: usize,
+    pub children: Vec<TreeOutline>,
+}
+
+/// 各目录（按节点路径）中所有视频（包含子目录）的总大小与总时长
+pub type DirectoryTotals = HashMap<String, (u64, Duration)>;
+
+/// 按根文件夹缓存目录总计，目录树版本变化后才重新计算，翻页时无需重复遍历子树
+#[derive(Default)]
+pub struct TotalsCache {
+    /// 根文件夹 ID → (目录树版本, 目录总计)
+    roots: HashMap<String, (u64, DirectoryTotals)>,
+}
+
+impl TotalsCache {
+    /// 获取根文件夹目录树的总计，缓存的版本与 `revision` 不一致时重新计算
+    pub fn get(&mut self, root_id: &str, revision: u64, tree: &DirectoryNode) -> &DirectoryTotals {
+        let entry = self.roots.entry(root_id.to_string()).or_default();
+        if entry.0 != revision || entry.1.is_empty() {
+            entry.1.clear();
+            collect_totals(tree, &mut entry.1);
+            entry.0 = revision;
+        }
+        &entry.1
+    }
+}
+
+/// 自底向上计算每个目录的总计，每个节点只访问一次
+fn collect_totals(node: &DirectoryNode, totals: &mut DirectoryTotals) -> (u64, Duration) {
+    let own = node.videos.iter().fold((0, Duration::ZERO), |(bytes, duration), video| {
+        (bytes + video.size, duration + video.duration.unwrap_or_default())
+    });
+    let total = node.children.iter().fold(own, |(bytes, duration), child| {
+        let (child_bytes, child_duration) = collect_totals(child, totals);
+        (bytes + child_bytes, duration + child_duration)
+    });
+    totals.insert(node.path.clone(), total);
+    total
+}
+
+/// 列出目录的一层内容，按指定方式排序并分页（子目录的总大小与总时长取自 `totals`）；`prepare` 在排序前处理当前目录的视频（如附加评分）
+pub fn list_directory(
+    root: &Path,
+    node: &DirectoryNode,
+    totals: &DirectoryTotals,
+    sort: QuerySort,
+    offset: usize,
+    limit: usize,
+    prepare: impl FnOnce(&mut [VideoInfo]),
+) -> DirectoryListing {
+    let mut directories: Vec<(DirectorySummary, Duration)> = node
+        .children
+        .iter()
+        .map(|child| {
+            let (total_bytes, total_duration) = totals.get(&child.path).copied().unwrap_or_default();
+            let summary = DirectorySummary {
+                name: child.name.clone(),
+                rel_path: persist::relative_key(root, Path::new(&child.path)).unwrap_or_default(),
+                video_count: child.video_count,
+                cover_count: child.cover_count,
+                cover_path: child.cover_path.clone(),
+                modified_time: child.modified_time,
+                total_bytes,
+                child_count: child.children.len(),
+            };
+            (summary, total_duration)
+        })
+        .collect();
+    directories.sort_by(|(a, a_duration), (b, b_duration)| {
+        let ordering = match sort.key {
+            SortKey::Size => a.total_bytes.cmp(&b.total_bytes),
+            SortKey::Duration => a_duration.cmp(b_duration),
+            SortKey::Modified => a.modified_time.cmp(&b.modified_time),
+            _ => Ordering::Equal,
+        };
+        let ordering = if sort.descending { ordering.reverse() } else { ordering };
+        // 名称排序或其他字段相同时按名称自然排序
+        ordering.then_with(|| {
+            let by_name = natural_cmp(&a.name, &b.name);
+            if sort.key == SortKey::Name && sort.descending { by_name.reverse() } else { by_name }
+        })
+    });
+
+    let mut videos = node.videos.clone();
+    prepare(&mut videos);
+    videos.sort_by(|a, b| sort.compare(a, b));
+
+    let limit = limit.min(MAX_PAGE_SIZE);
+    let total_directories = directories.len();
+    let directory_page: Vec<DirectorySummary> = directories
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .map(|(summary, _)| summary)
+        .collect();
+    let video_offset = offset.saturating_sub(total_directories);
+    let video_page: Vec<VideoInfo> = videos
+        .into_iter()
+        .skip(video_offset)
+        .take(limit - directory_page.len())
+        .collect();
+
+    DirectoryListing {
+        name: node.name.clone(),
+        rel_path: persist::relative_key(root, Path::new(&node.path)).unwrap_or_default(),
+        directories: directory_page,
+        total_videos: node.videos.len(),
+        videos: video_page,
+        total_directories,
+        offset,
+    }
+}
+
+/// 生成目录树大纲，`max_depth` 限制展开的层数（None 表示不限制），子目录按名称自然排序
+pub fn outline(root: &Path, node: &DirectoryNode, max_depth: Option<u32>) -> TreeOutline {
+    let mut children: Vec<&DirectoryNode> = if max_depth == Some(0) { Vec::new() } else { node.children.iter().collect() };
+    children.sort_by(|a, b| natural_cmp(&a.name, &b.name));
+    TreeOutline {
+        name: node.name.clone(),
+        rel_path: persist::relative_key(root, Path::new(&node.path)).unwrap_or_default(),
+        video_count: node.video_count,
+        cover_count: node.cover_count,
+        children: children
+            .into_iter()
+            .map(|child| outline(root, child, max_depth.map(|depth| depth - 1)))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{dir, video};
+
+    #[test]
+    fn test_list_directory_sorting_and_pagination() {
+        let sized = |path: &str, size: u64| VideoInfo { size, ..video(path) };
+        let root = dir(
+            "/lib",
+            vec![
+                dir("/lib/Season 10", Vec::new(), vec![sized("/lib/Season 10/a.mkv", 10)]),
+                dir("/lib/Season 2", Vec::new(), vec![sized("/lib/Season 2/a.mkv", 500)]),
+            ],
+            vec![sized("/lib/ep10.mkv", 1), sized("/lib/ep2.mkv", 3), sized("/lib/ep1.mkv", 2)],
+        );
+        let root_path = Path::new("/lib");
+        let mut cache = TotalsCache::default();
+        let totals = cache.get("root-1", 1, &root).clone();
+        assert_eq!(totals["/lib"].0, 516);
+
+        let listing = list_directory(root_path, &root, &totals, QuerySort::default(), 0, 3, |_| {});
+        assert_eq!(listing.total_directories, 2);
+        assert_eq!(listing.total_videos, 3);
+        assert_eq!(listing.directories[0].rel_path, "Season 2");
+        assert_eq!(listing.directories[0].total_bytes, 500);
+        assert_eq!(listing.videos.iter().map(|v| v.name.as_str()).collect::<Vec<_>>(), vec!["ep1.mkv"]);
+
+        let listing = list_directory(root_path, &root, &totals, QuerySort::default(), 3, 3, |_| {});
+        assert!(listing.directories.is_empty());
+        assert_eq!(listing.videos.iter().map(|v| v.name.as_str()).collect::<Vec<_>>(), vec!["ep2.mkv", "ep10.mkv"]);
+
+        let by_size = QuerySort { key: SortKey::Size, descending: true };
+        let listing = list_directory(root_path, &root, &totals, by_size, 0, 10, |_| {});
+        assert_eq!(listing.directories[0].name, "Season 2");
+        assert_eq!(listing.videos[0].name, "ep2.mkv");
+
+        let tree = outline(root_path, &root, Some(0));
+        assert_eq!(tree.video_count, 5);
+        assert!(tree.children.is_empty());
+        assert_eq!(outline(root_path, &root, None).children[1].rel_path, "Season 10");
+    }
+}
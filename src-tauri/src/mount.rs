@@ -0,0 +1,301 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// 不可能存放媒体文件的伪文件系统
+const PSEUDO_FS_TYPES: [&str; 17] = [
+    "proc", "sysfs", "devtmpfs", "devpts", "tmpfs", "cgroup", "cgroup2", "securityfs",
+    "pstore", "debugfs", "tracefs", "configfs", "mqueue", "hugetlbfs", "bpf", "autofs", "overlay",
+];
+
+/// 网络文件系统类型
+const NETWORK_FS_TYPES: [&str; 12] = [
+    "cifs", "smb3", "smbfs", "nfs", "nfs4", "afpfs", "9p", "ncpfs", "davfs", "fuse.sshfs", "fuse.rclone", "fuse.davfs2",
+];
+
+/// 根文件夹所在的挂载类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MountType {
+    /// 本地磁盘或可移动硬盘
+    Local,
+    /// 网络共享（SMB/NFS 等）
+    Network,
+    /// 无法判断
+    #[default]
+    Unknown,
+}
+
+/// 挂载点信息
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MountEntry {
+    /// 挂载点路径
+    pub mount_point: PathBuf,
+    /// 文件系统类型（ext4、ntfs3、cifs 等）
+    pub fs_type: String,
+    /// 挂载源（设备或网络地址）
+    pub source: String,
+    /// 设备号 major:minor（仅 Linux）
+    pub device: Option<String>,
+}
+
+impl MountEntry {
+    /// 是否为伪文件系统
+    pub fn is_pseudo(&self) -> bool {
+        PSEUDO_FS_TYPES.contains(&self.fs_type.as_str())
+    }
+
+    /// 是否为网络文件系统
+    pub fn is_network(&self) -> bool {
+        NETWORK_FS_TYPES.contains(&self.fs_type.as_str())
+    }
+}
+
+/// 解析 mountinfo 中的转义字符（空格为 \040、制表符为 \011 等）
+fn unescape_mount_field(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 3 < bytes.len() && bytes[i + 1..i + 4].iter().all(|b| (b'0'..=b'7').contains(b)) {
+            let code = (bytes[i + 1] - b'0') * 64 + (bytes[i + 2] - b'0') * 8 + (bytes[i + 3] - b'0');
+            out.push(code);
+            i += 4;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// 解析 `/proc/self/mountinfo` 的内容。
+/// 行格式：`挂载ID 父ID major:minor 根 挂载点 选项 [可选字段...] - 类型 源 超级块选项`
+pub fn parse_mountinfo(content: &str) -> Vec<MountEntry> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (left, right) = line.split_once(" - ")?;
+            let left: Vec<&str> = left.split(' ').collect();
+            let mut right = right.split(' ');
+            if left.len() < 5 {
+                return None;
+            }
+            let fs_type = right.next()?.to_string();
+            let source = unescape_mount_field(right.next().unwrap_or(""));
+            Some(MountEntry {
+                mount_point: PathBuf::from(unescape_mount_field(left[4])),
+                fs_type,
+                source,
+                device: Some(left[2].to_string()),
+            })
+        })
+        .collect()
+}
+
+/// 列出当前系统的挂载点（已排除伪文件系统）
+pub fn list_mounts() -> Vec<MountEntry> {
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read_to_string("/proc/self/mountinfo")
+            .map(|content| parse_mountinfo(&content))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|entry| !entry.is_pseudo())
+            .collect()
+    }
+
+    #[cfg(all(unix, not(target_os = "linux")))]
+    {
+        // macOS 等系统：外置卷挂载在 /Volumes 下
+        let mut mounts = vec![MountEntry {
+            mount_point: PathBuf::from("/"),
+            fs_type: String::new(),
+            source: String::new(),
+            device: None,
+        }];
+        if let Ok(entries) = std::fs::read_dir("/Volumes") {
+            mounts.extend(entries.flatten().map(|entry| MountEntry {
+                mount_point: entry.path(),
+                fs_type: String::new(),
+                source: String::new(),
+                device: None,
+            }));
+        }
+        mounts
+    }
+
+    #[cfg(windows)]
+    {
+        (b'A'..=b'Z')
+            .map(|letter| PathBuf::from(format!("{}:\\", letter as char)))
+            .filter(|path| path.exists())
+            .map(|mount_point| MountEntry {
+                mount_point,
+                fs_type: String::new(),
+                source: String::new(),
+                device: None,
+            })
+            .collect()
+    }
+}
+
+/// 查找包含指定路径的挂载点（最长前缀匹配）
+pub fn find_mount_for_path<'a>(mounts: &'a [MountEntry], path: &Path) -> Option<&'a MountEntry> {
+    mounts
+        .iter()
+        .filter(|entry| path.starts_with(&entry.mount_point))
+        .max_by_key(|entry| entry.mount_point.components().count())
+}
+
+/// 判断路径所在的挂载类型与文件系统类型。
+/// Windows 上的 UNC 路径（`\\server\share`）视为网络共享；Linux 根据 mountinfo 中的文件系统类型判断。
+pub fn detect_mount_type(path: &Path) -> (MountType, Option<String>) {
+    #[cfg(windows)]
+    {
+        use std::path::{Component, Prefix};
+        if let Some(Component::Prefix(prefix)) = path.components().next() {
+            if matches!(prefix.kind(), Prefix::UNC(..) | Prefix::VerbatimUNC(..)) {
+                return (MountType::Network, None);
+            }
+        }
+    }
+
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mounts = list_mounts();
+    match find_mount_for_path(&mounts, &path) {
+        Some(mount) if mount.fs_type.is_empty() => (MountType::Unknown, None),
+        Some(mount) if mount.is_network() => (MountType::Network, Some(mount.fs_type.clone())),
+        Some(mount) => (MountType::Local, Some(mount.fs_type.clone())),
+        None => (MountType::Unknown, None),
+    }
+}
+
+/// 文件系统的 UUID 与卷标
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FilesystemIds {
+    pub uuid: Option<String>,
+    pub label: Option<String>,
+}
+
+/// 还原 udev 链接名中的转义字符（如卷标中的空格为 `\x20`）
+fn decode_udev_name(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 3 < bytes.len() && bytes[i + 1] == b'x' {
+            let hex = std::str::from_utf8(&bytes[i + 2..i + 4]).ok();
+            if let Some(code) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                out.push(code);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// 在 `/dev/disk/by-uuid`、`/dev/disk/by-label` 这类目录中查找指向该挂载源的链接名。
+/// 先比较链接目标与挂载源，再比较 `/sys/class/block/<设备>/dev` 中的设备号（兼容 device-mapper 等别名）。
+#[cfg(target_os = "linux")]
+fn find_disk_link(dir: &Path, entry: &MountEntry) -> Option<String> {
+    let source = std::fs::canonicalize(&entry.source).ok();
+    std::fs::read_dir(dir).ok()?.flatten().find_map(|link| {
+        let target = std::fs::canonicalize(link.path()).ok()?;
+        let matches_source = source.as_ref() == Some(&target);
+        let matches_device = entry.device.as_deref().is_some_and(|device| {
+            target
+                .file_name()
+                .and_then(|name| std::fs::read_to_string(Path::new("/sys/class/block").join(name).join("dev")).ok())
+                .is_some_and(|dev| dev.trim() == device)
+        });
+        (matches_source || matches_device).then(|| decode_udev_name(&link.file_name().to_string_lossy()))
+    })
+}
+
+/// 获取挂载点所在文件系统的 UUID 与卷标（仅 Linux，通过 udev 维护的 `/dev/disk/by-*` 链接）
+pub fn filesystem_ids(entry: &MountEntry) -> FilesystemIds {
+    #[cfg(target_os = "linux")]
+    {
+        FilesystemIds {
+            uuid: find_disk_link(Path::new("/dev/disk/by-uuid"), entry),
+            label: find_disk_link(Path::new("/dev/disk/by-label"), entry),
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = entry;
+        FilesystemIds::default()
+    }
+}
+
+/// 根据旧路径生成在新挂载点下的候选路径：挂载点本身，以及依次拼接旧路径末尾的 1..n 级目录
+pub fn relocation_candidates(mount_point: &Path, old_path: &Path) -> Vec<PathBuf> {
+    let components: Vec<_> = old_path
+        .components()
+        .filter(|c| matches!(c, std::path::Component::Normal(_)))
+        .collect();
+    let mut candidates = vec![mount_point.to_path_buf()];
+    for start in (0..components.len()).rev() {
+        let candidate = components[start..]
+            .iter()
+            .fold(mount_point.to_path_buf(), |path, c| path.join(c.as_os_str()));
+        candidates.push(candidate);
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mountinfo() {
+        let content = "\
+22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw
+25 22 0:5 / /proc rw,nosuid - proc proc rw
+40 22 8:17 / /media/alice/My\\040Movies rw,nosuid shared:30 - exfat /dev/sdb1 rw,uid=1000
+";
+        let mounts = parse_mountinfo(content);
+        assert_eq!(mounts.len(), 3);
+        assert!(mounts[1].is_pseudo());
+        assert!(!mounts[2].is_network());
+        assert_eq!(mounts[2].mount_point, PathBuf::from("/media/alice/My Movies"));
+        assert_eq!(mounts[2].fs_type, "exfat");
+        assert_eq!(mounts[2].source, "/dev/sdb1");
+        assert_eq!(mounts[2].device.as_deref(), Some("8:17"));
+
+        let found = find_mount_for_path(&mounts, Path::new("/media/alice/My Movies/anime")).unwrap();
+        assert_eq!(found.device.as_deref(), Some("8:17"));
+        assert_eq!(find_mount_for_path(&mounts, Path::new("/home/alice")).unwrap().fs_type, "ext4");
+    }
+
+    #[test]
+    fn test_network_mount_detection() {
+        let content = "\
+50 22 0:60 / /mnt/nas rw,relatime shared:40 - cifs //nas/videos rw,vers=3.0
+51 22 0:61 / /mnt/nfs rw,relatime shared:41 - nfs4 nas:/export rw
+";
+        let mounts = parse_mountinfo(content);
+        assert!(mounts.iter().all(MountEntry::is_network));
+        assert_eq!(mounts[0].source, "//nas/videos");
+    }
+
+    #[test]
+    fn test_decode_udev_name() {
+        assert_eq!(decode_udev_name("My\\x20Movies"), "My Movies");
+        assert_eq!(decode_udev_name("1234-ABCD"), "1234-ABCD");
+        assert_eq!(decode_udev_name("bad\\xZZ"), "bad\\xZZ");
+    }
+
+    #[test]
+    fn test_relocation_candidates() {
+        let candidates = relocation_candidates(Path::new("/media/alice/Movies1"), Path::new("/media/alice/Movies/anime"));
+        assert_eq!(candidates[0], PathBuf::from("/media/alice/Movies1"));
+        assert_eq!(candidates[1], PathBuf::from("/media/alice/Movies1/anime"));
+        assert_eq!(candidates[2], PathBuf::from("/media/alice/Movies1/Movies/anime"));
+    }
+}
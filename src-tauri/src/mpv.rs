@@ -0,0 +1,611 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 等待 mpv 创建 IPC 套接字的最长时间
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// 单次 IPC 请求的读取超时
+const IPC_TIMEOUT: Duration = Duration::from_secs(2);
+/// 轮询播放进度的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// 两次轮询之间进度前进超过该秒数视为跳转，不计入观看时长
+const MAX_PROGRESS_STEP_SECS: f64 = 10.0;
+
+#[cfg(unix)]
+type IpcStream = std::os::unix::net::UnixStream;
+#[cfg(windows)]
+type IpcStream = pipe::PipeStream;
+
+/// 连接 mpv 的 IPC 套接字（Windows 上为命名管道）
+#[cfg(unix)]
+fn connect(socket_path: &Path) -> std::io::Result<IpcStream> {
+    let stream = std::os::unix::net::UnixStream::connect(socket_path)?;
+    stream.set_read_timeout(Some(IPC_TIMEOUT))?;
+    Ok(stream)
+}
+
+#[cfg(windows)]
+fn connect(socket_path: &Path) -> std::io::Result<IpcStream> {
+    let file = std::fs::OpenOptions::new().read(true).write(true).open(socket_path)?;
+    Ok(pipe::PipeStream::new(file, IPC_TIMEOUT))
+}
+
+#[cfg(windows)]
+mod pipe {
+    use std::ffi::c_void;
+    use std::fs::File;
+    use std::io::{self, Read, Write};
+    use std::os::windows::io::AsRawHandle;
+    use std::time::{Duration, Instant};
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn PeekNamedPipe(
+            pipe: *mut c_void,
+            buffer: *mut c_void,
+            buffer_size: u32,
+            bytes_read: *mut u32,
+            total_bytes_available: *mut u32,
+            bytes_left_this_message: *mut u32,
+        ) -> i32;
+    }
+
+    /// 带读取超时的命名管道。同步打开的管道不支持读取超时，读取前先查询可读字节数，
+    /// 超时仍无数据时返回 TimedOut，管道断开时返回 BrokenPipe
+    pub struct PipeStream {
+        file: File,
+        read_timeout: Duration,
+    }
+
+    impl PipeStream {
+        pub fn new(file: File, read_timeout: Duration) -> Self {
+            Self { file, read_timeout }
+        }
+
+        /// 管道中可立即读取的字节数
+        fn available(&self) -> io::Result<u32> {
+            let mut available = 0u32;
+            let handle = self.file.as_raw_handle() as *mut c_void;
+            // SAFETY: 句柄在 self.file 存活期间有效，缓冲区为空时只写入 available
+            let ok = unsafe { PeekNamedPipe(handle, std::ptr::null_mut(), 0, std::ptr::null_mut(), &mut available, std::ptr::null_mut()) };
+            if ok == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(available)
+        }
+    }
+
+    impl Read for PipeStream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let started = Instant::now();
+            loop {
+                let available = self.available()? as usize;
+                if available > 0 {
+                    let len = buf.len().min(available);
+                    return self.file.read(&mut buf[..len]);
+                }
+                if started.elapsed() >= self.read_timeout {
+                    return Err(io::Error::new(io::ErrorKind::TimedOut, "读取命名管道超时"));
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+
+    impl Write for PipeStream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.file.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.file.flush()
+        }
+    }
+}
+
+/// 为一次播放生成 IPC 套接字路径
+fn socket_path_for(session_id: &str) -> PathBuf {
+    #[cfg(windows)]
+    return PathBuf::from(format!(r"\\.\pipe\videovault-mpv-{}", session_id));
+    #[cfg(not(windows))]
+    std::env::temp_dir().join(format!("videovault-mpv-{}.sock", session_id))
+}
+
+/// IPC 请求失败的原因
+#[derive(Debug)]
+enum MpvError {
+    /// 连接已断开（mpv 关闭了连接或管道破裂），会话无法继续
+    Disconnected(String),
+    /// 单次请求失败（读取超时、属性暂不可用等），之后可以重试
+    Failed(String),
+}
+
+impl MpvError {
+    /// 按 IO 错误类型区分连接断开与可重试的错误（如读取超时）
+    fn from_io(context: &str, error: std::io::Error) -> Self {
+        use std::io::ErrorKind;
+        let message = format!("{}: {}", context, error);
+        match error.kind() {
+            ErrorKind::BrokenPipe
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::NotConnected
+            | ErrorKind::UnexpectedEof => MpvError::Disconnected(message),
+            _ => MpvError::Failed(message),
+        }
+    }
+}
+
+impl From<MpvError> for String {
+    fn from(error: MpvError) -> Self {
+        match error {
+            MpvError::Disconnected(message) | MpvError::Failed(message) => message,
+        }
+    }
+}
+
+/// mpv JSON IPC 客户端：每行一个 JSON 请求，响应中夹杂的事件消息会被跳过
+pub struct MpvClient<S: Read + Write> {
+    reader: BufReader<S>,
+    next_request_id: u64,
+}
+
+impl<S: Read + Write> MpvClient<S> {
+    pub fn new(stream: S) -> Self {
+        Self {
+            reader: BufReader::new(stream),
+            next_request_id: 1,
+        }
+    }
+
+    /// 发送命令并等待对应 request_id 的响应，返回 data 字段
+    pub fn command(&mut self, args: Value) -> Result<Value, String> {
+        self.request(args).map_err(String::from)
+    }
+
+    /// 发送命令并等待响应。读取超时后迟到的响应 request_id 不匹配，会在之后的请求中被跳过
+    fn request(&mut self, args: Value) -> Result<Value, MpvError> {
+        let request_id = self.next_request_id;
+        self.next_request_id += 1;
+        let mut request = json!({ "command": args, "request_id": request_id }).to_string();
+        request.push('\n');
+        let stream = self.reader.get_mut();
+        stream
+            .write_all(request.as_bytes())
+            .and_then(|_| stream.flush())
+            .map_err(|e| MpvError::from_io("发送 mpv 命令失败", e))?;
+
+        loop {
+            let mut line = String::new();
+            let read = self.reader.read_line(&mut line).map_err(|e| MpvError::from_io("读取 mpv 响应失败", e))?;
+            if read == 0 {
+                return Err(MpvError::Disconnected("mpv 已关闭连接".to_string()));
+            }
+            let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+            if message.get("request_id").and_then(Value::as_u64) != Some(request_id) {
+                continue;
+            }
+            return match message.get("error").and_then(Value::as_str) {
+                Some("success") => Ok(message.get("data").cloned().unwrap_or(Value::Null)),
+                Some(error) => Err(MpvError::Failed(format!("mpv 命令失败: {}", error))),
+                None => Err(MpvError::Failed("mpv 响应格式错误".to_string())),
+            };
+        }
+    }
+
+    /// 读取属性
+    pub fn get_property(&mut self, name: &str) -> Result<Value, String> {
+        self.command(json!(["get_property", name]))
+    }
+
+    /// 读取属性；属性暂不可用（如文件尚未加载）时返回 None
+    fn get_optional(&mut self, name: &str) -> Result<Option<Value>, MpvError> {
+        match self.request(json!(["get_property", name])) {
+            Ok(value) => Ok(Some(value)),
+            Err(MpvError::Failed(e)) if e.contains("property unavailable") => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 设置属性
+    pub fn set_property(&mut self, name: &str, value: Value) -> Result<(), String> {
+        self.command(json!(["set_property", name, value])).map(|_| ())
+    }
+
+    /// 跳转到指定位置（秒）
+    pub fn seek(&mut self, position_secs: f64) -> Result<(), String> {
+        self.command(json!(["seek", position_secs, "absolute"])).map(|_| ())
+    }
+
+    /// 章节列表
+    pub fn chapters(&mut self) -> Result<Vec<MpvChapter>, String> {
+        let value = self.get_property("chapter-list")?;
+        serde_json::from_value(value).map_err(|e| format!("解析章节列表失败: {}", e))
+    }
+
+    /// 跳转到指定章节（从 0 开始）
+    pub fn seek_chapter(&mut self, index: usize) -> Result<(), String> {
+        let count = self.chapters()?.len();
+        if index >= count {
+            return Err(format!("章节不存在（共 {} 个章节）", count));
+        }
+        self.set_property("chapter", json!(index))
+    }
+}
+
+/// 视频章节
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MpvChapter {
+    #[serde(default)]
+    pub title: Option<String>,
+    /// 起始位置（秒）
+    pub time: f64,
+}
+
+/// 播放状态
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaybackStatus {
+    pub video_path: PathBuf,
+    /// 当前位置（秒）
+    pub position_secs: Option<f64>,
+    pub duration_secs: Option<f64>,
+    pub paused: bool,
+    /// 是否已播放到结尾
+    pub eof_reached: bool,
+    /// 本次播放实际观看的秒数（不含跳过的部分）
+    pub watched_secs: f64,
+    /// mpv 是否已退出
+    pub finished: bool,
+}
+
+/// 播放进度回调（在轮询线程中调用）
+pub type PlaybackCallback = Arc<dyn Fn(&PlaybackStatus) + Send + Sync>;
+
+/// 跟踪一次播放的进度，累计实际观看时长
+struct PlaybackTracker {
+    status: PlaybackStatus,
+}
+
+impl PlaybackTracker {
+    fn new(video_path: PathBuf) -> Self {
+        Self {
+            status: PlaybackStatus {
+                video_path,
+                position_secs: None,
+                duration_secs: None,
+                paused: false,
+                eof_reached: false,
+                watched_secs: 0.0,
+                finished: false,
+            },
+        }
+    }
+
+    /// 读取 time-pos、duration、pause 与 eof-reached，更新状态
+    /// 文件加载期间不可用的属性按未知处理
+    fn poll<S: Read + Write>(&mut self, client: &mut MpvClient<S>) -> Result<&PlaybackStatus, MpvError> {
+        let position = client.get_optional("time-pos")?.and_then(|value| value.as_f64());
+        let duration = client.get_optional("duration")?.and_then(|value| value.as_f64());
+        let paused = client.get_optional("pause")?.and_then(|value| value.as_bool()).unwrap_or(false);
+        let eof_reached = client.get_optional("eof-reached")?.and_then(|value| value.as_bool()).unwrap_or(false);
+
+        if let (Some(previous), Some(current)) = (self.status.position_secs, position) {
+            let step = current - previous;
+            if step > 0.0 && step <= MAX_PROGRESS_STEP_SECS {
+                self.status.watched_secs += step;
+            }
+        }
+        let status = &mut self.status;
+        status.position_secs = if eof_reached { duration.or(position) } else { position.or(status.position_secs) };
+        status.duration_secs = duration.or(status.duration_secs);
+        status.paused = paused;
+        status.eof_reached = eof_reached;
+        Ok(&self.status)
+    }
+}
+
+/// 正在进行的播放
+struct MpvSession {
+    id: String,
+    child: Child,
+    client: MpvClient<IpcStream>,
+    socket_path: PathBuf,
+    tracker: PlaybackTracker,
+}
+
+impl MpvSession {
+    /// 结束播放：先请求 mpv 退出，再确保进程结束
+    fn stop(mut self) {
+        let _ = self.client.command(json!(["quit"]));
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        #[cfg(unix)]
+        let _ = std::fs::remove_file(&self.socket_path);
+    }
+}
+
+/// mpv 播放后端：同一时间只有一个播放会话，新播放会结束之前的会话
+#[derive(Default)]
+pub struct MpvBackend {
+    session: Arc<Mutex<Option<MpvSession>>>,
+}
+
+impl MpvBackend {
+    /// 启动 mpv 播放视频，`start_secs` 指定起始位置；播放进度定期通过回调报告。
+    /// 需要等待 mpv 创建 IPC 套接字（最长 CONNECT_TIMEOUT），应在后台线程中调用
+    pub fn play(&self, mpv_path: &str, video_path: &Path, start_secs: Option<f64>, callback: PlaybackCallback) -> Result<(), String> {
+        self.stop()?;
+
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let socket_path = socket_path_for(&id[..12]);
+        let mut command = Command::new(mpv_path);
+        command
+            .arg(format!("--input-ipc-server={}", socket_path.display()))
+            .arg("--force-window=yes")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        if let Some(start) = start_secs.filter(|start| *start > 0.0) {
+            command.arg(format!("--start={:.3}", start));
+        }
+        command.arg("--").arg(video_path);
+        let mut child = command.spawn().map_err(|e| format!("无法启动 mpv（{}）: {}", mpv_path, e))?;
+
+        let stream = match wait_for_socket(&mut child, &socket_path) {
+            Ok(stream) => stream,
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(e);
+            }
+        };
+        println!("mpv 已启动: {} (起始位置: {:?})", video_path.display(), start_secs);
+
+        let session = MpvSession {
+            id: id.clone(),
+            child,
+            client: MpvClient::new(stream),
+            socket_path,
+            tracker: PlaybackTracker::new(video_path.to_path_buf()),
+        };
+        *self.session.lock().map_err(|_| "无法获取播放会话锁".to_string())? = Some(session);
+
+        let sessions = Arc::clone(&self.session);
+        std::thread::spawn(move || Self::poll_loop(sessions, id, callback));
+        Ok(())
+    }
+
+    /// 轮询线程：定期读取进度并回调。读取超时等单次失败跳过本轮，
+    /// mpv 退出或连接断开后报告最终状态
+    fn poll_loop(sessions: Arc<Mutex<Option<MpvSession>>>, id: String, callback: PlaybackCallback) {
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let (status, finished) = {
+                let Ok(mut guard) = sessions.lock() else {
+                    return;
+                };
+                // 会话已被新的播放替换或已停止
+                let Some(session) = guard.as_mut().filter(|session| session.id == id) else {
+                    return;
+                };
+                let exited = !matches!(session.child.try_wait(), Ok(None));
+                let polled = if exited {
+                    None
+                } else {
+                    match session.tracker.poll(&mut session.client) {
+                        Ok(status) => Some(status.clone()),
+                        Err(MpvError::Failed(e)) => {
+                            println!("读取 mpv 播放进度失败，稍后重试: {}", e);
+                            continue;
+                        }
+                        Err(MpvError::Disconnected(e)) => {
+                            println!("mpv 连接已断开: {}", e);
+                            None
+                        }
+                    }
+                };
+                match polled {
+                    Some(status) => (status, false),
+                    None => {
+                        let mut status = session.tracker.status.clone();
+                        status.finished = true;
+                        if let Some(session) = guard.take() {
+                            session.stop();
+                        }
+                        (status, true)
+                    }
+                }
+            };
+            // 回调中可能获取其他锁，不持有会话锁
+            callback(&status);
+            if finished {
+                println!("mpv 播放结束: {}", status.video_path.display());
+                return;
+            }
+        }
+    }
+
+    /// 在当前播放会话上执行操作
+    fn with_session<T>(&self, action: impl FnOnce(&mut MpvSession) -> Result<T, String>) -> Result<T, String> {
+        let mut guard = self.session.lock().map_err(|_| "无法获取播放会话锁".to_string())?;
+        let session = guard.as_mut().ok_or("当前没有正在播放的视频".to_string())?;
+        action(session)
+    }
+
+    /// 当前播放状态
+    pub fn status(&self) -> Result<Option<PlaybackStatus>, String> {
+        let guard = self.session.lock().map_err(|_| "无法获取播放会话锁".to_string())?;
+        Ok(guard.as_ref().map(|session| session.tracker.status.clone()))
+    }
+
+    /// 跳转到指定位置（秒）
+    pub fn seek(&self, position_secs: f64) -> Result<(), String> {
+        if !position_secs.is_finite() || position_secs < 0.0 {
+            return Err("播放位置无效".to_string());
+        }
+        self.with_session(|session| session.client.seek(position_secs))
+    }
+
+    /// 当前视频的章节列表
+    pub fn chapters(&self) -> Result<Vec<MpvChapter>, String> {
+        self.with_session(|session| session.client.chapters())
+    }
+
+    /// 跳转到指定章节
+    pub fn seek_chapter(&self, index: usize) -> Result<(), String> {
+        self.with_session(|session| session.client.seek_chapter(index))
+    }
+
+    /// 停止当前播放
+    pub fn stop(&self) -> Result<(), String> {
+        let session = self.session.lock().map_err(|_| "无法获取播放会话锁".to_string())?.take();
+        if let Some(session) = session {
+            println!("停止 mpv 播放: {}", session.tracker.status.video_path.display());
+            session.stop();
+        }
+        Ok(())
+    }
+}
+
+/// 等待 mpv 创建 IPC 套接字并连接，mpv 提前退出时报错
+fn wait_for_socket(child: &mut Child, socket_path: &Path) -> Result<IpcStream, String> {
+    let started = Instant::now();
+    loop {
+        if let Ok(stream) = connect(socket_path) {
+            return Ok(stream);
+        }
+        if let Ok(Some(status)) = child.try_wait() {
+            return Err(format!("mpv 启动后立即退出: {}", status));
+        }
+        if started.elapsed() > CONNECT_TIMEOUT {
+            return Err("连接 mpv IPC 超时".to_string());
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+
+    /// 模拟 mpv 的 IPC 服务端：每轮轮询按属性名返回预设值（读取 eof-reached 后进入下一轮），
+    /// 并在每个响应前插入一条事件消息
+    fn fake_mpv(socket_path: &Path, chapters: Value, properties: Vec<Vec<(&'static str, Value)>>) -> std::thread::JoinHandle<Vec<Value>> {
+        let listener = UnixListener::bind(socket_path).unwrap();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut received = Vec::new();
+            let mut round = 0;
+            for line in BufReader::new(stream).lines() {
+                let request: Value = serde_json::from_str(&line.unwrap()).unwrap();
+                let request_id = request["request_id"].clone();
+                let command = request["command"].clone();
+                let response = match command[0].as_str().unwrap() {
+                    "get_property" if command[1] == "chapter-list" => {
+                        json!({ "data": chapters, "error": "success", "request_id": request_id })
+                    }
+                    "get_property" => {
+                        let name = command[1].as_str().unwrap();
+                        let values = &properties[round.min(properties.len() - 1)];
+                        if name == "eof-reached" {
+                            round += 1;
+                        }
+                        match values.iter().find(|(key, _)| *key == name) {
+                            Some((_, value)) => json!({ "data": value, "error": "success", "request_id": request_id }),
+                            None => json!({ "error": "property unavailable", "request_id": request_id }),
+                        }
+                    }
+                    _ => json!({ "data": null, "error": "success", "request_id": request_id }),
+                };
+                received.push(command);
+                writeln!(writer, r#"{{"event":"property-change","name":"time-pos"}}"#).unwrap();
+                writeln!(writer, "{}", response).unwrap();
+            }
+            received
+        })
+    }
+
+    #[test]
+    fn test_client_and_tracker_against_fake_ipc_server() {
+        let socket_path = std::env::temp_dir().join(format!("vv-mpv-test-{}.sock", &uuid::Uuid::new_v4().simple().to_string()[..12]));
+        let chapters = json!([{ "title": "片头", "time": 0.0 }, { "title": "正片", "time": 95.5 }]);
+        let server = fake_mpv(
+            &socket_path,
+            chapters,
+            vec![
+                // 文件加载期间 pause 与 eof-reached 也可能不可用
+                vec![("duration", json!(1800.0))],
+                vec![("time-pos", json!(100.0)), ("duration", json!(1800.0)), ("pause", json!(false)), ("eof-reached", json!(false))],
+                vec![("time-pos", json!(104.0)), ("duration", json!(1800.0)), ("pause", json!(true)), ("eof-reached", json!(false))],
+                // 跳转到片尾：不计入观看时长
+                vec![("time-pos", json!(1795.0)), ("duration", json!(1800.0)), ("pause", json!(false)), ("eof-reached", json!(true))],
+            ],
+        );
+
+        let mut client = MpvClient::new(connect(&socket_path).unwrap());
+        let mut tracker = PlaybackTracker::new(PathBuf::from("/media/a.mkv"));
+
+        // 文件尚未加载时 time-pos 不可用
+        let status = tracker.poll(&mut client).unwrap();
+        assert_eq!((status.position_secs, status.duration_secs), (None, Some(1800.0)));
+        assert!(!status.paused && !status.eof_reached);
+
+        let chapters = client.chapters().unwrap();
+        assert_eq!(chapters[1], MpvChapter { title: Some("正片".to_string()), time: 95.5 });
+        client.seek_chapter(1).unwrap();
+        assert!(client.seek_chapter(2).is_err());
+        client.seek(100.0).unwrap();
+
+        tracker.poll(&mut client).unwrap();
+        let status = tracker.poll(&mut client).unwrap();
+        assert_eq!(status.position_secs, Some(104.0));
+        assert!(status.paused);
+        assert_eq!(status.watched_secs, 4.0);
+
+        let status = tracker.poll(&mut client).unwrap();
+        assert!(status.eof_reached);
+        assert_eq!(status.position_secs, Some(1800.0));
+        assert_eq!(status.watched_secs, 4.0);
+
+        drop(client);
+        let received = server.join().unwrap();
+        assert!(received.contains(&json!(["set_property", "chapter", 1])));
+        assert!(received.contains(&json!(["seek", 100.0, "absolute"])));
+        std::fs::remove_file(&socket_path).unwrap();
+    }
+
+    #[test]
+    fn test_read_timeout_is_retryable_and_closed_connection_is_not() {
+        let socket_path = std::env::temp_dir().join(format!("vv-mpv-test-{}.sock", &uuid::Uuid::new_v4().simple().to_string()[..12]));
+        let listener = UnixListener::bind(&socket_path).unwrap();
+        // 第一个请求暂不响应，收到第二个请求后先补发第一个响应，再响应第二个，然后断开连接
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut lines = BufReader::new(stream).lines();
+            let first: Value = serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap();
+            let second: Value = serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap();
+            writeln!(writer, "{}", json!({ "data": true, "error": "success", "request_id": first["request_id"] })).unwrap();
+            writeln!(writer, "{}", json!({ "data": false, "error": "success", "request_id": second["request_id"] })).unwrap();
+        });
+
+        let stream = connect(&socket_path).unwrap();
+        stream.set_read_timeout(Some(Duration::from_millis(100))).unwrap();
+        let mut client = MpvClient::new(stream);
+        assert!(matches!(client.request(json!(["get_property", "pause"])), Err(MpvError::Failed(_))));
+        // 迟到的第一个响应被跳过
+        assert_eq!(client.get_property("pause").unwrap(), json!(false));
+
+        server.join().unwrap();
+        assert!(matches!(client.request(json!(["get_property", "pause"])), Err(MpvError::Disconnected(_))));
+        std::fs::remove_file(&socket_path).unwrap();
+    }
+}
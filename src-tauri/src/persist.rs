@@ -0,0 +1,115 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 根目录下存放 VideoVault 数据的目录名
+pub const ROOT_STATE_DIR: &str = ".videovault";
+
+/// 返回根目录下的 VideoVault 数据目录（随硬盘携带）
+pub fn root_state_dir(root: &Path) -> PathBuf {
+    root.join(ROOT_STATE_DIR)
+}
+
+/// 读取 JSON 文件，文件不存在时返回默认值
+pub fn read_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T, String> {
+    match fs::read_to_string(path) {
+        Ok(data) => serde_json::from_str(&data)
+            .map_err(|e| format!("解析 {} 失败: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(format!("读取 {} 失败: {}", path.display(), e)),
+    }
+}
+
+/// 写入 JSON 文件：先写临时文件再重命名，避免中途失败留下半个文件
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("创建目录 {} 失败: {}", parent.display(), e))?;
+    }
+
+    let data = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("序列化失败: {}", e))?;
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, data)
+        .map_err(|e| format!("写入 {} 失败: {}", tmp_path.display(), e))?;
+    fs::rename(&tmp_path, path)
+        .map_err(|e| format!("写入 {} 失败: {}", path.display(), e))
+}
+
+/// 将根目录下的绝对路径转换为以 `/` 分隔的相对路径键（根目录本身为空字符串）
+pub fn relative_key(root: &Path, absolute: &Path) -> Result<String, String> {
+    let rel = crate::volume::to_relative_path(root, absolute)?;
+    let parts: Vec<String> = rel
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    Ok(parts.join("/"))
+}
+
+/// 将相对路径键还原为根目录下的绝对路径
+pub fn resolve_key(root: &Path, key: &str) -> PathBuf {
+    key.split('/')
+        .filter(|part| !part.is_empty())
+        .fold(root.to_path_buf(), |path, part| path.join(part))
+}
+
+/// 拼接相对路径键：`prefix` 下的 `relative`（两者都可以为空）
+pub fn join_key(prefix: &str, relative: &str) -> String {
+    match (prefix.is_empty(), relative.is_empty()) {
+        (true, _) => relative.to_string(),
+        (false, true) => prefix.to_string(),
+        (false, false) => format!("{}/{}", prefix, relative),
+    }
+}
+
+/// 去掉相对路径键开头的 `prefix`，键不在 `prefix` 之下时返回 None
+pub fn strip_key_prefix<'a>(prefix: &str, key: &'a str) -> Option<&'a str> {
+    if prefix.is_empty() {
+        return Some(key);
+    }
+    match key.strip_prefix(prefix)? {
+        "" => Some(""),
+        rest => rest.strip_prefix('/'),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_key_roundtrip() {
+        let root = PathBuf::from("/media/movies");
+        let file = root.join("series").join("ep1.mkv");
+
+        let key = relative_key(&root, &file).unwrap();
+        assert_eq!(key, "series/ep1.mkv");
+        assert_eq!(resolve_key(&root, &key), file);
+        assert_eq!(relative_key(&root, &root).unwrap(), "");
+        assert!(relative_key(&root, Path::new("/other/file.mkv")).is_err());
+
+        assert_eq!(join_key("movies", &key), "movies/series/ep1.mkv");
+        assert_eq!(join_key("", &key), key);
+        assert_eq!(join_key("movies", ""), "movies");
+        assert_eq!(strip_key_prefix("movies", "movies/series/ep1.mkv"), Some("series/ep1.mkv"));
+        assert_eq!(strip_key_prefix("movies", "movies"), Some(""));
+        assert_eq!(strip_key_prefix("movies", "movies2/a.mkv"), None);
+        assert_eq!(strip_key_prefix("", "a.mkv"), Some("a.mkv"));
+    }
+
+    #[test]
+    fn test_read_write_json() {
+        let dir = std::env::temp_dir().join(format!("vv-persist-test-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("nested").join("data.json");
+
+        let missing: Vec<String> = read_json(&path).unwrap();
+        assert!(missing.is_empty());
+
+        write_json(&path, &vec!["a".to_string()]).unwrap();
+        let loaded: Vec<String> = read_json(&path).unwrap();
+        assert_eq!(loaded, vec!["a".to_string()]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,307 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use crate::settings::PlaybackSettings;
+
+/// 外挂字幕扩展名（小写）
+const SUBTITLE_EXTENSIONS: [&str; 6] = ["srt", "ass", "ssa", "vtt", "sub", "sup"];
+
+/// 外部播放器配置：可执行文件与参数模板
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerProfile {
+    pub name: String,
+    /// 可执行文件（名称或完整路径）
+    pub executable: String,
+    /// 参数模板，每项为一个参数，可包含 `{path}`、`{start}`、`{subtitle}` 占位符；
+    /// `{{`、`}}` 表示字面的花括号。含 `{start}` 或 `{subtitle}` 的参数在没有对应值时整项省略
+    #[serde(default = "default_args")]
+    pub args: Vec<String>,
+}
+
+fn default_args() -> Vec<String> {
+    vec!["{path}".to_string()]
+}
+
+/// 参数模板中的占位符
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Placeholder {
+    Path,
+    Start,
+    Subtitle,
+}
+
+/// 参数模板片段
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+/// 解析一个参数模板
+fn parse_template(arg: &str) -> Result<Vec<Segment>, String> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut chars = arg.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    name.push(c);
+                }
+                if !closed {
+                    return Err(format!("参数「{}」中有不成对的 {{", arg));
+                }
+                let placeholder = match name.as_str() {
+                    "path" => Placeholder::Path,
+                    "start" => Placeholder::Start,
+                    "subtitle" => Placeholder::Subtitle,
+                    _ => return Err(format!("参数「{}」中有未知占位符 {{{}}}（支持 {{path}}、{{start}}、{{subtitle}}）", arg, name)),
+                };
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                segments.push(Segment::Placeholder(placeholder));
+            }
+            '}' => return Err(format!("参数「{}」中有不成对的 }}", arg)),
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    Ok(segments)
+}
+
+impl PlayerProfile {
+    /// 校验名称、可执行文件与参数模板：必须包含 {path}
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("播放器配置名称不能为空".to_string());
+        }
+        if self.executable.trim().is_empty() {
+            return Err(format!("播放器配置「{}」的可执行文件不能为空", self.name));
+        }
+        let mut has_path = false;
+        for arg in &self.args {
+            has_path |= parse_template(arg)?.contains(&Segment::Placeholder(Placeholder::Path));
+        }
+        if !has_path {
+            return Err(format!("播放器配置「{}」的参数中缺少 {{path}}", self.name));
+        }
+        Ok(())
+    }
+
+    /// 按模板生成参数。以占位符开头的参数替换后若以 - 开头，会被播放器当作选项，直接报错
+    pub fn render_args(&self, video_path: &Path, start_secs: Option<f64>, subtitle: Option<&Path>) -> Result<Vec<String>, String> {
+        let start = start_secs.map(format_seconds);
+        let mut args = Vec::new();
+        'args: for arg in &self.args {
+            let mut rendered = String::new();
+            for segment in parse_template(arg)? {
+                match segment {
+                    Segment::Text(text) => rendered.push_str(&text),
+                    Segment::Placeholder(Placeholder::Path) => rendered.push_str(&video_path.to_string_lossy()),
+                    Segment::Placeholder(Placeholder::Start) => match &start {
+                        Some(start) => rendered.push_str(start),
+                        None => continue 'args,
+                    },
+                    Segment::Placeholder(Placeholder::Subtitle) => match subtitle {
+                        Some(subtitle) => rendered.push_str(&subtitle.to_string_lossy()),
+                        None => continue 'args,
+                    },
+                }
+            }
+            if rendered.starts_with('-') && !arg.starts_with('-') {
+                return Err(format!("参数「{}」以 - 开头，可能被播放器当作选项", rendered));
+            }
+            args.push(rendered);
+        }
+        Ok(args)
+    }
+
+    /// 启动播放器（不等待播放器退出）
+    pub fn launch(&self, args: &[String]) -> Result<(), String> {
+        println!("使用播放器「{}」播放: {} {:?}", self.name, self.executable, args);
+        let mut child = Command::new(self.executable.trim())
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("无法启动播放器「{}」: {}", self.name, e))?;
+        // 在后台等待播放器退出，避免留下僵尸进程
+        std::thread::spawn(move || {
+            let _ = child.wait();
+        });
+        Ok(())
+    }
+}
+
+/// 秒数格式化为播放器参数（最多保留三位小数）
+fn format_seconds(secs: f64) -> String {
+    let formatted = format!("{:.3}", secs.max(0.0));
+    formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// 播放选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayOptions {
+    /// 起始位置（秒），为空时按设置从上次的位置继续
+    pub start_secs: Option<f64>,
+    /// 外挂字幕文件，为空时自动查找与视频同名的字幕
+    pub subtitle: Option<PathBuf>,
+}
+
+impl PlayOptions {
+    /// 校验起始位置与字幕文件
+    pub fn validate(&self) -> Result<(), String> {
+        if self.start_secs.is_some_and(|start| !start.is_finite() || start < 0.0) {
+            return Err("起始位置无效".to_string());
+        }
+        if let Some(subtitle) = &self.subtitle {
+            if !subtitle.is_absolute() {
+                return Err(format!("字幕路径必须是绝对路径: {}", subtitle.display()));
+            }
+            if !is_subtitle(subtitle) {
+                return Err(format!("不支持的字幕格式: {}", subtitle.display()));
+            }
+            if !subtitle.is_file() {
+                return Err(format!("字幕文件不存在: {}", subtitle.display()));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_subtitle(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| SUBTITLE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// 查找与视频同名的外挂字幕（如 movie.srt、movie.zh.ass）
+pub fn find_subtitle(video_path: &Path) -> Option<PathBuf> {
+    let stem = video_path.file_stem()?.to_str()?;
+    let mut candidates: Vec<PathBuf> = std::fs::read_dir(video_path.parent()?)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| is_subtitle(path) && path.is_file())
+        .filter(|path| {
+            path.file_stem()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name == stem || name.strip_prefix(stem).is_some_and(|rest| rest.starts_with('.')))
+        })
+        .collect();
+    // 完全同名的优先，其次按文件名排序保证结果确定
+    candidates.sort_by_key(|path| (path.file_stem().and_then(|name| name.to_str()) != Some(stem), path.clone()));
+    candidates.into_iter().next()
+}
+
+/// 选择播放器配置：显式指定 > 根文件夹 > 容器格式（扩展名）> 默认配置；
+/// 返回 None 表示使用内置的播放后端
+pub fn select_profile<'a>(
+    settings: &'a PlaybackSettings,
+    requested: Option<&str>,
+    root_id: Option<&str>,
+    video_path: &Path,
+) -> Result<Option<&'a PlayerProfile>, String> {
+    let find = |name: &str| settings.profiles.iter().find(|profile| profile.name == name);
+    if let Some(name) = requested {
+        return find(name).map(Some).ok_or(format!("播放器配置不存在: {}", name));
+    }
+    let extension = video_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+    let name = root_id
+        .and_then(|root_id| settings.root_profiles.get(root_id))
+        .or_else(|| extension.and_then(|ext| settings.container_profiles.get(&ext)))
+        .or(settings.default_profile.as_ref());
+    Ok(name.and_then(|name| find(name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(args: &[&str]) -> PlayerProfile {
+        PlayerProfile {
+            name: "VLC".to_string(),
+            executable: "vlc".to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_render_args_and_validation() {
+        let vlc = profile(&["--start-time={start}", "--sub-file={subtitle}", "--meta-title={{VV}}", "{path}"]);
+        vlc.validate().unwrap();
+        let video = Path::new("/media/电影/A Movie.mkv");
+
+        let args = vlc.render_args(video, Some(95.5), Some(Path::new("/media/电影/A Movie.srt"))).unwrap();
+        assert_eq!(
+            args,
+            vec!["--start-time=95.5", "--sub-file=/media/电影/A Movie.srt", "--meta-title={VV}", "/media/电影/A Movie.mkv"]
+        );
+        // 没有起始位置和字幕时省略对应参数
+        assert_eq!(vlc.render_args(video, None, None).unwrap(), vec!["--meta-title={VV}", "/media/电影/A Movie.mkv"]);
+        assert_eq!(format_seconds(600.0), "600");
+
+        assert!(profile(&["--start={start}"]).validate().unwrap_err().contains("{path}"));
+        assert!(profile(&["{path}", "{file}"]).validate().unwrap_err().contains("{file}"));
+        assert!(profile(&["{path}}"]).validate().is_err());
+        assert!(profile(&["{path"]).validate().is_err());
+        assert!(PlayOptions { start_secs: Some(f64::NAN), subtitle: None }.validate().is_err());
+        assert!(PlayOptions { start_secs: None, subtitle: Some(PathBuf::from("/tmp/a.exe")) }.validate().is_err());
+        assert!(PlayOptions { start_secs: None, subtitle: Some(PathBuf::from("-x.srt")) }.validate().is_err());
+
+        // 替换出的路径不能以 - 开头，避免被当作播放器选项
+        assert!(vlc.render_args(Path::new("--play-and-exit.mkv"), None, None).is_err());
+        assert!(vlc.render_args(video, None, Some(Path::new("-x.srt"))).is_ok());
+        assert!(profile(&["--sub-file", "{subtitle}", "{path}"]).render_args(video, None, Some(Path::new("-x.srt"))).is_err());
+    }
+
+    #[test]
+    fn test_select_profile_and_find_subtitle() {
+        let mut settings = PlaybackSettings {
+            profiles: vec![profile(&["{path}"]), PlayerProfile { name: "IINA".to_string(), ..profile(&["{path}"]) }],
+            default_profile: Some("VLC".to_string()),
+            ..PlaybackSettings::default()
+        };
+        settings.root_profiles.insert("root-1".to_string(), "IINA".to_string());
+        settings.container_profiles.insert("webm".to_string(), "IINA".to_string());
+
+        let pick = |requested, root_id, path| select_profile(&settings, requested, root_id, Path::new(path)).unwrap().map(|p| p.name.clone());
+        assert_eq!(pick(None, Some("root-1"), "/a.mkv").as_deref(), Some("IINA"));
+        assert_eq!(pick(None, None, "/a.WEBM").as_deref(), Some("IINA"));
+        assert_eq!(pick(None, None, "/a.mkv").as_deref(), Some("VLC"));
+        assert_eq!(pick(Some("VLC"), Some("root-1"), "/a.mkv").as_deref(), Some("VLC"));
+        assert!(select_profile(&settings, Some("mpv"), None, Path::new("/a.mkv")).is_err());
+
+        let dir = std::env::temp_dir().join(format!("vv-player-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["ep1.mkv", "ep1.zh.ass", "ep10.srt", "ep1.srt"] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+        assert_eq!(find_subtitle(&dir.join("ep1.mkv")), Some(dir.join("ep1.srt")));
+        std::fs::remove_file(dir.join("ep1.srt")).unwrap();
+        assert_eq!(find_subtitle(&dir.join("ep1.mkv")), Some(dir.join("ep1.zh.ass")));
+        assert_eq!(find_subtitle(&dir.join("ep2.mkv")), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
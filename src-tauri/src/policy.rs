@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::persist;
+use crate::volume::VOLUME_ID_FILE;
+
+/// 存储策略文件名（位于应用数据目录）
+const POLICY_FILE: &str = "storage_policy.json";
+/// 只读根文件夹的数据在应用数据目录中的存放位置
+const ROOT_STATES_DIR: &str = "roots";
+/// 只读模式下卷标识在数据目录中的文件名
+pub const STATE_VOLUME_ID_FILE: &str = "volume-id";
+
+/// 存储策略：决定根文件夹的数据（卷标识、自定义封面等）写入根目录还是应用数据目录
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoragePolicy {
+    /// 全局只读：未单独设置的根文件夹不向用户目录写入任何文件
+    pub read_only: bool,
+    /// 单个根文件夹的设置（覆盖全局设置）
+    pub roots: BTreeMap<String, bool>,
+}
+
+impl StoragePolicy {
+    /// 从应用数据目录加载存储策略
+    pub fn load(data_dir: &Path) -> Self {
+        persist::read_json(&data_dir.join(POLICY_FILE)).unwrap_or_else(|e| {
+            println!("加载存储策略失败: {}", e);
+            Self::default()
+        })
+    }
+
+    /// 保存存储策略
+    pub fn save(&self, data_dir: &Path) -> Result<(), String> {
+        persist::write_json(&data_dir.join(POLICY_FILE), self)
+    }
+
+    /// 根文件夹是否为只读
+    pub fn is_read_only(&self, root_id: &str) -> bool {
+        self.roots.get(root_id).copied().unwrap_or(self.read_only)
+    }
+}
+
+/// 只读根文件夹在应用数据目录中的数据目录
+pub fn data_state_dir(data_dir: &Path, root_id: &str) -> PathBuf {
+    data_dir.join(ROOT_STATES_DIR).join(root_id)
+}
+
+/// 状态迁移结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateMigrationReport {
+    /// 迁移后是否为只读
+    pub read_only: bool,
+    /// 迁移的文件数
+    pub moved_files: usize,
+    /// 迁移后的数据目录
+    pub state_dir: PathBuf,
+}
+
+/// 在根目录（`.videovault/` 与卷标识文件）和应用数据目录之间迁移根文件夹的数据。
+/// `to_read_only` 为 true 时移入应用数据目录，并从用户目录中删除；否则移回根目录。
+pub fn migrate_root_state(root: &Path, data_state: &Path, to_read_only: bool) -> Result<StateMigrationReport, String> {
+    let root_state = persist::root_state_dir(root);
+    let root_marker = root.join(VOLUME_ID_FILE);
+    let data_marker = data_state.join(STATE_VOLUME_ID_FILE);
+
+    let (from_dir, to_dir, from_marker, to_marker) = if to_read_only {
+        (root_state.as_path(), data_state, &root_marker, &data_marker)
+    } else {
+        if !root.is_dir() {
+            return Err("根文件夹所在的卷不在线".to_string());
+        }
+        (data_state, root_state.as_path(), &data_marker, &root_marker)
+    };
+
+    let mut moved_files = 0;
+    if from_dir.is_dir() {
+        for entry in fs::read_dir(from_dir).map_err(|e| format!("读取 {} 失败: {}", from_dir.display(), e))?.flatten() {
+            let name = entry.file_name();
+            // 卷标识文件单独处理
+            if !to_read_only && name.to_string_lossy() == STATE_VOLUME_ID_FILE {
+                continue;
+            }
+            moved_files += move_path(&entry.path(), &to_dir.join(&name))?;
+        }
+    }
+    if from_marker.is_file() {
+        moved_files += move_path(from_marker, to_marker)?;
+    }
+
+    // 移入数据目录后不在用户目录留下空目录
+    if to_read_only {
+        let _ = fs::remove_dir(&root_state);
+    } else {
+        let _ = fs::remove_dir(data_state);
+    }
+
+    Ok(StateMigrationReport {
+        read_only: to_read_only,
+        moved_files,
+        state_dir: to_dir.to_path_buf(),
+    })
+}
+
+/// 移动文件或目录，跨设备时退回复制后删除；返回移动的文件数
+fn move_path(from: &Path, to: &Path) -> Result<usize, String> {
+    if from.is_dir() {
+        fs::create_dir_all(to).map_err(|e| format!("创建目录 {} 失败: {}", to.display(), e))?;
+        let mut count = 0;
+        for entry in fs::read_dir(from).map_err(|e| format!("读取 {} 失败: {}", from.display(), e))?.flatten() {
+            count += move_path(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        fs::remove_dir(from).map_err(|e| format!("删除目录 {} 失败: {}", from.display(), e))?;
+        return Ok(count);
+    }
+
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建目录 {} 失败: {}", parent.display(), e))?;
+    }
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to).map_err(|e| format!("复制 {} 失败: {}", from.display(), e))?;
+        fs::remove_file(from).map_err(|e| format!("删除 {} 失败: {}", from.display(), e))?;
+    }
+    Ok(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_root_state_roundtrip() {
+        let base = std::env::temp_dir().join(format!("vv-policy-test-{}", uuid::Uuid::new_v4()));
+        let root = base.join("movies");
+        let data_state = data_state_dir(&base.join("data"), "root-1");
+        fs::create_dir_all(persist::root_state_dir(&root)).unwrap();
+        fs::write(persist::root_state_dir(&root).join("covers.json"), "{}").unwrap();
+        fs::write(root.join(VOLUME_ID_FILE), "abc").unwrap();
+
+        let report = migrate_root_state(&root, &data_state, true).unwrap();
+        assert_eq!(report.moved_files, 2);
+        assert!(!persist::root_state_dir(&root).exists());
+        assert!(!root.join(VOLUME_ID_FILE).exists());
+        assert!(data_state.join("covers.json").is_file());
+        assert_eq!(fs::read_to_string(data_state.join(STATE_VOLUME_ID_FILE)).unwrap(), "abc");
+
+        let report = migrate_root_state(&root, &data_state, false).unwrap();
+        assert_eq!(report.moved_files, 2);
+        assert!(persist::root_state_dir(&root).join("covers.json").is_file());
+        assert!(root.join(VOLUME_ID_FILE).is_file());
+        assert!(!data_state.exists());
+
+        let mut policy = StoragePolicy::default();
+        policy.roots.insert("root-1".to_string(), true);
+        assert!(policy.is_read_only("root-1"));
+        assert!(!policy.is_read_only("root-2"));
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -0,0 +1,324 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use crate::video::{media_command, VideoProcessor};
+
+/// 预览片段数量
+const SEGMENT_COUNT: u32 = 6;
+/// 每个片段时长（秒）
+const SEGMENT_SECS: f64 = 1.0;
+/// 预览宽度（像素）
+const PREVIEW_WIDTH: u32 = 320;
+/// 预览文件扩展名
+const PREVIEW_EXTENSION: &str = "webm";
+
+/// 预览生成事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewEvent {
+    /// 视频路径
+    pub video_path: PathBuf,
+    /// 生成的预览文件（失败时为 None）
+    pub preview_path: Option<PathBuf>,
+    /// 错误信息
+    pub error: Option<String>,
+}
+
+/// 预览任务状态
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PreviewJobStatus {
+    /// 等待中的任务数
+    pub queued: usize,
+    /// 正在生成的任务数
+    pub running: usize,
+    /// 已完成的任务数
+    pub completed: usize,
+    /// 失败的任务数
+    pub failed: usize,
+    /// 最大并发数
+    pub max_concurrency: usize,
+}
+
+/// 预览事件回调
+pub type PreviewCallback = Arc<dyn Fn(PreviewEvent) + Send + Sync>;
+
+/// 任务队列
+#[derive(Default)]
+struct PreviewQueue {
+    pending: VecDeque<PathBuf>,
+    queued: HashSet<PathBuf>,
+    running: usize,
+    completed: usize,
+    failed: usize,
+}
+
+/// 悬停预览管理器：后台生成低分辨率循环片段，按视频指纹缓存在 `<缓存目录>/previews/`
+pub struct PreviewManager {
+    cache_dir: PathBuf,
+    max_concurrency: Mutex<usize>,
+    queue: Arc<Mutex<PreviewQueue>>,
+}
+
+impl PreviewManager {
+    pub fn new(cache_dir: &Path, max_concurrency: usize) -> Self {
+        Self {
+            cache_dir: cache_dir.join("previews"),
+            max_concurrency: Mutex::new(max_concurrency.max(1)),
+            queue: Arc::new(Mutex::new(PreviewQueue::default())),
+        }
+    }
+
+    /// 设置最大并发数（对之后启动的工作线程生效）
+    pub fn set_max_concurrency(&self, max_concurrency: usize) {
+        if let Ok(mut value) = self.max_concurrency.lock() {
+            *value = max_concurrency.max(1);
+        }
+    }
+
+    fn max_concurrency(&self) -> usize {
+        self.max_concurrency.lock().map(|v| *v).unwrap_or(1)
+    }
+
+    /// 视频对应的预览文件路径（不检查是否存在）
+    fn preview_file(&self, video_path: &Path) -> Result<PathBuf, String> {
+        let fingerprint = crate::video::compute_fingerprint(video_path)?;
+        Ok(self.cache_dir.join(format!("{}.{}", fingerprint, PREVIEW_EXTENSION)))
+    }
+
+    /// 获取已生成的预览文件
+    pub fn get_preview_path(&self, video_path: &Path) -> Option<PathBuf> {
+        self.preview_file(video_path).ok().filter(|path| path.exists())
+    }
+
+    /// 根据文件名解析缓存目录中的预览文件：只接受 `<指纹>.webm`，
+    /// 拒绝目录穿越以及仍在生成中的 `<指纹>.part.webm`
+    pub fn resolve_cached_file(&self, file_name: &str) -> Option<PathBuf> {
+        let fingerprint = file_name.strip_suffix(&format!(".{}", PREVIEW_EXTENSION))?;
+        if fingerprint.is_empty() || !fingerprint.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return None;
+        }
+        Some(self.cache_dir.join(file_name)).filter(|path| path.is_file())
+    }
+
+    /// 将视频加入生成队列，返回新加入的数量
+    pub fn enqueue(&self, videos: Vec<PathBuf>, callback: PreviewCallback) -> usize {
+        let (added, workers_to_start) = {
+            let Ok(mut queue) = self.queue.lock() else {
+                return 0;
+            };
+            let mut added = 0;
+            for video in videos {
+                if queue.queued.insert(video.clone()) {
+                    queue.pending.push_back(video);
+                    added += 1;
+                }
+            }
+            let idle = self.max_concurrency().saturating_sub(queue.running);
+            let workers = idle.min(queue.pending.len());
+            queue.running += workers;
+            (added, workers)
+        };
+
+        for _ in 0..workers_to_start {
+            let queue = Arc::clone(&self.queue);
+            let cache_dir = self.cache_dir.clone();
+            let callback = Arc::clone(&callback);
+            std::thread::spawn(move || Self::worker_loop(queue, cache_dir, callback));
+        }
+
+        added
+    }
+
+    /// 工作线程：持续取任务直到队列为空
+    fn worker_loop(queue: Arc<Mutex<PreviewQueue>>, cache_dir: PathBuf, callback: PreviewCallback) {
+        loop {
+            let next = match queue.lock() {
+                Ok(mut queue) => {
+                    let next = queue.pending.pop_front();
+                    if next.is_none() {
+                        queue.running = queue.running.saturating_sub(1);
+                    }
+                    next
+                }
+                Err(_) => return,
+            };
+            let Some(video_path) = next else {
+                return;
+            };
+
+            let result = crate::video::compute_fingerprint(&video_path).and_then(|fingerprint| {
+                let output = cache_dir.join(format!("{}.{}", fingerprint, PREVIEW_EXTENSION));
+                if output.exists() {
+                    Ok(output)
+                } else {
+                    generate_preview(&video_path, &output).map(|_| output)
+                }
+            });
+
+            if let Ok(mut queue) = queue.lock() {
+                queue.queued.remove(&video_path);
+                match result {
+                    Ok(_) => queue.completed += 1,
+                    Err(_) => queue.failed += 1,
+                }
+            }
+
+            let event = match result {
+                Ok(preview_path) => PreviewEvent {
+                    video_path,
+                    preview_path: Some(preview_path),
+                    error: None,
+                },
+                Err(e) => {
+                    println!("生成预览失败: {} ({})", video_path.display(), e);
+                    PreviewEvent {
+                        video_path,
+                        preview_path: None,
+                        error: Some(e),
+                    }
+                }
+            };
+            callback(event);
+        }
+    }
+
+    /// 取消所有等待中的任务，返回取消的数量（正在生成的任务会继续完成）
+    pub fn cancel_pending(&self) -> usize {
+        match self.queue.lock() {
+            Ok(mut queue) => {
+                let cancelled = queue.pending.len();
+                let pending: Vec<PathBuf> = queue.pending.drain(..).collect();
+                for video in pending {
+                    queue.queued.remove(&video);
+                }
+                cancelled
+            }
+            Err(_) => 0,
+        }
+    }
+
+    /// 获取任务状态
+    pub fn status(&self) -> PreviewJobStatus {
+        let max_concurrency = self.max_concurrency();
+        match self.queue.lock() {
+            Ok(queue) => PreviewJobStatus {
+                queued: queue.pending.len(),
+                running: queue.running,
+                completed: queue.completed,
+                failed: queue.failed,
+                max_concurrency,
+            },
+            Err(_) => PreviewJobStatus {
+                max_concurrency,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// 片段起始时间：在 10%~90% 区间内均匀分布；视频过短时只取开头一段
+fn segment_starts(duration: f64) -> Vec<f64> {
+    if duration <= SEGMENT_COUNT as f64 * SEGMENT_SECS * 2.0 {
+        return vec![0.0];
+    }
+    let start = duration * 0.1;
+    let span = duration * 0.8 - SEGMENT_SECS;
+    let step = span / (SEGMENT_COUNT - 1) as f64;
+    (0..SEGMENT_COUNT).map(|i| start + step * i as f64).collect()
+}
+
+/// 构建 ffmpeg 的拼接滤镜
+fn build_filter(segment_count: usize) -> String {
+    let mut filter = String::new();
+    for i in 0..segment_count {
+        filter.push_str(&format!("[{}:v]scale={}:-2,setsar=1,fps=15[v{}];", i, PREVIEW_WIDTH, i));
+    }
+    for i in 0..segment_count {
+        filter.push_str(&format!("[v{}]", i));
+    }
+    filter.push_str(&format!("concat=n={}:v=1:a=0[out]", segment_count));
+    filter
+}
+
+/// 使用 ffmpeg 截取多个片段并拼接为无声 WebM
+fn generate_preview(video_path: &Path, output: &Path) -> Result<(), String> {
+    let duration = VideoProcessor::new()
+        .get_video_metadata_ffprobe(video_path)
+        .and_then(|metadata| metadata.duration)
+        .map(|d| d.as_secs_f64())
+        .filter(|d| *d > 0.0)
+        .ok_or("无法获取视频时长")?;
+
+    let starts = segment_starts(duration);
+    let segment_secs = if starts.len() == 1 {
+        duration.min(SEGMENT_COUNT as f64 * SEGMENT_SECS)
+    } else {
+        SEGMENT_SECS
+    };
+
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建预览缓存目录失败: {}", e))?;
+    }
+    // 先写入临时文件，避免前端读到未完成的预览
+    let partial = output.with_extension(format!("part.{}", PREVIEW_EXTENSION));
+
+    let mut cmd = media_command("ffmpeg");
+    cmd.args(["-v", "quiet", "-y"]);
+    for start in &starts {
+        cmd.args(["-ss", &format!("{:.3}", start), "-t", &format!("{:.3}", segment_secs), "-i"])
+            .arg(video_path);
+    }
+    cmd.args(["-filter_complex", &build_filter(starts.len()), "-map", "[out]", "-an"])
+        .args(["-c:v", "libvpx-vp9", "-b:v", "0", "-crf", "40", "-deadline", "realtime", "-cpu-used", "8"])
+        .arg(&partial);
+
+    let status = cmd.output().map_err(|e| format!("执行 ffmpeg 命令失败: {}", e))?.status;
+    if !status.success() || !partial.exists() {
+        let _ = fs::remove_file(&partial);
+        return Err("ffmpeg 生成预览失败".to_string());
+    }
+
+    fs::rename(&partial, output).map_err(|e| format!("保存预览失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_starts() {
+        assert_eq!(segment_starts(8.0), vec![0.0]);
+
+        let starts = segment_starts(100.0);
+        assert_eq!(starts.len(), SEGMENT_COUNT as usize);
+        assert_eq!(starts[0], 10.0);
+        assert!((starts[5] + SEGMENT_SECS - 90.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_build_filter() {
+        assert_eq!(
+            build_filter(2),
+            "[0:v]scale=320:-2,setsar=1,fps=15[v0];[1:v]scale=320:-2,setsar=1,fps=15[v1];[v0][v1]concat=n=2:v=1:a=0[out]"
+        );
+    }
+
+    #[test]
+    fn test_resolve_cached_file_rejects_traversal() {
+        let manager = PreviewManager::new(&std::env::temp_dir(), 2);
+        assert!(manager.resolve_cached_file("../secret").is_none());
+        assert!(manager.resolve_cached_file("a/b.webm").is_none());
+        assert!(manager.resolve_cached_file("").is_none());
+
+        // 生成中的临时文件不对外提供
+        let dir = std::env::temp_dir().join(format!("vv-preview-test-{}", uuid::Uuid::new_v4()));
+        let manager = PreviewManager::new(&dir, 2);
+        fs::create_dir_all(&manager.cache_dir).unwrap();
+        fs::write(manager.cache_dir.join("abc123.webm"), b"").unwrap();
+        fs::write(manager.cache_dir.join("def456.part.webm"), b"").unwrap();
+        assert!(manager.resolve_cached_file("abc123.webm").is_some());
+        assert!(manager.resolve_cached_file("def456.part.webm").is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,860 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use crate::collation::natural_cmp;
+use crate::annotations::VideoAnnotation;
+use crate::video::VideoInfo;
+
+/// 查询语法错误，`position` 为出错词在查询字符串中的字符位置（从 0 开始）
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryError {
+    pub position: usize,
+    pub token: String,
+    pub message: String,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.token.is_empty() {
+            write!(f, "查询语法错误（位置 {}，查询末尾）：{}", self.position + 1, self.message)
+        } else {
+            write!(f, "查询语法错误（位置 {}，「{}」）：{}", self.position + 1, self.token, self.message)
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// 比较运算符
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CmpOp {
+    /// `:` 字符串包含，其他类型等于
+    Match,
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl CmpOp {
+    fn test(self, ordering: Ordering) -> bool {
+        match self {
+            CmpOp::Match | CmpOp::Eq => ordering == Ordering::Equal,
+            CmpOp::Ne => ordering != Ordering::Equal,
+            CmpOp::Gt => ordering == Ordering::Greater,
+            CmpOp::Ge => ordering != Ordering::Less,
+            CmpOp::Lt => ordering == Ordering::Less,
+            CmpOp::Le => ordering != Ordering::Greater,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Word(String),
+    Str(String),
+    Op(CmpOp),
+    LParen,
+    RParen,
+    Not,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    /// 起始字符位置
+    position: usize,
+    /// 原始文本
+    text: String,
+}
+
+/// 词法分析
+fn lex(input: &str) -> Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let is_word_char = |c: char| !c.is_whitespace() && !"()<>=!:\"".contains(c);
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let kind = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => {
+                i += 1;
+                TokenKind::LParen
+            }
+            ')' => {
+                i += 1;
+                TokenKind::RParen
+            }
+            ':' => {
+                i += 1;
+                TokenKind::Op(CmpOp::Match)
+            }
+            '=' => {
+                i += if chars.get(i + 1) == Some(&'=') { 2 } else { 1 };
+                TokenKind::Op(CmpOp::Eq)
+            }
+            '!' if chars.get(i + 1) == Some(&'=') => {
+                i += 2;
+                TokenKind::Op(CmpOp::Ne)
+            }
+            '!' => {
+                i += 1;
+                TokenKind::Not
+            }
+            '>' | '<' => {
+                let or_equal = chars.get(i + 1) == Some(&'=');
+                i += if or_equal { 2 } else { 1 };
+                TokenKind::Op(match (c, or_equal) {
+                    ('>', true) => CmpOp::Ge,
+                    ('>', false) => CmpOp::Gt,
+                    ('<', true) => CmpOp::Le,
+                    _ => CmpOp::Lt,
+                })
+            }
+            '"' => {
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => {
+                            return Err(QueryError {
+                                position: start,
+                                token: chars[start..].iter().collect(),
+                                message: "引号没有闭合".to_string(),
+                            })
+                        }
+                        Some('"') => {
+                            i += 1;
+                            break;
+                        }
+                        Some('\\') if i + 1 < chars.len() => {
+                            value.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some(&c) => {
+                            value.push(c);
+                            i += 1;
+                        }
+                    }
+                }
+                TokenKind::Str(value)
+            }
+            _ => {
+                while i < chars.len() && is_word_char(chars[i]) {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                match word.to_lowercase().as_str() {
+                    "not" => TokenKind::Not,
+                    _ => TokenKind::Word(word),
+                }
+            }
+        };
+        tokens.push(Token {
+            kind,
+            position: start,
+            text: chars[start..i].iter().collect(),
+        });
+    }
+    Ok(tokens)
+}
+
+/// 数值字段
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NumberField {
+    Width,
+    Height,
+    /// 短边像素（兼容竖屏视频）
+    Resolution,
+    FrameRate,
+    BitRate,
+    Size,
+    /// 秒
+    Duration,
+    /// 星级评分
+    Rating,
+}
+
+/// 字符串字段
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TextField {
+    Codec,
+    AudioCodec,
+    Container,
+    Name,
+    Title,
+    Root,
+    Notes,
+}
+
+/// 日期字段
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DateField {
+    Modified,
+    Created,
+}
+
+/// 查询条件
+#[derive(Debug, Clone, PartialEq)]
+enum Predicate {
+    Number(NumberField, CmpOp, f64),
+    Text(TextField, CmpOp, String),
+    Date(DateField, CmpOp, NaiveDate),
+    /// 相对根文件夹路径的通配符匹配
+    PathGlob(CmpOp, String),
+    Tag(CmpOp, String),
+    Favorite(bool),
+    /// 是否已看完
+    Watched(bool),
+    /// 不带字段的词：匹配编码或文件名
+    Bare(String),
+}
+
+/// 查询表达式
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Predicate(Predicate),
+}
+
+/// 已解析的查询
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    expr: Option<Expr>,
+}
+
+/// 求值时视频所在的上下文
+pub struct VideoContext<'a> {
+    pub video: &'a VideoInfo,
+    /// 根文件夹 ID
+    pub root_id: &'a str,
+    /// 根文件夹名称
+    pub root_name: &'a str,
+    /// 相对根文件夹的 `/` 分隔路径
+    pub relative_path: &'a str,
+    /// 视频的标签
+    pub tags: &'a [String],
+    /// 评分、收藏与备注
+    pub annotation: Option<&'a VideoAnnotation>,
+    /// 是否已看完
+    pub watched: bool,
+}
+
+impl Query {
+    /// 解析查询表达式，空字符串匹配所有视频
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        let tokens = lex(input)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end: input.chars().count(),
+        };
+        if parser.tokens.is_empty() {
+            return Ok(Self { expr: None });
+        }
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(parser.error_at(token.clone(), "多余的右括号或无法识别的内容"));
+        }
+        Ok(Self { expr: Some(expr) })
+    }
+
+    /// 视频是否满足查询
+    pub fn matches(&self, context: &VideoContext) -> bool {
+        self.expr.as_ref().map_or(true, |expr| evaluate(expr, context))
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// 查询字符串长度（用于指向末尾的错误）
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn error_at(&self, token: Token, message: &str) -> QueryError {
+        QueryError {
+            position: token.position,
+            token: token.text,
+            message: message.to_string(),
+        }
+    }
+
+    fn error_at_end(&self, message: &str) -> QueryError {
+        QueryError {
+            position: self.end,
+            token: String::new(),
+            message: message.to_string(),
+        }
+    }
+
+    fn is_keyword(token: Option<&Token>, keyword: &str) -> bool {
+        matches!(token, Some(Token { kind: TokenKind::Word(word), .. }) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, QueryError> {
+        let mut left = self.parse_and()?;
+        while Self::is_keyword(self.peek(), "or") || self.peek().is_some_and(|t| t.text == "||") {
+            self.next();
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    /// `and` 可以省略：相邻的条件默认为“且”
+    fn parse_and(&mut self) -> Result<Expr, QueryError> {
+        let mut left = self.parse_unary()?;
+        loop {
+            if Self::is_keyword(self.peek(), "and") || self.peek().is_some_and(|t| t.text == "&&") {
+                self.next();
+            } else if self.peek().map_or(true, |t| t.kind == TokenKind::RParen || Self::is_keyword(Some(t), "or") || t.text == "||") {
+                break;
+            }
+            let right = self.parse_unary()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, QueryError> {
+        let Some(token) = self.next() else {
+            return Err(self.error_at_end("此处需要查询条件"));
+        };
+        match &token.kind {
+            TokenKind::Not => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            TokenKind::LParen => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token { kind: TokenKind::RParen, .. }) => Ok(expr),
+                    Some(other) => Err(self.error_at(other, "此处需要右括号")),
+                    None => Err(self.error_at(token, "括号没有闭合")),
+                }
+            }
+            TokenKind::Word(word) if ["and", "or", "&&", "||"].contains(&word.to_lowercase().as_str()) => {
+                Err(self.error_at(token.clone(), "此处需要查询条件"))
+            }
+            TokenKind::Word(word) | TokenKind::Str(word) => {
+                let word = word.clone();
+                match self.peek() {
+                    Some(Token { kind: TokenKind::Op(op), .. }) if matches!(token.kind, TokenKind::Word(_)) => {
+                        let op = *op;
+                        let op_token = self.next().expect("已确认存在运算符");
+                        let value = match self.next() {
+                            Some(Token { kind: TokenKind::Word(value) | TokenKind::Str(value), .. }) => value,
+                            Some(other) => return Err(self.error_at(other, "此处需要值")),
+                            None => return Err(self.error_at_end("运算符后缺少值")),
+                        };
+                        let value_token = self.tokens[self.pos - 1].clone();
+                        parse_predicate(&token, &op_token, op, &value_token, &value).map(Expr::Predicate)
+                    }
+                    _ => Ok(Expr::Predicate(Predicate::Bare(word.to_lowercase()))),
+                }
+            }
+            TokenKind::RParen | TokenKind::Op(_) => Err(self.error_at(token, "此处需要查询条件")),
+        }
+    }
+}
+
+/// 根据字段名解析条件
+fn parse_predicate(field_token: &Token, op_token: &Token, op: CmpOp, value_token: &Token, value: &str) -> Result<Predicate, QueryError> {
+    let error = |token: &Token, message: String| QueryError {
+        position: token.position,
+        token: token.text.clone(),
+        message,
+    };
+    let number = |field: NumberField, parsed: Option<f64>, expected: &str| {
+        parsed
+            .map(|number| Predicate::Number(field, op, number))
+            .ok_or_else(|| error(value_token, format!("无法解析为{}", expected)))
+    };
+    let text = |field: TextField| match op {
+        CmpOp::Match | CmpOp::Eq | CmpOp::Ne => Ok(Predicate::Text(field, op, value.to_lowercase())),
+        _ => Err(error(op_token, "文本字段只支持 :、= 和 !=".to_string())),
+    };
+
+    match field_token.text.to_lowercase().as_str() {
+        "width" => number(NumberField::Width, parse_resolution(value), "像素"),
+        "height" => number(NumberField::Height, parse_resolution(value), "像素"),
+        "resolution" | "res" => number(NumberField::Resolution, parse_resolution(value), "分辨率（如 1080p、4k）"),
+        "fps" | "frame_rate" | "framerate" => number(NumberField::FrameRate, value.parse().ok(), "帧率"),
+        "bitrate" | "bit_rate" => number(NumberField::BitRate, parse_bit_rate(value), "比特率（如 8m、500k）"),
+        "size" => number(NumberField::Size, parse_size(value), "文件大小（如 700mb、4gb）"),
+        "duration" | "length" => number(NumberField::Duration, parse_duration(value), "时长（如 90m、1h30m、45s）"),
+        "codec" | "vcodec" => text(TextField::Codec),
+        "audio" | "acodec" | "audio_codec" => text(TextField::AudioCodec),
+        "container" | "format" | "ext" => text(TextField::Container),
+        "name" => text(TextField::Name),
+        "title" => text(TextField::Title),
+        "root" => text(TextField::Root),
+        "modified" | "created" => {
+            let field = if field_token.text.eq_ignore_ascii_case("modified") { DateField::Modified } else { DateField::Created };
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map(|date| Predicate::Date(field, op, date))
+                .map_err(|_| error(value_token, "无法解析为日期（格式为 YYYY-MM-DD）".to_string()))
+        }
+        "path" => match op {
+            CmpOp::Match | CmpOp::Eq | CmpOp::Ne => Ok(Predicate::PathGlob(op, value.to_lowercase())),
+            _ => Err(error(op_token, "path 只支持 :、= 和 !=".to_string())),
+        },
+        "tag" => match op {
+            CmpOp::Match | CmpOp::Eq | CmpOp::Ne => Ok(Predicate::Tag(op, value.to_lowercase())),
+            _ => Err(error(op_token, "tag 只支持 :、= 和 !=".to_string())),
+        },
+        "rating" | "stars" => number(NumberField::Rating, value.parse().ok(), "评分（1～5）"),
+        "notes" | "note" => text(TextField::Notes),
+        "favorite" | "fav" | "watched" => {
+            let name = if field_token.text.eq_ignore_ascii_case("watched") { "watched" } else { "favorite" };
+            let expected = match value.to_lowercase().as_str() {
+                "yes" | "true" | "1" => true,
+                "no" | "false" | "0" => false,
+                _ => return Err(error(value_token, format!("{} 的值只能是 yes 或 no", name))),
+            };
+            let expected = match op {
+                CmpOp::Match | CmpOp::Eq => expected,
+                CmpOp::Ne => !expected,
+                _ => return Err(error(op_token, format!("{} 只支持 :、= 和 !=", name))),
+            };
+            Ok(if name == "watched" { Predicate::Watched(expected) } else { Predicate::Favorite(expected) })
+        }
+        _ => Err(error(field_token, "未知字段".to_string())),
+    }
+}
+
+/// 带单位的数值：返回（数值，小写单位）
+fn split_unit(value: &str) -> Option<(f64, String)> {
+    let value = value.trim().to_lowercase();
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let number: f64 = value[..split].parse().ok()?;
+    Some((number, value[split..].to_string()))
+}
+
+/// 分辨率：像素数，或 720p、1080p、4k、8k 等写法
+fn parse_resolution(value: &str) -> Option<f64> {
+    let (number, unit) = split_unit(value)?;
+    match unit.as_str() {
+        "" | "p" | "px" => Some(number),
+        "k" => Some(match number as u32 {
+            2 => 1080.0,
+            4 => 2160.0,
+            8 => 4320.0,
+            _ => number * 540.0,
+        }),
+        _ => None,
+    }
+}
+
+/// 比特率（bit/s）：默认单位为 bit/s，支持 k、m（可带 bps 后缀）
+fn parse_bit_rate(value: &str) -> Option<f64> {
+    let (number, unit) = split_unit(value)?;
+    let factor = match unit.trim_end_matches("bps").trim_end_matches('b') {
+        "" => 1.0,
+        "k" => 1_000.0,
+        "m" => 1_000_000.0,
+        "g" => 1_000_000_000.0,
+        _ => return None,
+    };
+    Some(number * factor)
+}
+
+/// 文件大小（字节）：支持 b、kb、mb、gb、tb（1024 进制）
+fn parse_size(value: &str) -> Option<f64> {
+    let (number, unit) = split_unit(value)?;
+    let exponent = match unit.trim_end_matches("ib").trim_end_matches('b') {
+        "" => 0,
+        "k" => 1,
+        "m" => 2,
+        "g" => 3,
+        "t" => 4,
+        _ => return None,
+    };
+    Some(number * 1024f64.powi(exponent))
+}
+
+/// 时长（秒）：支持 h、m、s 的组合（如 1h30m），不带单位时为秒
+fn parse_duration(value: &str) -> Option<f64> {
+    let value = value.trim().to_lowercase();
+    if let Ok(seconds) = value.parse::<f64>() {
+        return Some(seconds);
+    }
+    let mut total = 0.0;
+    let mut rest = value.as_str();
+    while !rest.is_empty() {
+        let (number, unit) = split_unit(rest)?;
+        let unit_len = unit.find(|c: char| c.is_ascii_digit()).unwrap_or(unit.len());
+        let factor = match &unit[..unit_len] {
+            "h" => 3600.0,
+            "m" | "min" => 60.0,
+            "s" => 1.0,
+            _ => return None,
+        };
+        total += number * factor;
+        rest = &rest[rest.len() - (unit.len() - unit_len)..];
+    }
+    Some(total)
+}
+
+/// 编码名称别名（h265 → hevc 等）
+fn normalize_codec(codec: &str) -> &str {
+    match codec {
+        "h265" | "x265" | "hevc" => "hevc",
+        "h264" | "x264" | "avc" | "avc1" => "h264",
+        other => other,
+    }
+}
+
+fn is_codec_name(word: &str) -> bool {
+    matches!(normalize_codec(word), "hevc" | "h264" | "av1" | "vp8" | "vp9" | "mpeg4" | "mpeg2video" | "prores" | "aac" | "ac3" | "eac3" | "dts" | "flac" | "opus" | "mp3" | "truehd")
+}
+
+/// 字符串比较：`:` 为包含，`=` / `!=` 为（不）相等
+fn compare_text(actual: Option<&str>, op: CmpOp, expected: &str) -> bool {
+    let actual = actual.map(str::to_lowercase);
+    match op {
+        CmpOp::Match => actual.is_some_and(|actual| actual.contains(expected)),
+        CmpOp::Ne => actual.as_deref() != Some(expected),
+        _ => actual.as_deref() == Some(expected),
+    }
+}
+
+/// 通配符匹配：`*` 匹配除 `/` 外的任意字符，`**` 匹配任意字符（含 `/`），`?` 匹配单个字符
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    /// 按（模式位置，文本位置）记忆化，避免多个 `**` 回溯时的指数级复杂度
+    struct Matcher {
+        pattern: Vec<char>,
+        text: Vec<char>,
+        memo: Vec<Option<bool>>,
+    }
+
+    impl Matcher {
+        fn matches(&mut self, p: usize, t: usize) -> bool {
+            let slot = p * (self.text.len() + 1) + t;
+            if let Some(result) = self.memo[slot] {
+                return result;
+            }
+            let result = match self.pattern.get(p) {
+                None => t == self.text.len(),
+                Some('*') if self.pattern.get(p + 1) == Some(&'*') => {
+                    let rest = p + 2;
+                    // `**/` 也可以匹配零层目录
+                    let rest_without_slash = if self.pattern.get(rest) == Some(&'/') { rest + 1 } else { rest };
+                    (t..=self.text.len()).any(|i| self.matches(rest, i) || self.matches(rest_without_slash, i))
+                }
+                Some('*') => {
+                    // `*` 不跨越目录分隔符
+                    let end = self.text[t..].iter().position(|&c| c == '/').map_or(self.text.len(), |i| t + i);
+                    (t..=end).any(|i| self.matches(p + 1, i))
+                }
+                Some('?') => t < self.text.len() && self.text[t] != '/' && self.matches(p + 1, t + 1),
+                Some(&c) => self.text.get(t) == Some(&c) && self.matches(p + 1, t + 1),
+            };
+            self.memo[slot] = Some(result);
+            result
+        }
+    }
+
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let memo = vec![None; (pattern.len() + 1) * (text.len() + 1)];
+    Matcher { pattern, text, memo }.matches(0, 0)
+}
+
+fn evaluate(expr: &Expr, context: &VideoContext) -> bool {
+    match expr {
+        Expr::And(left, right) => evaluate(left, context) && evaluate(right, context),
+        Expr::Or(left, right) => evaluate(left, context) || evaluate(right, context),
+        Expr::Not(inner) => !evaluate(inner, context),
+        Expr::Predicate(predicate) => evaluate_predicate(predicate, context),
+    }
+}
+
+fn evaluate_predicate(predicate: &Predicate, context: &VideoContext) -> bool {
+    let video = context.video;
+    match predicate {
+        Predicate::Number(field, op, expected) => {
+            let actual = match field {
+                NumberField::Width => video.resolution.map(|(w, _)| w as f64),
+                NumberField::Height => video.resolution.map(|(_, h)| h as f64),
+                NumberField::Resolution => video.resolution.map(|(w, h)| w.min(h) as f64),
+                NumberField::FrameRate => video.frame_rate,
+                NumberField::BitRate => video.bit_rate.map(|b| b as f64),
+                NumberField::Size => Some(video.size as f64),
+                NumberField::Duration => video.duration.map(|d| d.as_secs_f64()),
+                NumberField::Rating => context.annotation.and_then(|a| a.rating).map(f64::from),
+            };
+            actual.is_some_and(|actual| op.test(actual.total_cmp(expected)))
+        }
+        Predicate::Text(field, op, expected) => {
+            let actual = match field {
+                TextField::Codec | TextField::AudioCodec => {
+                    let codec = if *field == TextField::Codec { &video.codec } else { &video.audio_codec };
+                    let actual = codec.as_deref().map(|c| normalize_codec(&c.to_lowercase()).to_string());
+                    return compare_text(actual.as_deref(), *op, normalize_codec(expected));
+                }
+                TextField::Container => container_name(video),
+                TextField::Name => Some(video.name.clone()),
+                TextField::Title => video.title.clone(),
+                TextField::Notes => context.annotation.and_then(|a| a.notes.clone()),
+                TextField::Root => {
+                    // 根文件夹可以用 ID 或名称指定
+                    let positive = if *op == CmpOp::Ne { CmpOp::Eq } else { *op };
+                    let matched = [context.root_id, context.root_name]
+                        .iter()
+                        .any(|actual| compare_text(Some(actual), positive, expected));
+                    return matched != (*op == CmpOp::Ne);
+                }
+            };
+            compare_text(actual.as_deref(), *op, expected)
+        }
+        Predicate::Date(field, op, expected) => {
+            let time = match field {
+                DateField::Modified => video.modified_time,
+                DateField::Created => video.created_time,
+            };
+            time.map(|time| DateTime::<Utc>::from(time).date_naive())
+                .is_some_and(|date| op.test(date.cmp(expected)))
+        }
+        Predicate::PathGlob(op, pattern) => {
+            let matched = glob_match(pattern, context.relative_path);
+            if *op == CmpOp::Ne { !matched } else { matched }
+        }
+        Predicate::Tag(op, expected) => {
+            let tagged = context.tags.iter().any(|tag| {
+                let tag = tag.to_lowercase();
+                if *op == CmpOp::Match { tag.contains(expected.as_str()) } else { tag == *expected }
+            });
+            if *op == CmpOp::Ne { !tagged } else { tagged }
+        }
+        Predicate::Favorite(expected) => context.annotation.is_some_and(|a| a.favorite) == *expected,
+        Predicate::Watched(expected) => context.watched == *expected,
+        Predicate::Bare(word) => {
+            if is_codec_name(word) {
+                let codec = normalize_codec(word);
+                [&video.codec, &video.audio_codec]
+                    .iter()
+                    .any(|c| c.as_deref().is_some_and(|c| normalize_codec(&c.to_lowercase()) == codec))
+            } else {
+                video.name.to_lowercase().contains(word.as_str())
+                    || video.title.as_deref().is_some_and(|title| title.to_lowercase().contains(word.as_str()))
+            }
+        }
+    }
+}
+
+/// 容器格式：优先使用探测结果的第一个格式名，否则使用扩展名
+fn container_name(video: &VideoInfo) -> Option<String> {
+    video
+        .container_format
+        .as_deref()
+        .and_then(|format| format.split(',').next())
+        .map(|format| format.trim().to_lowercase())
+        .filter(|format| !format.is_empty())
+        .or_else(|| video.path.extension().map(|ext| ext.to_string_lossy().to_lowercase()))
+}
+
+/// 排序字段
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    #[default]
+    Name,
+    Path,
+    Size,
+    Duration,
+    Resolution,
+    BitRate,
+    Modified,
+    Created,
+    /// 星级评分（未评分的排在最后）
+    Rating,
+}
+
+/// 排序方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuerySort {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+impl QuerySort {
+    /// 比较两个视频；缺少该字段的视频总排在最后，相同时按路径排序保证结果稳定
+    pub fn compare(&self, a: &VideoInfo, b: &VideoInfo) -> Ordering {
+        fn by<T: PartialOrd>(a: Option<T>, b: Option<T>, descending: bool) -> Ordering {
+            match (a, b) {
+                (Some(a), Some(b)) => {
+                    let ordering = a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+                    if descending { ordering.reverse() } else { ordering }
+                }
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+        }
+        let descending = self.descending;
+        let ordering = match self.key {
+            SortKey::Name => {
+                let ordering = natural_cmp(&a.name, &b.name);
+                if descending { ordering.reverse() } else { ordering }
+            }
+            SortKey::Path => by(Some(&a.path), Some(&b.path), descending),
+            SortKey::Size => by(Some(a.size), Some(b.size), descending),
+            SortKey::Duration => by(a.duration, b.duration, descending),
+            SortKey::Resolution => by(
+                a.resolution.map(|(w, h)| w as u64 * h as u64),
+                b.resolution.map(|(w, h)| w as u64 * h as u64),
+                descending,
+            ),
+            SortKey::BitRate => by(a.bit_rate, b.bit_rate, descending),
+            SortKey::Modified => by(a.modified_time, b.modified_time, descending),
+            SortKey::Created => by(a.created_time, b.created_time, descending),
+            SortKey::Rating => by(
+                a.annotation.as_ref().and_then(|a| a.rating),
+                b.annotation.as_ref().and_then(|b| b.rating),
+                descending,
+            ),
+        };
+        ordering.then_with(|| a.path.cmp(&b.path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn video(name: &str, codec: &str, resolution: (u32, u32), minutes: u64, size: u64) -> VideoInfo {
+        VideoInfo {
+            size,
+            duration: Some(Duration::from_secs(minutes * 60)),
+            resolution: Some(resolution),
+            codec: Some(codec.to_string()),
+            audio_codec: Some("aac".to_string()),
+            container_format: Some("matroska,webm".to_string()),
+            frame_rate: Some(23.976),
+            bit_rate: Some(8_000_000),
+            ..crate::test_support::video(&format!("/media/movies/anime/{}", name))
+        }
+    }
+
+    fn matches(query: &str, video: &VideoInfo, tags: &[String]) -> bool {
+        let relative_path = format!("anime/{}", video.name);
+        Query::parse(query).unwrap().matches(&VideoContext {
+            video,
+            root_id: "root-1",
+            root_name: "Movies",
+            relative_path: &relative_path,
+            tags,
+            annotation: video.annotation.as_ref(),
+            watched: tags.iter().any(|tag| tag == "Watched"),
+        })
+    }
+
+    #[test]
+    fn test_query_evaluation() {
+        let uhd = video("Dune.mkv", "hevc", (3840, 2160), 155, 40 * 1024 * 1024 * 1024);
+        let hd = video("Short.mkv", "h264", (1920, 1080), 20, 700 * 1024 * 1024);
+        let watched = vec!["Watched".to_string()];
+
+        let query = "h265 and height>=2160 and duration>90m and not tag:watched";
+        assert!(matches(query, &uhd, &[]));
+        assert!(!matches(query, &uhd, &watched));
+        assert!(!matches(query, &hd, &[]));
+
+        assert!(matches("resolution>=4k size>10gb", &uhd, &[]));
+        assert!(matches("codec=h264 or fps>60", &hd, &[]));
+        assert!(matches("(container:matroska) and path:anime/*.mkv", &hd, &[]));
+        assert!(matches("path:**/short.mkv root:movies bitrate>=8m", &hd, &[]));
+        assert!(!matches("!(duration<1h30m)", &hd, &[]));
+        assert!(matches("name:\"dune\" audio:aac", &uhd, &[]));
+        assert!(matches("", &hd, &[]));
+
+        let mut rated = uhd.clone();
+        rated.annotation = Some(VideoAnnotation {
+            rating: Some(5),
+            favorite: true,
+            notes: Some("IMAX 版本".to_string()),
+            updated_at: None,
+        });
+        assert!(matches("rating>=4 and favorite:yes and notes:imax", &rated, &[]));
+        assert!(matches("fav:no and not rating>0", &uhd, &[]));
+        assert!(Query::parse("favorite:maybe").is_err());
+        assert!(matches("watched:yes", &uhd, &watched));
+        assert!(matches("watched!=yes", &hd, &[]));
+    }
+
+    #[test]
+    fn test_parse_errors_point_at_token() {
+        let error = Query::parse("codec:hevc and heigth>=2160").unwrap_err();
+        assert_eq!((error.position, error.token.as_str()), (15, "heigth"));
+
+        let error = Query::parse("duration>90x").unwrap_err();
+        assert_eq!((error.position, error.token.as_str()), (9, "90x"));
+
+        let error = Query::parse("(codec:hevc or size>1gb").unwrap_err();
+        assert_eq!((error.position, error.token.as_str()), (0, "("));
+
+        let error = Query::parse("codec>=hevc").unwrap_err();
+        assert_eq!(error.token, ">=");
+
+        let error = Query::parse("size>").unwrap_err();
+        assert_eq!((error.position, error.token.as_str()), (5, ""));
+        assert!(error.to_string().contains("末尾"));
+
+        let error = Query::parse("h265 and and 4k").unwrap_err();
+        assert_eq!((error.position, error.token.as_str()), (9, "and"));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("anime/*.mkv", "Anime/Short.mkv"));
+        assert!(!glob_match("*.mkv", "anime/short.mkv"));
+        assert!(glob_match("**/short.mkv", "short.mkv"));
+        assert!(glob_match("**/s?ort.mkv", "a/b/short.mkv"));
+        assert!(!glob_match("a/?", "a/"));
+        // 多个 `**` 不会导致指数级回溯
+        let text = format!("{}/b", "a/".repeat(200));
+        assert!(!glob_match("**a**a**a**a**a**a**a**a**c", &text));
+        assert!(glob_match("**a**a**a**a**a**a**a**a**b", &text));
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut videos = [
+            video("b.mkv", "hevc", (1920, 1080), 10, 300),
+            video("a.mkv", "hevc", (3840, 2160), 20, 100),
+        ];
+        videos[1].duration = None;
+        let sort = QuerySort { key: SortKey::Duration, descending: true };
+        videos.sort_by(|a, b| sort.compare(a, b));
+        assert_eq!(videos[0].name, "b.mkv");
+
+        let sort = QuerySort { key: SortKey::Size, descending: false };
+        videos.sort_by(|a, b| sort.compare(a, b));
+        assert_eq!(videos[0].name, "a.mkv");
+    }
+}
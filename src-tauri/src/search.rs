@@ -0,0 +1,393 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use crate::folder::DirectoryNode;
+use crate::video::VideoInfo;
+
+/// 前缀匹配的得分系数
+const PREFIX_FACTOR: f32 = 0.8;
+/// 模糊匹配的得分系数
+const FUZZY_FACTOR: f32 = 0.5;
+/// 文件名包含完整查询字符串时的额外得分
+const PHRASE_BONUS: f32 = 2.0;
+/// 单次返回的最大结果数
+const MAX_LIMIT: usize = 1000;
+
+/// 搜索结果（按相关度排序）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResults {
+    /// 匹配的视频总数
+    pub total: usize,
+    /// 当前页的视频
+    pub videos: Vec<VideoInfo>,
+}
+
+/// 被索引的字段
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    /// 文件名（不含扩展名）
+    Name,
+    /// 内嵌标题
+    Title,
+    /// 所在目录名（相对根文件夹）
+    Directory,
+    /// 标签名称（包括上级目录的标签）
+    Tag,
+    /// 用户备注
+    Notes,
+}
+
+impl Field {
+    fn weight(self) -> f32 {
+        match self {
+            Field::Name => 3.0,
+            Field::Title => 2.0,
+            Field::Directory => 1.0,
+            Field::Tag => 2.0,
+            Field::Notes => 1.0,
+        }
+    }
+}
+
+/// 目录树之外需要索引的用户数据
+#[derive(Debug, Clone, Default)]
+pub struct DocumentExtras {
+    /// 标签名称（包括上级目录的标签）
+    pub tags: Vec<String>,
+    /// 备注
+    pub notes: Option<String>,
+}
+
+/// 词条在某个视频中出现的位置与权重
+#[derive(Debug, Clone, Copy)]
+struct Posting {
+    doc: usize,
+    weight: f32,
+}
+
+/// 跨所有根文件夹的全文索引，在目录树或标签的版本号变化时重建
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    /// 建立索引时的版本号
+    revision: Option<u64>,
+    /// 被索引的视频
+    documents: Vec<VideoInfo>,
+    /// 各视频的标签名称（小写，用于按标签筛选）
+    tags: Vec<Vec<String>>,
+    /// 词条 → 倒排列表（有序，便于前缀查找）
+    terms: BTreeMap<String, Vec<Posting>>,
+}
+
+impl SearchIndex {
+    /// 索引是否与指定版本号一致
+    pub fn is_current(&self, revision: u64) -> bool {
+        self.revision == Some(revision)
+    }
+
+    /// 根据所有根文件夹的目录树重建索引，`extras_of` 返回视频的标签与备注
+    pub fn rebuild<'a>(
+        &mut self,
+        revision: u64,
+        trees: impl IntoIterator<Item = &'a DirectoryNode>,
+        extras_of: impl Fn(&VideoInfo) -> DocumentExtras,
+    ) {
+        self.documents.clear();
+        self.tags.clear();
+        self.terms.clear();
+        for tree in trees {
+            let root_path = Path::new(&tree.path);
+            for video in tree.all_videos() {
+                self.add_document(root_path, video, extras_of(video));
+            }
+        }
+        self.revision = Some(revision);
+        println!("搜索索引已重建: {} 个视频，{} 个词条", self.documents.len(), self.terms.len());
+    }
+
+    fn add_document(&mut self, root_path: &Path, video: &VideoInfo, extras: DocumentExtras) {
+        let doc = self.documents.len();
+        let mut weights: HashMap<String, f32> = HashMap::new();
+        let mut add = |text: &str, field: Field| {
+            for term in tokenize(text) {
+                let weight = weights.entry(term).or_insert(0.0);
+                *weight = weight.max(field.weight());
+            }
+        };
+
+        let stem = video
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| video.name.clone());
+        add(&stem, Field::Name);
+        if let Some(title) = &video.title {
+            add(title, Field::Title);
+        }
+        if let Some(dir) = video.path.parent().and_then(|parent| parent.strip_prefix(root_path).ok()) {
+            for component in dir.components() {
+                add(&component.as_os_str().to_string_lossy(), Field::Directory);
+            }
+        }
+        for tag in &extras.tags {
+            add(tag, Field::Tag);
+        }
+        if let Some(notes) = &extras.notes {
+            add(notes, Field::Notes);
+        }
+
+        for (term, weight) in weights {
+            self.terms.entry(term).or_default().push(Posting { doc, weight });
+        }
+        self.documents.push(video.clone());
+        self.tags.push(extras.tags.iter().map(|tag| tag.to_lowercase()).collect());
+    }
+
+    /// 搜索视频：查询中的每个词都必须匹配（精确、前缀或模糊），且必须带有 `tags` 中的所有标签，
+    /// 按相关度排序后分页返回；查询为空时返回带有这些标签的所有视频
+    pub fn search(&self, query: &str, tags: &[String], limit: usize, offset: usize) -> SearchResults {
+        let query_terms = tokenize_query(query);
+        if query_terms.is_empty() && tags.is_empty() {
+            return SearchResults { total: 0, videos: Vec::new() };
+        }
+
+        let required: Vec<String> = tags.iter().map(|tag| tag.trim().to_lowercase()).collect();
+        let mut scores: Option<HashMap<usize, f32>> = (!required.is_empty()).then(|| {
+            (0..self.documents.len())
+                .filter(|&doc| required.iter().all(|tag| self.tags[doc].contains(tag)))
+                .map(|doc| (doc, 0.0))
+                .collect()
+        });
+        for term in &query_terms {
+            let term_scores = self.match_term(term);
+            scores = Some(match scores {
+                None => term_scores,
+                Some(previous) => previous
+                    .into_iter()
+                    .filter_map(|(doc, score)| term_scores.get(&doc).map(|s| (doc, score + s)))
+                    .collect(),
+            });
+        }
+
+        let phrase = query.trim().to_lowercase();
+        let mut ranked: Vec<(usize, f32)> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(doc, score)| {
+                let bonus = if self.documents[doc].name.to_lowercase().contains(&phrase) { PHRASE_BONUS } else { 0.0 };
+                (doc, score + bonus)
+            })
+            .collect();
+        ranked.sort_by(|(a, score_a), (b, score_b)| {
+            let (a, b) = (&self.documents[*a], &self.documents[*b]);
+            score_b
+                .total_cmp(score_a)
+                .then_with(|| a.name.cmp(&b.name))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+
+        SearchResults {
+            total: ranked.len(),
+            videos: ranked
+                .into_iter()
+                .skip(offset)
+                .take(limit.min(MAX_LIMIT))
+                .map(|(doc, _)| self.documents[doc].clone())
+                .collect(),
+        }
+    }
+
+    /// 单个查询词在各视频中的最佳得分
+    fn match_term(&self, term: &str) -> HashMap<usize, f32> {
+        let mut scores: HashMap<usize, f32> = HashMap::new();
+        let mut record = |postings: &[Posting], factor: f32| {
+            for posting in postings {
+                let score = scores.entry(posting.doc).or_insert(0.0);
+                *score = score.max(posting.weight * factor);
+            }
+        };
+
+        // 精确与前缀匹配
+        for (candidate, postings) in self.terms.range(term.to_string()..) {
+            if !candidate.starts_with(term) {
+                break;
+            }
+            record(postings, if candidate == term { 1.0 } else { PREFIX_FACTOR });
+        }
+
+        // 模糊匹配：只对较长的非 CJK 词生效，容忍 1～2 个字符的差异
+        let length = term.chars().count();
+        if length >= 4 && !term.chars().any(is_cjk) {
+            let max_distance = if length >= 8 { 2 } else { 1 };
+            for (candidate, postings) in &self.terms {
+                if candidate.chars().count().abs_diff(length) > max_distance {
+                    continue;
+                }
+                if edit_distance(term, candidate, max_distance).is_some() {
+                    record(postings, FUZZY_FACTOR);
+                }
+            }
+        }
+        scores
+    }
+}
+
+/// 是否为 CJK 字符（中日韩统一表意文字、假名、谚文）
+pub fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF     // 平假名、片假名
+        | 0x3400..=0x4DBF   // 扩展 A
+        | 0x4E00..=0x9FFF   // 统一表意文字
+        | 0xAC00..=0xD7AF   // 谚文音节
+        | 0xF900..=0xFAFF   // 兼容表意文字
+        | 0x20000..=0x2FFFF // 扩展 B 及以后
+    )
+}
+
+/// 文本分段：连续的字母数字为一段，连续的 CJK 字符为一段（第二项为 true），其余字符作为分隔符
+fn segments(text: &str) -> Vec<(String, bool)> {
+    let mut segments: Vec<(String, bool)> = Vec::new();
+    for c in text.to_lowercase().chars() {
+        let cjk = is_cjk(c);
+        if !cjk && !c.is_alphanumeric() {
+            segments.push((String::new(), false));
+            continue;
+        }
+        match segments.last_mut() {
+            Some((segment, is_cjk_segment)) if *is_cjk_segment == cjk => segment.push(c),
+            _ => segments.push((c.to_string(), cjk)),
+        }
+    }
+    segments.retain(|(segment, _)| !segment.is_empty());
+    segments
+}
+
+/// 建立索引用的分词：CJK 按单字与相邻双字切分，字母数字混合的词另外拆出字母段与数字段
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for (segment, cjk) in segments(text) {
+        if cjk {
+            let chars: Vec<char> = segment.chars().collect();
+            tokens.extend(chars.iter().map(|c| c.to_string()));
+            tokens.extend(chars.windows(2).map(|pair| pair.iter().collect::<String>()));
+        } else {
+            let parts = split_letters_digits(&segment);
+            if parts.len() > 1 {
+                tokens.extend(parts);
+            }
+            tokens.push(segment);
+        }
+    }
+    tokens
+}
+
+/// 查询用的分词：CJK 段按相邻双字切分（单字时为该字），使多字查询要求各字相邻出现
+fn tokenize_query(text: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    for (segment, cjk) in segments(text) {
+        let chars: Vec<char> = segment.chars().collect();
+        if cjk && chars.len() > 1 {
+            tokens.extend(chars.windows(2).map(|pair| pair.iter().collect::<String>()));
+        } else {
+            tokens.push(segment);
+        }
+    }
+    tokens.dedup();
+    tokens
+}
+
+/// 按字母与数字的边界拆分（s01e02 → s、01、e、02）
+fn split_letters_digits(word: &str) -> Vec<String> {
+    let mut parts: Vec<String> = Vec::new();
+    let mut previous_digit: Option<bool> = None;
+    for c in word.chars() {
+        let digit = c.is_ascii_digit();
+        if previous_digit == Some(digit) {
+            if let Some(part) = parts.last_mut() {
+                part.push(c);
+            }
+        } else {
+            parts.push(c.to_string());
+        }
+        previous_digit = Some(digit);
+    }
+    parts
+}
+
+/// 编辑距离（不超过 `max` 时返回距离，否则返回 None）
+fn edit_distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        if current.iter().min().is_some_and(|&min| min > max) {
+            return None;
+        }
+        previous = current;
+    }
+    previous.last().copied().filter(|&distance| distance <= max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{dir, video};
+
+    #[test]
+    fn test_tokenize_cjk_and_mixed_words() {
+        assert_eq!(tokenize("巨人S01"), vec!["巨", "人", "巨人", "s", "01", "s01"]);
+        assert_eq!(tokenize_query("进击的巨人"), vec!["进击", "击的", "的巨", "巨人"]);
+        assert_eq!(edit_distance("intrstellar", "interstellar", 2), Some(1));
+        assert_eq!(edit_distance("kitten", "sitting", 2), None);
+    }
+
+    #[test]
+    fn test_search_ranking_prefix_and_fuzzy() {
+        let tree = dir(
+            "/media/movies",
+            vec![dir(
+                "/media/movies/科幻电影",
+                Vec::new(),
+                vec![
+                    VideoInfo { title: Some("星际穿越".to_string()), ..video("/media/movies/科幻电影/Interstellar.2014.mkv") },
+                    video("/media/movies/科幻电影/Arrival.mkv"),
+                ],
+            )],
+            vec![video("/media/movies/进击的巨人 S01E02.mp4")],
+        );
+        let mut index = SearchIndex::default();
+        index.rebuild(1, [&tree], |video| {
+            if !video.name.starts_with("Arrival") {
+                return DocumentExtras::default();
+            }
+            DocumentExtras {
+                tags: vec!["已看".to_string()],
+                notes: Some("外星语言学家".to_string()),
+            }
+        });
+        assert!(index.is_current(1));
+        assert_eq!(index.documents.len(), 3);
+
+        let names = |results: SearchResults| results.videos.into_iter().map(|v| v.name).collect::<Vec<_>>();
+        assert_eq!(names(index.search("巨人", &[], 10, 0)), vec!["进击的巨人 S01E02.mp4"]);
+        assert_eq!(names(index.search("巨人 s01e02", &[], 10, 0)), vec!["进击的巨人 S01E02.mp4"]);
+        assert_eq!(names(index.search("inter", &[], 10, 0)), vec!["Interstellar.2014.mkv"]);
+        assert_eq!(names(index.search("intrstellar", &[], 10, 0)), vec!["Interstellar.2014.mkv"]);
+        assert_eq!(names(index.search("星际", &[], 10, 0)), vec!["Interstellar.2014.mkv"]);
+        assert!(index.search("巨星", &[], 10, 0).videos.is_empty());
+
+        let results = index.search("科幻", &[], 1, 1);
+        assert_eq!(results.total, 2);
+        assert_eq!(names(results), vec!["Interstellar.2014.mkv"]);
+
+        let watched = ["已看".to_string()];
+        assert_eq!(names(index.search("科幻", &watched, 10, 0)), vec!["Arrival.mkv"]);
+        assert_eq!(names(index.search("", &watched, 10, 0)), vec!["Arrival.mkv"]);
+        assert_eq!(names(index.search("已看", &[], 10, 0)), vec!["Arrival.mkv"]);
+        assert_eq!(names(index.search("语言学", &[], 10, 0)), vec!["Arrival.mkv"]);
+    }
+}
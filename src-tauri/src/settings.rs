@@ -0,0 +1,430 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use crate::persist;
+use crate::player::PlayerProfile;
+
+/// 设置文件名（位于应用配置目录）
+const SETTINGS_FILE: &str = "settings.json";
+/// 当前设置格式版本
+pub const SETTINGS_VERSION: u32 = 1;
+/// 封面管理器支持的图片扩展名
+const SUPPORTED_IMAGE_EXTENSIONS: [&str; 5] = ["jpg", "jpeg", "png", "bmp", "webp"];
+
+/// 全局设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    /// 设置格式版本
+    pub version: u32,
+    /// 扫描设置
+    pub scan: ScanSettings,
+    /// 封面设置
+    pub covers: CoverSettings,
+    /// 预览生成设置
+    pub preview: PreviewSettings,
+    /// 播放设置
+    pub playback: PlaybackSettings,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            scan: ScanSettings::default(),
+            covers: CoverSettings::default(),
+            preview: PreviewSettings::default(),
+            playback: PlaybackSettings::default(),
+        }
+    }
+}
+
+/// 扫描设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanSettings {
+    /// 是否并行扫描
+    pub parallel: bool,
+    /// 本地磁盘的扫描线程数（0 表示使用全局线程池）
+    pub max_threads: usize,
+    /// 每个目录最多读取的条目数
+    pub max_files_per_dir: usize,
+    /// 扫描深度上限（防止符号链接循环等导致无限递归）
+    pub max_depth: i32,
+    /// 本地磁盘的 ffprobe 超时（秒，0 表示不限制）
+    pub probe_timeout_secs: u64,
+    /// 视频文件扩展名（小写，不含点）
+    pub video_extensions: Vec<String>,
+    /// 使用系统命令读取目录时，每个目录最多收录的视频数
+    pub fallback_max_videos: usize,
+    /// 使用系统命令读取目录时，每个目录最多收录的子目录数
+    pub fallback_max_children: usize,
+}
+
+impl Default for ScanSettings {
+    fn default() -> Self {
+        Self {
+            parallel: true,
+            max_threads: 0,
+            max_files_per_dir: 10000,
+            max_depth: 100,
+            probe_timeout_secs: 0,
+            video_extensions: ["mp4", "avi", "mov", "mkv", "wmv", "flv", "webm"]
+                .iter()
+                .map(|ext| ext.to_string())
+                .collect(),
+            fallback_max_videos: 1000,
+            fallback_max_children: 1000,
+        }
+    }
+}
+
+/// 封面设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CoverSettings {
+    /// 作为封面的图片扩展名（小写，不含点）
+    pub image_extensions: Vec<String>,
+    /// 作为目录封面的文件名（不含扩展名）
+    pub folder_cover_names: Vec<String>,
+}
+
+impl Default for CoverSettings {
+    fn default() -> Self {
+        Self {
+            image_extensions: SUPPORTED_IMAGE_EXTENSIONS.iter().map(|ext| ext.to_string()).collect(),
+            folder_cover_names: ["cover", "folder", "poster", "fanart"]
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// 预览生成设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreviewSettings {
+    /// 同时运行的 ffmpeg 进程数
+    pub max_concurrency: usize,
+}
+
+impl Default for PreviewSettings {
+    fn default() -> Self {
+        Self { max_concurrency: 2 }
+    }
+}
+
+/// 播放后端
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaybackBackend {
+    /// 使用系统默认程序打开，无法获取播放进度
+    #[default]
+    System,
+    /// 通过 JSON IPC 控制 mpv，支持续播、章节跳转与进度记录
+    Mpv,
+}
+
+/// 播放设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlaybackSettings {
+    pub backend: PlaybackBackend,
+    /// mpv 可执行文件（名称或完整路径）
+    pub mpv_path: String,
+    /// 使用 mpv 或外部播放器播放时是否从上次的位置继续
+    pub resume: bool,
+    /// 外部播放器配置
+    pub profiles: Vec<PlayerProfile>,
+    /// 默认使用的播放器配置名称（为空时使用上面的播放后端）
+    pub default_profile: Option<String>,
+    /// 按根文件夹 ID 指定的播放器配置名称
+    pub root_profiles: BTreeMap<String, String>,
+    /// 按容器格式（文件扩展名，小写不含点）指定的播放器配置名称
+    pub container_profiles: BTreeMap<String, String>,
+}
+
+impl Default for PlaybackSettings {
+    fn default() -> Self {
+        Self {
+            backend: PlaybackBackend::System,
+            mpv_path: "mpv".to_string(),
+            resume: true,
+            profiles: Vec::new(),
+            default_profile: None,
+            root_profiles: BTreeMap::new(),
+            container_profiles: BTreeMap::new(),
+        }
+    }
+}
+
+impl AppSettings {
+    /// 规范化列表项（小写、去掉前导点、去重），再校验取值范围
+    pub fn normalize_and_validate(&mut self) -> Result<(), String> {
+        normalize_list(&mut self.scan.video_extensions);
+        normalize_list(&mut self.covers.image_extensions);
+        normalize_list(&mut self.covers.folder_cover_names);
+
+        if self.scan.video_extensions.is_empty() {
+            return Err("视频扩展名列表不能为空".to_string());
+        }
+        if let Some(ext) = self
+            .scan
+            .video_extensions
+            .iter()
+            .find(|ext| !ext.chars().all(|c| c.is_ascii_alphanumeric()))
+        {
+            return Err(format!("无效的视频扩展名: {}", ext));
+        }
+        if let Some(ext) = self
+            .covers
+            .image_extensions
+            .iter()
+            .find(|ext| !SUPPORTED_IMAGE_EXTENSIONS.contains(&ext.as_str()))
+        {
+            return Err(format!("不支持的封面格式: {}（支持 {}）", ext, SUPPORTED_IMAGE_EXTENSIONS.join("、")));
+        }
+        if self.scan.max_threads > 256 {
+            return Err("扫描线程数不能超过 256".to_string());
+        }
+        if self.scan.max_files_per_dir == 0 {
+            return Err("每个目录的最大条目数必须大于 0".to_string());
+        }
+        if !(1..=1000).contains(&self.scan.max_depth) {
+            return Err("扫描深度上限必须在 1 到 1000 之间".to_string());
+        }
+        if self.scan.fallback_max_videos == 0 || self.scan.fallback_max_children == 0 {
+            return Err("备用扫描的数量限制必须大于 0".to_string());
+        }
+        if !(1..=16).contains(&self.preview.max_concurrency) {
+            return Err("预览并发数必须在 1 到 16 之间".to_string());
+        }
+        self.playback.mpv_path = self.playback.mpv_path.trim().to_string();
+        if self.playback.mpv_path.is_empty() {
+            return Err("mpv 路径不能为空".to_string());
+        }
+        self.playback.validate_profiles()
+    }
+}
+
+impl PlaybackSettings {
+    /// 校验播放器配置：名称唯一、模板有效，引用的配置必须存在
+    fn validate_profiles(&mut self) -> Result<(), String> {
+        for index in 0..self.profiles.len() {
+            let name = self.profiles[index].name.trim().to_string();
+            self.profiles[index].name = name.clone();
+            self.profiles[index].validate()?;
+            if self.profiles[..index].iter().any(|other| other.name == name) {
+                return Err(format!("播放器配置名称重复: {}", name));
+            }
+        }
+        self.container_profiles = std::mem::take(&mut self.container_profiles)
+            .into_iter()
+            .map(|(ext, name)| (ext.trim().trim_start_matches('.').to_lowercase(), name))
+            .collect();
+        let references = self
+            .default_profile
+            .iter()
+            .chain(self.root_profiles.values())
+            .chain(self.container_profiles.values());
+        for name in references {
+            if !self.profiles.iter().any(|profile| &profile.name == name) {
+                return Err(format!("播放器配置不存在: {}", name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 列表项转为小写、去掉前导点与空项，并保持顺序去重
+fn normalize_list(items: &mut Vec<String>) {
+    let mut normalized: Vec<String> = Vec::new();
+    for item in items.iter() {
+        let item = item.trim().trim_start_matches('.').to_lowercase();
+        if !item.is_empty() && !normalized.contains(&item) {
+            normalized.push(item);
+        }
+    }
+    *items = normalized;
+}
+
+/// 各版本的升级步骤：`MIGRATIONS[n]` 将版本 n 升级到版本 n + 1
+const MIGRATIONS: [fn(&mut serde_json::Map<String, Value>); SETTINGS_VERSION as usize] = [migrate_v0_to_v1];
+
+/// 版本 0 为不带版本号的设置文件（如手动编写），结构与版本 1 相同，缺少的项使用默认值
+fn migrate_v0_to_v1(_settings: &mut serde_json::Map<String, Value>) {}
+
+/// 将旧版本的设置升级到当前版本
+fn migrate(mut value: Value) -> Result<Value, String> {
+    let Some(object) = value.as_object_mut() else {
+        return Err("设置文件格式错误".to_string());
+    };
+    let version = object.get("version").and_then(Value::as_u64).unwrap_or(0) as usize;
+    if version > SETTINGS_VERSION as usize {
+        return Err(format!("设置文件版本 {} 过新，请升级 VideoVault", version));
+    }
+    for step in &MIGRATIONS[version..] {
+        step(object);
+    }
+    object.insert("version".to_string(), Value::from(SETTINGS_VERSION));
+    Ok(value)
+}
+
+/// 将补丁递归合并到设置中（对象按键合并，其他值直接替换）
+fn merge_patch(target: &mut Value, patch: Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge_patch(target.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+/// 设置存储：加载、校验并保存全局设置
+pub struct SettingsStore {
+    path: PathBuf,
+    settings: AppSettings,
+}
+
+impl SettingsStore {
+    /// 从应用配置目录加载设置，旧版本自动升级；文件无效时使用默认值
+    pub fn load(config_dir: &Path) -> Self {
+        let path = config_dir.join(SETTINGS_FILE);
+        let settings = Self::read(&path).unwrap_or_else(|e| {
+            println!("加载设置失败，使用默认设置: {}", e);
+            AppSettings::default()
+        });
+        Self { path, settings }
+    }
+
+    fn read(path: &Path) -> Result<AppSettings, String> {
+        let value: Option<Value> = persist::read_json(path)?;
+        let Some(value) = value else {
+            return Ok(AppSettings::default());
+        };
+        let original_version = value.get("version").and_then(Value::as_u64);
+        let mut settings: AppSettings = serde_json::from_value(migrate(value)?)
+            .map_err(|e| format!("解析 {} 失败: {}", path.display(), e))?;
+        settings.normalize_and_validate()?;
+        if original_version != Some(SETTINGS_VERSION as u64) {
+            println!("设置已升级到版本 {}", SETTINGS_VERSION);
+            persist::write_json(path, &settings)?;
+        }
+        Ok(settings)
+    }
+
+    /// 当前设置
+    pub fn get(&self) -> &AppSettings {
+        &self.settings
+    }
+
+    /// 合并部分设置，校验通过后保存并返回新设置；校验失败时保持原设置不变。
+    /// `root_ids` 为已有的根文件夹：新指定的按根文件夹播放器配置必须对应其中之一，已移除的根文件夹留下的配置一并清理
+    pub fn update(&mut self, patch: Value, root_ids: &[String]) -> Result<AppSettings, String> {
+        let mut value = serde_json::to_value(&self.settings).map_err(|e| format!("序列化失败: {}", e))?;
+        merge_patch(&mut value, patch);
+        let mut settings: AppSettings = serde_json::from_value(value).map_err(|e| format!("设置格式错误: {}", e))?;
+        settings.version = SETTINGS_VERSION;
+        let previous = &self.settings.playback.root_profiles;
+        if let Some(root_id) = settings
+            .playback
+            .root_profiles
+            .iter()
+            .find(|(root_id, name)| !root_ids.contains(root_id) && previous.get(*root_id) != Some(*name))
+            .map(|(root_id, _)| root_id)
+        {
+            return Err(format!("根文件夹不存在: {}", root_id));
+        }
+        settings.playback.root_profiles.retain(|root_id, _| root_ids.contains(root_id));
+        settings.normalize_and_validate()?;
+        persist::write_json(&self.path, &settings)?;
+        self.settings = settings.clone();
+        Ok(settings)
+    }
+
+    /// 移除根文件夹后清理为其指定的播放器配置，返回设置是否有变化
+    pub fn remove_root_profile(&mut self, root_id: &str) -> Result<bool, String> {
+        if !self.settings.playback.root_profiles.contains_key(root_id) {
+            return Ok(false);
+        }
+        let mut settings = self.settings.clone();
+        settings.playback.root_profiles.remove(root_id);
+        persist::write_json(&self.path, &settings)?;
+        self.settings = settings;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_load_migrates_unversioned_settings() {
+        let dir = std::env::temp_dir().join(format!("vv-settings-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(SETTINGS_FILE), r#"{"scan": {"parallel": false, "video_extensions": [".MKV", "mp4", "mkv"]}}"#).unwrap();
+
+        let store = SettingsStore::load(&dir);
+        assert_eq!(store.get().version, SETTINGS_VERSION);
+        assert!(!store.get().scan.parallel);
+        assert_eq!(store.get().scan.video_extensions, vec!["mkv", "mp4"]);
+        assert_eq!(store.get().preview, PreviewSettings::default());
+        let saved = fs::read_to_string(dir.join(SETTINGS_FILE)).unwrap();
+        assert!(saved.contains("\"version\": 1"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_update_validates_and_keeps_previous_settings() {
+        let dir = std::env::temp_dir().join(format!("vv-settings-test-{}", uuid::Uuid::new_v4()));
+        let mut store = SettingsStore::load(&dir);
+
+        let updated = store.update(serde_json::json!({"preview": {"max_concurrency": 4}}), &[]).unwrap();
+        assert_eq!(updated.preview.max_concurrency, 4);
+        assert_eq!(updated.scan, ScanSettings::default());
+        let updated = store.update(serde_json::json!({"playback": {"backend": "mpv"}}), &[]).unwrap();
+        assert_eq!(updated.playback.backend, PlaybackBackend::Mpv);
+        assert_eq!(updated.playback.mpv_path, "mpv");
+        let profile = serde_json::json!({"name": "VLC", "executable": "vlc", "args": ["--start-time={start}", "{path}"]});
+        let updated = store
+            .update(serde_json::json!({"playback": {"profiles": [profile], "container_profiles": {".AVI": "VLC"}}}), &[])
+            .unwrap();
+        assert_eq!(updated.playback.container_profiles.get("avi").map(String::as_str), Some("VLC"));
+        assert!(store.update(serde_json::json!({"playback": {"default_profile": "IINA"}}), &[]).is_err());
+
+        assert!(store.update(serde_json::json!({"covers": {"image_extensions": ["gif"]}}), &[]).is_err());
+        assert!(store.update(serde_json::json!({"scan": {"max_depth": "deep"}}), &[]).is_err());
+        assert_eq!(store.get().covers, CoverSettings::default());
+        assert_eq!(SettingsStore::load(&dir).get().preview.max_concurrency, 4);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_root_profiles_must_reference_existing_roots() {
+        let dir = std::env::temp_dir().join(format!("vv-settings-test-{}", uuid::Uuid::new_v4()));
+        let mut store = SettingsStore::load(&dir);
+        let roots = vec!["root-a".to_string(), "root-b".to_string()];
+        let profile = serde_json::json!({"name": "VLC", "executable": "vlc"});
+
+        store.update(serde_json::json!({"playback": {"profiles": [profile], "root_profiles": {"root-a": "VLC", "root-b": "VLC"}}}), &roots).unwrap();
+        assert!(store.update(serde_json::json!({"playback": {"root_profiles": {"root-c": "VLC"}}}), &roots).is_err());
+        assert!(store.update(serde_json::json!({"playback": {"root_profiles": {"root-a": "IINA"}}}), &roots).is_err());
+
+        // 移除根文件夹时清理对应配置；已移除的根文件夹留下的配置不妨碍之后的更新，并在更新时清理
+        assert!(store.remove_root_profile("root-a").unwrap());
+        assert!(!store.remove_root_profile("root-a").unwrap());
+        let updated = store.update(serde_json::json!({"playback": {"resume": false}}), &roots[..1]).unwrap();
+        assert!(updated.playback.root_profiles.is_empty());
+        assert!(SettingsStore::load(&dir).get().playback.root_profiles.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use crate::folder::DirectoryNode;
+use crate::video::VideoInfo;
+use crate::volume::VolumeInfo;
+
+/// 报告中列出的最大文件数量
+const LARGEST_FILES_LIMIT: usize = 20;
+/// 缺少信息时的分组名称
+const UNKNOWN_KEY: &str = "未知";
+
+/// 目录占用空间（包含子目录，与 `video_count` 的统计方式一致）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryUsage {
+    /// 目录路径
+    pub path: String,
+    /// 目录名称
+    pub name: String,
+    /// 视频总大小（字节）
+    pub total_bytes: u64,
+    /// 视频数量
+    pub video_count: usize,
+    /// 子目录（按大小降序）
+    pub children: Vec<DirectoryUsage>,
+}
+
+/// 按编码、分辨率或容器分组的占用空间
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageBucket {
+    /// 分组名称
+    pub key: String,
+    /// 总大小（字节）
+    pub total_bytes: u64,
+    /// 视频数量
+    pub video_count: usize,
+}
+
+/// 大文件信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LargeFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub codec: Option<String>,
+    pub resolution: Option<(u32, u32)>,
+}
+
+/// 根文件夹的存储报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageReport {
+    /// 根文件夹 ID
+    pub root_id: String,
+    /// 视频总大小（字节）
+    pub total_bytes: u64,
+    /// 视频总数
+    pub video_count: usize,
+    /// 各级目录的占用空间
+    pub directories: DirectoryUsage,
+    /// 按视频编码分组
+    pub by_codec: Vec<UsageBucket>,
+    /// 按分辨率档位分组
+    pub by_resolution: Vec<UsageBucket>,
+    /// 按容器格式分组
+    pub by_container: Vec<UsageBucket>,
+    /// 最大的文件
+    pub largest_files: Vec<LargeFile>,
+    /// 所在卷的容量与剩余空间
+    pub volume: Option<VolumeInfo>,
+}
+
+/// 根据已扫描的目录树生成存储报告
+pub fn build_report(root_id: &str, tree: &DirectoryNode, volume: Option<VolumeInfo>) -> StorageReport {
+    let directories = directory_usage(tree);
+    let videos = tree.all_videos();
+
+    let mut largest: Vec<&VideoInfo> = videos.clone();
+    largest.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    let largest_files = largest
+        .into_iter()
+        .take(LARGEST_FILES_LIMIT)
+        .map(|video| LargeFile {
+            path: video.path.clone(),
+            size: video.size,
+            codec: video.codec.clone(),
+            resolution: video.resolution,
+        })
+        .collect();
+
+    StorageReport {
+        root_id: root_id.to_string(),
+        total_bytes: directories.total_bytes,
+        video_count: directories.video_count,
+        by_codec: group_by(&videos, |video| video.codec.as_ref().map(|codec| codec.to_lowercase())),
+        by_resolution: group_by(&videos, |video| video.resolution.map(resolution_tier)),
+        by_container: group_by(&videos, container_key),
+        largest_files,
+        directories,
+        volume,
+    }
+}
+
+/// 递归汇总目录占用空间
+fn directory_usage(node: &DirectoryNode) -> DirectoryUsage {
+    let mut children: Vec<DirectoryUsage> = node.children.iter().map(directory_usage).collect();
+    children.sort_by(|a, b| b.total_bytes.cmp(&a.total_bytes).then_with(|| a.name.cmp(&b.name)));
+
+    DirectoryUsage {
+        path: node.path.clone(),
+        name: node.name.clone(),
+        total_bytes: node.videos.iter().map(|v| v.size).sum::<u64>() + children.iter().map(|c| c.total_bytes).sum::<u64>(),
+        video_count: node.videos.len() + children.iter().map(|c| c.video_count).sum::<usize>(),
+        children,
+    }
+}
+
+/// 按指定键分组统计，结果按大小降序
+fn group_by(videos: &[&VideoInfo], key: impl Fn(&VideoInfo) -> Option<String>) -> Vec<UsageBucket> {
+    let mut buckets: HashMap<String, UsageBucket> = HashMap::new();
+    for video in videos {
+        let key = key(video).unwrap_or_else(|| UNKNOWN_KEY.to_string());
+        let bucket = buckets.entry(key.clone()).or_insert_with(|| UsageBucket {
+            key,
+            total_bytes: 0,
+            video_count: 0,
+        });
+        bucket.total_bytes += video.size;
+        bucket.video_count += 1;
+    }
+    let mut buckets: Vec<UsageBucket> = buckets.into_values().collect();
+    buckets.sort_by(|a, b| b.total_bytes.cmp(&a.total_bytes).then_with(|| a.key.cmp(&b.key)));
+    buckets
+}
+
+/// 分辨率档位（按短边判断，兼容竖屏视频）
+fn resolution_tier((width, height): (u32, u32)) -> String {
+    match width.min(height) {
+        h if h >= 2160 => "4K",
+        h if h >= 1440 => "1440p",
+        h if h >= 1080 => "1080p",
+        h if h >= 720 => "720p",
+        h if h >= 480 => "480p",
+        _ => "SD",
+    }
+    .to_string()
+}
+
+/// 容器格式：优先使用探测结果的第一个格式名，否则使用扩展名
+fn container_key(video: &VideoInfo) -> Option<String> {
+    video
+        .container_format
+        .as_ref()
+        .and_then(|format| format.split(',').next())
+        .map(|format| format.trim().to_lowercase())
+        .filter(|format| !format.is_empty())
+        .or_else(|| {
+            video
+                .path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{dir, video};
+
+    #[test]
+    fn test_build_report() {
+        let tree = dir(
+            "/lib",
+            vec![
+                dir("/lib/a", Vec::new(), vec![VideoInfo { size: 300, codec: Some("HEVC".to_string()), resolution: Some((3840, 2160)), ..video("/lib/a/1.mkv") }]),
+                dir("/lib/b", Vec::new(), vec![VideoInfo { size: 500, codec: Some("h264".to_string()), resolution: Some((1920, 1080)), ..video("/lib/b/2.mp4") }]),
+            ],
+            vec![VideoInfo { size: 100, ..video("/lib/3.mp4") }],
+        );
+
+        let report = build_report("root", &tree, None);
+        assert_eq!(report.total_bytes, 900);
+        assert_eq!(report.video_count, 3);
+        assert_eq!(report.directories.children[0].name, "b");
+        assert_eq!(report.directories.children[1].total_bytes, 300);
+        assert_eq!(report.by_codec[0].key, "h264");
+        assert_eq!(report.by_codec[1].key, "hevc");
+        assert_eq!(report.by_codec[2].key, UNKNOWN_KEY);
+        assert_eq!(report.by_resolution[1].key, "4K");
+        assert_eq!(report.by_container[0], UsageBucket { key: "mp4".to_string(), total_bytes: 600, video_count: 2 });
+        assert_eq!(report.largest_files[0].path, PathBuf::from("/lib/b/2.mp4"));
+    }
+}
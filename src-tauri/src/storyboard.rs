@@ -0,0 +1,266 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::persist;
+use crate::video::{media_command, VideoProcessor};
+
+/// 单个缩略图的宽度（像素）
+const TILE_WIDTH: u32 = 320;
+/// 无法获取分辨率时使用的缩略图高度
+const DEFAULT_TILE_HEIGHT: u32 = 180;
+/// 每个方向允许的最大格数
+const MAX_GRID_SIZE: u32 = 20;
+
+/// 故事板（联系表）信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoryboardInfo {
+    /// 拼接后的联系表图片
+    pub image_path: PathBuf,
+    /// WebVTT 精灵图映射（用于悬停拖动预览）
+    pub vtt_path: PathBuf,
+    /// 列数
+    pub columns: u32,
+    /// 行数
+    pub rows: u32,
+    /// 单个缩略图宽度
+    pub tile_width: u32,
+    /// 单个缩略图高度
+    pub tile_height: u32,
+    /// 每格覆盖的时长（秒）
+    pub interval_secs: f64,
+}
+
+/// 故事板生成器，结果缓存在 `<缓存目录>/storyboards/` 下
+pub struct StoryboardGenerator {
+    cache_dir: PathBuf,
+}
+
+impl StoryboardGenerator {
+    pub fn new(cache_dir: &Path) -> Self {
+        Self {
+            cache_dir: cache_dir.join("storyboards"),
+        }
+    }
+
+    /// 生成视频的联系表与 WebVTT 映射，已缓存时直接返回
+    pub fn generate(&self, video_path: &Path, columns: u32, rows: u32) -> Result<StoryboardInfo, String> {
+        if columns == 0 || rows == 0 || columns > MAX_GRID_SIZE || rows > MAX_GRID_SIZE {
+            return Err(format!("行列数必须在 1 到 {} 之间", MAX_GRID_SIZE));
+        }
+
+        let fingerprint = crate::video::compute_fingerprint(video_path)?;
+        let base_name = format!("{}_{}x{}", fingerprint, columns, rows);
+        let info_path = self.cache_dir.join(format!("{}.json", base_name));
+
+        if let Some(info) = persist::read_json::<Option<StoryboardInfo>>(&info_path)? {
+            if info.image_path.exists() && info.vtt_path.exists() {
+                return Ok(info);
+            }
+        }
+
+        let metadata = VideoProcessor::new()
+            .get_video_metadata_ffprobe(video_path)
+            .ok_or("无法读取视频元数据")?;
+        let duration = metadata
+            .duration
+            .map(|d| d.as_secs_f64())
+            .filter(|d| *d > 0.0)
+            .ok_or("无法获取视频时长")?;
+        let tile_height = match (metadata.width, metadata.height) {
+            (Some(w), Some(h)) if w > 0 => ((TILE_WIDTH as f64 * h as f64 / w as f64 / 2.0).round() as u32 * 2).max(2),
+            _ => DEFAULT_TILE_HEIGHT,
+        };
+
+        let frame_count = columns * rows;
+        let timestamps = frame_timestamps(duration, frame_count);
+        // 每次调用使用独立的临时目录，完成后再重命名到缓存目录，
+        // 同一视频的并发请求不会互相覆盖帧文件，也不会读到写了一半的结果
+        let work_dir = self.cache_dir.join(format!("{}.{}.tmp", base_name, uuid::Uuid::new_v4()));
+        fs::create_dir_all(&work_dir).map_err(|e| format!("创建缓存目录失败: {}", e))?;
+        let result = self.build(video_path, &work_dir, &timestamps, &base_name, columns, rows, tile_height, duration);
+        let _ = fs::remove_dir_all(&work_dir);
+        result
+    }
+
+    /// 在临时目录中生成联系表、WebVTT 与信息文件，再依次移入缓存目录（信息文件最后移入）
+    #[allow(clippy::too_many_arguments)]
+    fn build(
+        &self,
+        video_path: &Path,
+        work_dir: &Path,
+        timestamps: &[f64],
+        base_name: &str,
+        columns: u32,
+        rows: u32,
+        tile_height: u32,
+        duration: f64,
+    ) -> Result<StoryboardInfo, String> {
+        let frame_count = columns * rows;
+        let image_name = format!("{}.jpg", base_name);
+        let vtt_name = format!("{}.vtt", base_name);
+        let info_name = format!("{}.json", base_name);
+
+        self.compose(video_path, work_dir, timestamps, &work_dir.join(&image_name), columns, rows, tile_height)?;
+        let vtt = build_vtt(&image_name, duration, frame_count, columns, TILE_WIDTH, tile_height);
+        fs::write(work_dir.join(&vtt_name), vtt).map_err(|e| format!("写入 WebVTT 失败: {}", e))?;
+
+        let info = StoryboardInfo {
+            image_path: self.cache_dir.join(&image_name),
+            vtt_path: self.cache_dir.join(&vtt_name),
+            columns,
+            rows,
+            tile_width: TILE_WIDTH,
+            tile_height,
+            interval_secs: duration / frame_count as f64,
+        };
+        persist::write_json(&work_dir.join(&info_name), &info)?;
+
+        for name in [&image_name, &vtt_name, &info_name] {
+            fs::rename(work_dir.join(name), self.cache_dir.join(name))
+                .map_err(|e| format!("保存故事板失败: {}", e))?;
+        }
+        Ok(info)
+    }
+
+    /// 逐帧截图后用 ffmpeg 的 tile 滤镜拼成一张图
+    #[allow(clippy::too_many_arguments)]
+    fn compose(
+        &self,
+        video_path: &Path,
+        frames_dir: &Path,
+        timestamps: &[f64],
+        image_path: &Path,
+        columns: u32,
+        rows: u32,
+        tile_height: u32,
+    ) -> Result<(), String> {
+        let mut last_frame: Option<PathBuf> = None;
+        for (index, timestamp) in timestamps.iter().enumerate() {
+            let frame_path = frames_dir.join(format!("frame_{:03}.jpg", index));
+            let scale = format!("scale={}:{}", TILE_WIDTH, tile_height);
+            let with_label = format!(
+                "{},drawtext=text='{}':x=w-tw-6:y=h-th-6:fontsize=16:fontcolor=white:box=1:boxcolor=black@0.6:boxborderw=3",
+                scale,
+                format_label(*timestamp).replace(':', "\\:")
+            );
+
+            // 部分 ffmpeg 构建缺少 drawtext 所需字体，失败时退回无时间戳版本
+            if !extract_frame(video_path, *timestamp, &with_label, &frame_path) {
+                extract_frame(video_path, *timestamp, &scale, &frame_path);
+            }
+
+            if frame_path.exists() {
+                last_frame = Some(frame_path);
+            } else if let Some(previous) = &last_frame {
+                // 末尾帧可能因时长误差截取失败，复用上一帧保持网格完整
+                fs::copy(previous, &frame_path).map_err(|e| format!("复制帧失败: {}", e))?;
+            } else {
+                return Err("截取视频帧失败".to_string());
+            }
+        }
+
+        let output = media_command("ffmpeg")
+            .args(["-v", "quiet", "-y", "-start_number", "0", "-i"])
+            .arg(frames_dir.join("frame_%03d.jpg"))
+            .args(["-vf", &format!("tile={}x{}", columns, rows), "-frames:v", "1"])
+            .arg(image_path)
+            .output()
+            .map_err(|e| format!("执行 ffmpeg 命令失败: {}", e))?;
+
+        if !output.status.success() || !image_path.exists() {
+            return Err("拼接联系表失败".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// 在指定时间点截取一帧
+fn extract_frame(video_path: &Path, timestamp: f64, filter: &str, output_path: &Path) -> bool {
+    media_command("ffmpeg")
+        .args(["-v", "quiet", "-y", "-ss", &format!("{:.3}", timestamp), "-i"])
+        .arg(video_path)
+        .args(["-frames:v", "1", "-vf", filter])
+        .arg(output_path)
+        .output()
+        .map(|output| output.status.success() && output_path.exists())
+        .unwrap_or(false)
+}
+
+/// 将时长均分为 count 段，取每段中点作为截图时间
+fn frame_timestamps(duration: f64, count: u32) -> Vec<f64> {
+    let interval = duration / count as f64;
+    (0..count).map(|i| interval * (i as f64 + 0.5)).collect()
+}
+
+/// 叠加在缩略图上的时间标签（H:MM:SS 或 MM:SS）
+fn format_label(secs: f64) -> String {
+    let total = secs.floor() as u64;
+    let (h, m, s) = (total / 3600, (total % 3600) / 60, total % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{:02}:{:02}", m, s)
+    }
+}
+
+/// WebVTT 时间格式 HH:MM:SS.mmm
+fn format_vtt_time(secs: f64) -> String {
+    let millis = (secs * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        (millis % 3_600_000) / 60_000,
+        (millis % 60_000) / 1000,
+        millis % 1000
+    )
+}
+
+/// 生成精灵图映射：每个时间段对应联系表中的一个 `#xywh` 区域
+fn build_vtt(image_name: &str, duration: f64, frame_count: u32, columns: u32, tile_width: u32, tile_height: u32) -> String {
+    let interval = duration / frame_count as f64;
+    let mut vtt = String::from("WEBVTT\n");
+    for index in 0..frame_count {
+        let start = interval * index as f64;
+        let end = (interval * (index + 1) as f64).min(duration);
+        let x = (index % columns) * tile_width;
+        let y = (index / columns) * tile_height;
+        vtt.push_str(&format!(
+            "\n{} --> {}\n{}#xywh={},{},{},{}\n",
+            format_vtt_time(start),
+            format_vtt_time(end),
+            image_name,
+            x,
+            y,
+            tile_width,
+            tile_height
+        ));
+    }
+    vtt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_timestamps_evenly_spaced() {
+        let timestamps = frame_timestamps(100.0, 4);
+        assert_eq!(timestamps, vec![12.5, 37.5, 62.5, 87.5]);
+    }
+
+    #[test]
+    fn test_time_formats() {
+        assert_eq!(format_label(83.9), "01:23");
+        assert_eq!(format_label(3725.0), "1:02:05");
+        assert_eq!(format_vtt_time(3725.5), "01:02:05.500");
+    }
+
+    #[test]
+    fn test_build_vtt_sprite_regions() {
+        let vtt = build_vtt("sheet.jpg", 40.0, 4, 2, 320, 180);
+        assert!(vtt.starts_with("WEBVTT\n"));
+        assert!(vtt.contains("00:00:00.000 --> 00:00:10.000\nsheet.jpg#xywh=0,0,320,180"));
+        assert!(vtt.contains("00:00:10.000 --> 00:00:20.000\nsheet.jpg#xywh=320,0,320,180"));
+        assert!(vtt.contains("00:00:30.000 --> 00:00:40.000\nsheet.jpg#xywh=320,180,320,180"));
+    }
+}
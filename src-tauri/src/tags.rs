@@ -0,0 +1,366 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use crate::persist;
+
+/// 标签文件名（位于应用数据目录）
+const TAGS_FILE: &str = "tags.json";
+/// 未指定颜色时使用的默认颜色
+const DEFAULT_TAG_COLOR: &str = "#909399";
+
+/// 用户标签
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tag {
+    pub id: String,
+    pub name: String,
+    /// 颜色（`#rrggbb`）
+    pub color: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 标签文件内容
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TagFile {
+    #[serde(default)]
+    tags: Vec<Tag>,
+    /// 存储键（卷标识）→ 卷上的相对路径 → 标签 ID
+    #[serde(default)]
+    assignments: BTreeMap<String, BTreeMap<String, BTreeSet<String>>>,
+}
+
+/// 随目录文件导出的标签：按名称引用，导入到另一台电脑时与已有标签合并
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CatalogTags {
+    /// 用到的标签定义
+    pub tags: Vec<Tag>,
+    /// 相对路径 → 标签名称
+    pub assignments: BTreeMap<String, Vec<String>>,
+}
+
+/// 校验并规范化颜色（`#rgb` 或 `#rrggbb`，统一为小写 `#rrggbb`）
+fn normalize_color(color: &str) -> Result<String, String> {
+    let hex = color.trim().strip_prefix('#').unwrap_or_default();
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("无效的颜色: {}", color));
+    }
+    match hex.len() {
+        6 => Ok(format!("#{}", hex.to_lowercase())),
+        3 => Ok(hex.chars().fold("#".to_string(), |mut out, c| {
+            out.push(c.to_ascii_lowercase());
+            out.push(c.to_ascii_lowercase());
+            out
+        })),
+        _ => Err(format!("无效的颜色: {}", color)),
+    }
+}
+
+/// 规范化相对路径键（去掉首尾的 `/`）
+fn normalize_key(path: &str) -> &str {
+    path.trim_matches('/')
+}
+
+/// 标签存储：标签定义与按卷标识 + 相对路径记录的分配，重新挂载到其他路径后仍然有效
+pub struct TagStore {
+    path: PathBuf,
+    file: TagFile,
+    /// 每次修改后递增，用于判断搜索索引等派生数据是否过期
+    revision: u64,
+}
+
+impl TagStore {
+    /// 从应用数据目录加载标签
+    pub fn load(data_dir: &Path) -> Self {
+        let path = data_dir.join(TAGS_FILE);
+        let file = persist::read_json(&path).unwrap_or_else(|e| {
+            println!("加载标签失败: {}", e);
+            TagFile::default()
+        });
+        Self { path, file, revision: 0 }
+    }
+
+    fn save(&mut self) -> Result<(), String> {
+        self.revision += 1;
+        persist::write_json(&self.path, &self.file)
+    }
+
+    /// 修改计数
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// 所有标签（按创建时间排序）
+    pub fn list(&self) -> &[Tag] {
+        &self.file.tags
+    }
+
+    fn find_by_name(&self, name: &str) -> Option<&Tag> {
+        let name = name.to_lowercase();
+        self.file.tags.iter().find(|tag| tag.name.to_lowercase() == name)
+    }
+
+    /// 校验标签名称：不能为空，不能与其他标签重名（不区分大小写）
+    fn validate_name(&self, name: &str, except_id: Option<&str>) -> Result<String, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("标签名称不能为空".to_string());
+        }
+        if self.find_by_name(name).is_some_and(|tag| Some(tag.id.as_str()) != except_id) {
+            return Err(format!("已存在名为「{}」的标签", name));
+        }
+        Ok(name.to_string())
+    }
+
+    /// 创建标签
+    pub fn create(&mut self, name: &str, color: Option<&str>) -> Result<Tag, String> {
+        let tag = Tag {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: self.validate_name(name, None)?,
+            color: normalize_color(color.unwrap_or(DEFAULT_TAG_COLOR))?,
+            created_at: Utc::now(),
+        };
+        self.file.tags.push(tag.clone());
+        self.save()?;
+        Ok(tag)
+    }
+
+    /// 修改标签名称或颜色
+    pub fn update(&mut self, id: &str, name: Option<&str>, color: Option<&str>) -> Result<Tag, String> {
+        let name = name.map(|name| self.validate_name(name, Some(id))).transpose()?;
+        let color = color.map(normalize_color).transpose()?;
+        let tag = self
+            .file
+            .tags
+            .iter_mut()
+            .find(|tag| tag.id == id)
+            .ok_or("标签不存在".to_string())?;
+        if let Some(name) = name {
+            tag.name = name;
+        }
+        if let Some(color) = color {
+            tag.color = color;
+        }
+        let tag = tag.clone();
+        self.save()?;
+        Ok(tag)
+    }
+
+    /// 删除标签及其所有分配
+    pub fn delete(&mut self, id: &str) -> Result<bool, String> {
+        let before = self.file.tags.len();
+        self.file.tags.retain(|tag| tag.id != id);
+        if self.file.tags.len() == before {
+            return Ok(false);
+        }
+        for paths in self.file.assignments.values_mut() {
+            for tag_ids in paths.values_mut() {
+                tag_ids.remove(id);
+            }
+            paths.retain(|_, tag_ids| !tag_ids.is_empty());
+        }
+        self.file.assignments.retain(|_, paths| !paths.is_empty());
+        self.save()?;
+        Ok(true)
+    }
+
+    /// 为多个路径（视频或目录）批量添加标签，返回新增的分配数
+    pub fn assign(&mut self, storage_key: &str, paths: &[String], tag_ids: &[String]) -> Result<usize, String> {
+        if paths.is_empty() || tag_ids.is_empty() {
+            return Ok(0);
+        }
+        if let Some(missing) = tag_ids.iter().find(|id| !self.file.tags.iter().any(|tag| &tag.id == *id)) {
+            return Err(format!("标签不存在: {}", missing));
+        }
+        let entries = self.file.assignments.entry(storage_key.to_string()).or_default();
+        let mut added = 0;
+        for path in paths {
+            let assigned = entries.entry(normalize_key(path).to_string()).or_default();
+            for id in tag_ids {
+                added += usize::from(assigned.insert(id.clone()));
+            }
+        }
+        if added > 0 {
+            self.save()?;
+        }
+        Ok(added)
+    }
+
+    /// 从多个路径批量移除标签，返回移除的分配数
+    pub fn unassign(&mut self, storage_key: &str, paths: &[String], tag_ids: &[String]) -> Result<usize, String> {
+        let Some(entries) = self.file.assignments.get_mut(storage_key) else {
+            return Ok(0);
+        };
+        let mut removed = 0;
+        for path in paths {
+            let key = normalize_key(path);
+            if let Some(assigned) = entries.get_mut(key) {
+                for id in tag_ids {
+                    removed += usize::from(assigned.remove(id));
+                }
+                if assigned.is_empty() {
+                    entries.remove(key);
+                }
+            }
+        }
+        if entries.is_empty() {
+            self.file.assignments.remove(storage_key);
+        }
+        if removed > 0 {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// 根文件夹内的分配，`prefix` 为根文件夹在卷上的位置；返回相对根目录的路径
+    fn root_entries<'a>(&'a self, storage_key: &str, prefix: &'a str) -> impl Iterator<Item = (&'a str, &'a BTreeSet<String>)> {
+        self.file
+            .assignments
+            .get(storage_key)
+            .into_iter()
+            .flatten()
+            .filter_map(move |(path, tag_ids)| persist::strip_key_prefix(prefix, path).map(|relative| (relative, tag_ids)))
+    }
+
+    /// 根文件夹内所有路径的标签分配（相对根目录的路径 → 标签 ID）
+    pub fn root_assignments(&self, storage_key: &str, prefix: &str) -> BTreeMap<String, Vec<String>> {
+        self.root_entries(storage_key, prefix)
+            .map(|(path, tag_ids)| (path.to_string(), tag_ids.iter().cloned().collect()))
+            .collect()
+    }
+
+    /// 路径的有效标签名称：自身的标签加上所有上级目录的标签
+    pub fn effective_names(&self, storage_key: &str, path: &str) -> Vec<String> {
+        let Some(entries) = self.file.assignments.get(storage_key) else {
+            return Vec::new();
+        };
+        let path = normalize_key(path);
+        let mut ids: BTreeSet<&String> = BTreeSet::new();
+        let prefixes = path
+            .match_indices('/')
+            .map(|(index, _)| &path[..index])
+            .chain(std::iter::once(path));
+        for prefix in std::iter::once("").chain(prefixes) {
+            if let Some(assigned) = entries.get(prefix) {
+                ids.extend(assigned);
+            }
+        }
+        self.file
+            .tags
+            .iter()
+            .filter(|tag| ids.contains(&tag.id))
+            .map(|tag| tag.name.clone())
+            .collect()
+    }
+
+    /// 导出根文件夹的标签分配（写入目录文件）
+    pub fn export_root(&self, storage_key: &str, prefix: &str) -> CatalogTags {
+        let mut used: BTreeSet<&str> = BTreeSet::new();
+        let mut assignments = BTreeMap::new();
+        for (path, tag_ids) in self.root_entries(storage_key, prefix) {
+            let names: Vec<String> = self
+                .file
+                .tags
+                .iter()
+                .filter(|tag| tag_ids.contains(&tag.id))
+                .map(|tag| {
+                    used.insert(tag.id.as_str());
+                    tag.name.clone()
+                })
+                .collect();
+            assignments.insert(path.to_string(), names);
+        }
+        CatalogTags {
+            tags: self.file.tags.iter().filter(|tag| used.contains(tag.id.as_str())).cloned().collect(),
+            assignments,
+        }
+    }
+
+    /// 合并目录文件中的标签分配（相对根目录的路径）：按名称匹配已有标签，没有则创建；返回新增的分配数
+    pub fn import_root(&mut self, storage_key: &str, prefix: &str, catalog: &CatalogTags) -> Result<usize, String> {
+        let mut ids: BTreeMap<String, String> = BTreeMap::new();
+        for tag in &catalog.tags {
+            let id = match self.find_by_name(&tag.name) {
+                Some(existing) => existing.id.clone(),
+                None => {
+                    let color = normalize_color(&tag.color).unwrap_or_else(|_| DEFAULT_TAG_COLOR.to_string());
+                    let created = Tag {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        name: tag.name.trim().to_string(),
+                        color,
+                        created_at: tag.created_at,
+                    };
+                    let id = created.id.clone();
+                    self.file.tags.push(created);
+                    id
+                }
+            };
+            ids.insert(tag.name.to_lowercase(), id);
+        }
+
+        let entries = self.file.assignments.entry(storage_key.to_string()).or_default();
+        let mut added = 0;
+        for (path, names) in &catalog.assignments {
+            let assigned = entries.entry(persist::join_key(prefix, normalize_key(path))).or_default();
+            for name in names {
+                if let Some(id) = ids.get(&name.to_lowercase()) {
+                    added += usize::from(assigned.insert(id.clone()));
+                }
+            }
+        }
+        entries.retain(|_, tag_ids| !tag_ids.is_empty());
+        if entries.is_empty() {
+            self.file.assignments.remove(storage_key);
+        }
+        self.save()?;
+        Ok(added)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tags_assign_and_inherit() {
+        let dir = std::env::temp_dir().join(format!("vv-tags-test-{}", uuid::Uuid::new_v4()));
+        let mut store = TagStore::load(&dir);
+
+        let watched = store.create("已看", Some("#0F0")).unwrap();
+        assert_eq!(watched.color, "#00ff00");
+        let anime = store.create("Anime", None).unwrap();
+        assert!(store.create("anime", None).is_err());
+        assert!(store.create("坏颜色", Some("green")).is_err());
+
+        let paths = vec!["season1/ep1.mkv".to_string(), "season1/ep2.mkv".to_string()];
+        assert_eq!(store.assign("custom:abc", &paths, std::slice::from_ref(&watched.id)).unwrap(), 2);
+        assert_eq!(store.assign("custom:abc", &["/season1/".to_string()], std::slice::from_ref(&anime.id)).unwrap(), 1);
+        assert!(store.assign("custom:abc", &paths, &["missing".to_string()]).is_err());
+        assert_eq!(store.effective_names("custom:abc", "season1/ep1.mkv"), vec!["已看", "Anime"]);
+        assert!(store.effective_names("custom:other", "season1/ep1.mkv").is_empty());
+
+        // 另一台电脑上导入目录文件中的标签
+        let exported = store.export_root("custom:abc", "");
+        let mut other = TagStore::load(&dir.join("other"));
+        other.create("anime", Some("#123456")).unwrap();
+        assert_eq!(other.import_root("custom:abc", "", &exported).unwrap(), 3);
+        assert_eq!(other.list().len(), 2);
+        assert_eq!(other.effective_names("custom:abc", "season1/ep2.mkv"), vec!["anime", "已看"]);
+
+        // 同一卷上的另一个根文件夹：路径按在卷上的位置区分，互不影响
+        assert_eq!(other.import_root("uuid:disk", "shows", &exported).unwrap(), 3);
+        assert_eq!(other.effective_names("uuid:disk", "shows/season1/ep1.mkv"), vec!["anime", "已看"]);
+        assert!(other.effective_names("uuid:disk", "season1/ep1.mkv").is_empty());
+        assert!(other.root_assignments("uuid:disk", "movies").is_empty());
+        assert!(other.export_root("uuid:disk", "shows").assignments.keys().eq(exported.assignments.keys()));
+
+        assert_eq!(store.unassign("custom:abc", &paths[..1], std::slice::from_ref(&watched.id)).unwrap(), 1);
+        store.update(&anime.id, Some("动画"), Some("#abcdef")).unwrap();
+        assert!(store.delete(&watched.id).unwrap());
+        let reloaded = TagStore::load(&dir);
+        assert_eq!(reloaded.list().len(), 1);
+        assert_eq!(reloaded.effective_names("custom:abc", "season1/ep2.mkv"), vec!["动画"]);
+        assert_eq!(reloaded.root_assignments("custom:abc", "").len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,31 @@
+//! 测试共用的视频与目录树构造函数
+
+use std::path::{Path, PathBuf};
+use crate::folder::DirectoryNode;
+use crate::video::VideoInfo;
+
+fn file_name(path: &str) -> String {
+    Path::new(path).file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// 只有路径与文件名的视频，其余字段按需用结构体更新语法覆盖
+pub fn video(path: &str) -> VideoInfo {
+    VideoInfo {
+        path: PathBuf::from(path),
+        name: file_name(path),
+        ..VideoInfo::default()
+    }
+}
+
+/// 目录节点，视频数量包含子目录
+pub fn dir(path: &str, children: Vec<DirectoryNode>, videos: Vec<VideoInfo>) -> DirectoryNode {
+    DirectoryNode {
+        name: file_name(path),
+        path: path.to_string(),
+        is_directory: true,
+        video_count: videos.len() + children.iter().map(|child| child.video_count).sum::<usize>(),
+        children,
+        videos,
+        ..DirectoryNode::default()
+    }
+}
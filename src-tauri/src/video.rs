@@ -1,11 +1,49 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use std::process::Command;
 use std::str::FromStr;
 
+/// 指纹计算时读取的首尾数据块大小
+const FINGERPRINT_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// 内嵌封面的来源类型
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum EmbeddedCoverKind {
+    /// 附加图片流（MP4 `covr` 原子、MKV/MP3 的 attached_pic）
+    AttachedPic,
+    /// Matroska 附件（`Attachments` 中的图片）
+    Attachment,
+}
+
+/// 视频内嵌的封面图片
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmbeddedCover {
+    /// 来源类型
+    pub kind: EmbeddedCoverKind,
+    /// 在容器中的流索引
+    pub stream_index: u32,
+    /// 图片 MIME 类型
+    pub mime_type: String,
+    /// 附件文件名（仅 Matroska 附件）
+    pub filename: Option<String>,
+}
+
+impl EmbeddedCover {
+    /// 根据 MIME 类型返回导出时使用的扩展名
+    pub fn extension(&self) -> &'static str {
+        match self.mime_type.as_str() {
+            "image/png" => "png",
+            "image/bmp" => "bmp",
+            "image/webp" => "webp",
+            _ => "jpg",
+        }
+    }
+}
+
 /// 视频文件信息
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct VideoInfo {
     /// 文件路径
     pub path: PathBuf,
@@ -31,39 +69,152 @@ pub struct VideoInfo {
     pub frame_rate: Option<f64>,
     /// 比特率
     pub bit_rate: Option<u32>,
+    /// 内嵌封面（探测时发现）
+    #[serde(default)]
+    pub embedded_cover: Option<EmbeddedCover>,
+    /// 内嵌标题（容器元数据中的 title 标签）
+    #[serde(default)]
+    pub title: Option<String>,
+    /// 用户的评分、收藏与备注（只在返回给前端时附加，不写入目录树快照）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotation: Option<crate::annotations::VideoAnnotation>,
 }
 
+/// 创建隐藏控制台窗口的外部媒体工具命令（ffprobe / ffmpeg）
+pub fn media_command(program: &str) -> Command {
+    #[allow(unused_mut)]
+    let mut cmd = Command::new(program);
+
+    // 在 Windows 上隐藏命令行窗口
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    cmd
+}
+
+/// 执行命令并等待结束；超过 timeout 时终止进程并返回 TimedOut 错误
+pub fn run_with_timeout(cmd: &mut Command, timeout: Option<Duration>) -> std::io::Result<std::process::Output> {
+    let Some(timeout) = timeout else {
+        return cmd.output();
+    };
+
+    let mut child = cmd
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+
+    // 在后台线程读取输出，避免管道写满导致子进程阻塞
+    let mut stdout = child.stdout.take();
+    let mut stderr = child.stderr.take();
+    let stdout_reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(out) = stdout.as_mut() {
+            let _ = out.read_to_end(&mut buf);
+        }
+        buf
+    });
+    let stderr_reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(err) = stderr.as_mut() {
+            let _ = err.read_to_end(&mut buf);
+        }
+        buf
+    });
+
+    let deadline = std::time::Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if std::time::Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "命令执行超时"));
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+
+    Ok(std::process::Output {
+        status,
+        stdout: stdout_reader.join().unwrap_or_default(),
+        stderr: stderr_reader.join().unwrap_or_default(),
+    })
+}
+
+/// 计算视频文件指纹：文件大小 + 首尾各 64KiB 内容的 UUID v5 摘要。
+/// 不依赖路径，文件移动或重新挂载后保持不变，用作各类缓存的键。
+pub fn compute_fingerprint(path: &Path) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("无法打开文件: {}", e))?;
+    let size = file.metadata().map_err(|e| format!("无法读取文件信息: {}", e))?.len();
+
+    let mut data = size.to_le_bytes().to_vec();
+    let head_len = size.min(FINGERPRINT_CHUNK_SIZE);
+    let mut head = vec![0u8; head_len as usize];
+    file.read_exact(&mut head).map_err(|e| format!("读取文件失败: {}", e))?;
+    data.extend_from_slice(&head);
+
+    if size > FINGERPRINT_CHUNK_SIZE * 2 {
+        let mut tail = vec![0u8; FINGERPRINT_CHUNK_SIZE as usize];
+        file.seek(SeekFrom::End(-(FINGERPRINT_CHUNK_SIZE as i64)))
+            .and_then(|_| file.read_exact(&mut tail))
+            .map_err(|e| format!("读取文件失败: {}", e))?;
+        data.extend_from_slice(&tail);
+    }
+
+    Ok(uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_OID, &data).simple().to_string())
+}
+
+/// ffprobe 探测选项
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProbeOptions {
+    /// 超时时间（None 表示不限制），超时后终止 ffprobe
+    pub timeout: Option<Duration>,
+    /// 只读取文件头部（限制 probesize / analyzeduration，减少网络共享上的数据读取）
+    pub header_only: bool,
+}
+
+/// 仅探测文件头时读取的最大字节数
+const HEADER_PROBE_SIZE: &str = "1048576";
+
 /// 视频文件处理器
-pub struct VideoProcessor;
+#[derive(Default)]
+pub struct VideoProcessor {
+    probe: ProbeOptions,
+}
 
 impl VideoProcessor {
     /// 创建新的视频处理器实例
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// 使用指定探测选项创建视频处理器
+    pub fn with_probe_options(probe: ProbeOptions) -> Self {
+        Self { probe }
     }
 
     /// 使用 ffprobe 解析视频元数据
-    pub fn get_video_metadata_ffprobe(&self, path: &PathBuf) -> Option<VideoMetadata> {
+    pub fn get_video_metadata_ffprobe(&self, path: &Path) -> Option<VideoMetadata> {
         let path_str = path.to_string_lossy();
         
         // 构建 ffprobe 命令
-        let mut cmd = Command::new("ffprobe");
-        cmd.args(&[
-            "-v", "quiet",
+        let mut cmd = media_command("ffprobe");
+        cmd.args(["-v", "quiet"]);
+        if self.probe.header_only {
+            cmd.args(["-probesize", HEADER_PROBE_SIZE, "-analyzeduration", "0"]);
+        }
+        cmd.args([
             "-print_format", "json",
             "-show_format",
             "-show_streams",
             &path_str
         ]);
         
-        // 在 Windows 上隐藏命令行窗口
-        #[cfg(windows)]
-        {
-            use std::os::windows::process::CommandExt;
-            cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-        }
-        
-        let output = cmd.output();
+        let output = run_with_timeout(&mut cmd, self.probe.timeout);
         
         let output = match output {
             Ok(output) => output,
@@ -96,6 +247,11 @@ impl VideoProcessor {
             }
         };
         
+        Some(self.parse_ffprobe_json(path, &json))
+    }
+
+    /// 解析 ffprobe 的 JSON 输出
+    fn parse_ffprobe_json(&self, path: &Path, json: &serde_json::Value) -> VideoMetadata {
         let mut metadata = VideoMetadata::default();
         
         // 从文件扩展名获取容器格式
@@ -116,12 +272,29 @@ impl VideoProcessor {
                     metadata.bit_rate = Some(bit_rate);
                 }
             }
+
+            // 标题标签（Matroska 为 TITLE，MP4 为 title）
+            metadata.title = format_info
+                .get("tags")
+                .and_then(|tags| tags.as_object())
+                .and_then(|tags| tags.iter().find(|(key, _)| key.eq_ignore_ascii_case("title")))
+                .and_then(|(_, value)| value.as_str())
+                .map(|title| title.trim().to_string())
+                .filter(|title| !title.is_empty());
         }
         
         // 解析流信息
         if let Some(streams) = json.get("streams").and_then(|s| s.as_array()) {
             for stream in streams {
                 let codec_type = stream.get("codec_type").and_then(|t| t.as_str());
+
+                // 内嵌封面不是真正的视频流，单独记录后跳过
+                if let Some(cover) = self.parse_embedded_cover(stream) {
+                    if metadata.embedded_cover.is_none() || Self::is_preferred_attachment(&cover) {
+                        metadata.embedded_cover = Some(cover);
+                    }
+                    continue;
+                }
                 
                 match codec_type {
                     Some("video") => {
@@ -179,7 +352,70 @@ impl VideoProcessor {
             }
         }
         
-        Some(metadata)
+        metadata
+    }
+
+    /// 识别内嵌封面流：attached_pic 视频流或图片类型的 Matroska 附件
+    fn parse_embedded_cover(&self, stream: &serde_json::Value) -> Option<EmbeddedCover> {
+        let stream_index = stream.get("index").and_then(|i| i.as_u64())? as u32;
+        let codec_type = stream.get("codec_type").and_then(|t| t.as_str());
+        let codec_name = stream.get("codec_name").and_then(|c| c.as_str()).unwrap_or("");
+        let tags = stream.get("tags");
+
+        match codec_type {
+            Some("video") => {
+                let attached_pic = stream
+                    .get("disposition")
+                    .and_then(|d| d.get("attached_pic"))
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0);
+                if attached_pic != 1 {
+                    return None;
+                }
+                let mime_type = match codec_name {
+                    "png" => "image/png",
+                    "bmp" => "image/bmp",
+                    "webp" => "image/webp",
+                    _ => "image/jpeg",
+                };
+                Some(EmbeddedCover {
+                    kind: EmbeddedCoverKind::AttachedPic,
+                    stream_index,
+                    mime_type: mime_type.to_string(),
+                    filename: None,
+                })
+            }
+            Some("attachment") => {
+                let mime_type = tags
+                    .and_then(|t| t.get("mimetype"))
+                    .and_then(|m| m.as_str())?
+                    .to_lowercase();
+                if !mime_type.starts_with("image/") {
+                    return None;
+                }
+                let filename = tags
+                    .and_then(|t| t.get("filename"))
+                    .and_then(|f| f.as_str())
+                    .map(|f| f.to_string());
+                Some(EmbeddedCover {
+                    kind: EmbeddedCoverKind::Attachment,
+                    stream_index,
+                    mime_type,
+                    filename,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Matroska 约定以 `cover.*` 命名正面封面，优先于其它附件图片
+    fn is_preferred_attachment(cover: &EmbeddedCover) -> bool {
+        cover.kind == EmbeddedCoverKind::Attachment
+            && cover
+                .filename
+                .as_deref()
+                .map(|f| f.to_lowercase().starts_with("cover."))
+                .unwrap_or(false)
     }
     
     /// 解析帧率字符串 (例如: "30000/1001")
@@ -227,6 +463,9 @@ impl VideoProcessor {
             container_format: Some(video_metadata.container_format),
             frame_rate: video_metadata.frame_rate,
             bit_rate: video_metadata.bit_rate,
+            embedded_cover: video_metadata.embedded_cover,
+            title: video_metadata.title,
+            annotation: None,
         })
     }
 
@@ -245,5 +484,69 @@ pub struct VideoMetadata {
     pub bit_rate: Option<u32>,
     pub has_video: bool,
     pub has_audio: bool,
+    pub embedded_cover: Option<EmbeddedCover>,
+    pub title: Option<String>,
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_with_timeout_kills_slow_command() {
+        let mut slow = Command::new("sleep");
+        slow.arg("5");
+        let err = run_with_timeout(&mut slow, Some(Duration::from_millis(100))).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+        let mut fast = Command::new("echo");
+        fast.arg("ok");
+        let output = run_with_timeout(&mut fast, Some(Duration::from_secs(5))).unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "ok");
+    }
+
+    #[test]
+    fn test_attached_pic_is_embedded_cover() {
+        let json = serde_json::json!({
+            "streams": [
+                { "index": 0, "codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080,
+                  "disposition": { "attached_pic": 0 } },
+                { "index": 1, "codec_type": "video", "codec_name": "mjpeg", "width": 600, "height": 900,
+                  "disposition": { "attached_pic": 1 } }
+            ]
+        });
+        let metadata = VideoProcessor::new().parse_ffprobe_json(Path::new("a.mp4"), &json);
+
+        assert_eq!(metadata.video_codec.as_deref(), Some("h264"));
+        assert_eq!(metadata.width, Some(1920));
+        let cover = metadata.embedded_cover.unwrap();
+        assert_eq!(cover.kind, EmbeddedCoverKind::AttachedPic);
+        assert_eq!(cover.stream_index, 1);
+        assert_eq!(cover.extension(), "jpg");
+    }
+
+    #[test]
+    fn test_matroska_cover_attachment_preferred() {
+        let json = serde_json::json!({
+            "format": { "tags": { "TITLE": " 进击的巨人 第1集 " } },
+            "streams": [
+                { "index": 0, "codec_type": "video", "codec_name": "hevc" },
+                { "index": 2, "codec_type": "attachment",
+                  "tags": { "filename": "small_cover.png", "mimetype": "image/png" } },
+                { "index": 3, "codec_type": "attachment",
+                  "tags": { "filename": "cover.jpg", "mimetype": "image/jpeg" } },
+                { "index": 4, "codec_type": "attachment",
+                  "tags": { "filename": "font.ttf", "mimetype": "application/x-truetype-font" } }
+            ]
+        });
+        let metadata = VideoProcessor::new().parse_ffprobe_json(Path::new("a.mkv"), &json);
+
+        let cover = metadata.embedded_cover.unwrap();
+        assert_eq!(cover.kind, EmbeddedCoverKind::Attachment);
+        assert_eq!(cover.stream_index, 3);
+        assert_eq!(cover.filename.as_deref(), Some("cover.jpg"));
+        assert_eq!(metadata.title.as_deref(), Some("进击的巨人 第1集"));
+    }
+}
@@ -1,43 +1,46 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use crate::persist;
+
+/// 卷登记文件名（位于应用数据目录）
+const VOLUMES_FILE: &str = "volumes.json";
+/// 卷标识文件名
+pub const VOLUME_ID_FILE: &str = ".videovault.volume-id";
 
 /// 返回（或在可写时创建）一个稳定的卷标识。
-/// 优先读取根目录下的 `.videovault.volume-id`，不存在且可写时创建一个 UUID。
-/// 若不可写或创建失败，则回退到系统级标识。
+/// 优先读取根目录下的 `.videovault.volume-id`，不存在时尝试创建一个 UUID。
+/// 若不可写或创建失败，则回退到系统级标识。只读模式下请使用 [`read_volume_key`]。
 pub fn get_or_create_volume_key(root: &Path) -> Result<String, String> {
-    let id_file = root.join(".videovault.volume-id");
+    let id_file = root.join(VOLUME_ID_FILE);
 
-    if let Ok(data) = fs::read_to_string(&id_file) {
-        let id = data.trim();
-        if !id.is_empty() {
-            return Ok(format!("custom:{}", id));
-        }
+    if let Some(id) = read_marker(&id_file) {
+        return Ok(format!("custom:{}", id));
     }
 
-    // 尝试创建自定义卷 ID（如果目录可写）
-    if is_dir_writable(root) {
-        let uuid = uuid::Uuid::new_v4().to_string();
-        if let Err(e) = fs::write(&id_file, &uuid) {
-            // 写入失败则继续回退
-            eprintln!("写入卷ID文件失败: {}", e);
-        } else {
-            return Ok(format!("custom:{}", uuid));
-        }
+    // 直接尝试创建自定义卷 ID，不再写入探测文件判断是否可写
+    let uuid = uuid::Uuid::new_v4().to_string();
+    let created = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&id_file)
+        .and_then(|mut file| std::io::Write::write_all(&mut file, uuid.as_bytes()));
+    // 写入失败则继续回退
+    if created.is_ok() {
+        return Ok(format!("custom:{}", uuid));
     }
 
     // 回退：使用平台相关的系统标识
     get_system_volume_key(root)
 }
 
-fn is_dir_writable(dir: &Path) -> bool {
-    let probe = dir.join(".vv.__probe__");
-    match fs::write(&probe, b"probe") {
-        Ok(_) => {
-            let _ = fs::remove_file(&probe);
-            true
-        }
-        Err(_) => false,
-    }
+/// 读取卷标识文件，内容为空时返回 None
+fn read_marker(path: &Path) -> Option<String> {
+    let data = fs::read_to_string(path).ok()?;
+    let id = data.trim();
+    (!id.is_empty()).then(|| id.to_string())
 }
 
 fn get_system_volume_key(root: &Path) -> Result<String, String> {
@@ -99,6 +102,13 @@ fn get_system_volume_key(root: &Path) -> Result<String, String> {
     #[cfg(unix)]
     {
         use std::os::unix::fs::MetadataExt;
+
+        // Linux：优先使用文件系统 UUID / 卷标，重启或换接口后保持不变
+        #[cfg(target_os = "linux")]
+        if let Some(key) = linux_filesystem_key(root) {
+            return Ok(key);
+        }
+
         let md = fs::metadata(root).map_err(|e| e.to_string())?;
         let dev = md.dev();
         let ino = md.ino();
@@ -106,10 +116,346 @@ fn get_system_volume_key(root: &Path) -> Result<String, String> {
     }
 }
 
+/// 卷标识是否为旧版本的 `unix:设备号:inode` 格式（重启或重新插拔后会变化）
+pub fn is_legacy_unix_key(key: &str) -> bool {
+    key.starts_with("unix:")
+}
+
+/// 根据根目录所在挂载点的文件系统 UUID（或卷标）生成卷标识
+#[cfg(target_os = "linux")]
+fn linux_filesystem_key(root: &Path) -> Option<String> {
+    let root = fs::canonicalize(root).ok()?;
+    let mounts = crate::mount::list_mounts();
+    let entry = crate::mount::find_mount_for_path(&mounts, &root)?;
+    filesystem_key(crate::mount::filesystem_ids(entry))
+}
+
+/// 由文件系统 UUID（优先）或卷标生成卷标识；卷标可能重复，仅在没有 UUID 时使用
+fn filesystem_key(ids: crate::mount::FilesystemIds) -> Option<String> {
+    ids.uuid
+        .map(|uuid| format!("uuid:{}", uuid))
+        .or_else(|| ids.label.map(|label| format!("label:{}", label)))
+}
+
+/// 用户数据中相对路径的基准目录（真实路径）：自定义卷标识随标识文件所在的根目录携带，以根目录为基准；
+/// 文件系统标识属于整个卷，以挂载点为基准，使同一卷上多个根文件夹的相对路径互不冲突
+pub fn volume_anchor(volume_key: &str, root: &Path) -> Option<PathBuf> {
+    let canonical = fs::canonicalize(root).ok()?;
+    if volume_key.starts_with("custom:") {
+        return Some(canonical);
+    }
+    let mounts = crate::mount::list_mounts();
+    let mount_point = crate::mount::find_mount_for_path(&mounts, &canonical).map(|mount| mount.mount_point.clone());
+    // 找不到挂载点时（如 Windows 上带 \\?\ 前缀的真实路径）以所在盘的根目录为基准
+    mount_point.or_else(|| canonical.ancestors().last().map(Path::to_path_buf))
+}
+
+/// 根文件夹在卷上的位置：相对基准目录的 `/` 分隔路径，卷不在线时返回 None
+pub fn volume_path(volume_key: &str, root: &Path) -> Option<String> {
+    let anchor = volume_anchor(volume_key, root)?;
+    persist::relative_key(&anchor, &fs::canonicalize(root).ok()?).ok()
+}
+
+/// 卷的详细信息（挂载点、文件系统类型与容量）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VolumeInfo {
+    /// 卷标识
+    pub volume_key: Option<String>,
+    /// 挂载点
+    pub mount_point: Option<PathBuf>,
+    /// 文件系统类型
+    pub fs_type: Option<String>,
+    /// 挂载源（设备或网络地址）
+    pub source: Option<String>,
+    /// 文件系统 UUID
+    pub uuid: Option<String>,
+    /// 卷标
+    pub label: Option<String>,
+    /// 总容量（字节）
+    pub total_bytes: Option<u64>,
+    /// 可用空间（字节）
+    pub available_bytes: Option<u64>,
+    /// 是否在线（离线时容量为最后一次在线时的记录）
+    pub online: bool,
+}
+
+/// 读取在线根目录所在卷的信息
+pub fn get_volume_info(root: &Path, state_dir: Option<&Path>) -> Result<VolumeInfo, String> {
+    let canonical = fs::canonicalize(root).map_err(|e| format!("无法访问 {}: {}", root.display(), e))?;
+    let mounts = crate::mount::list_mounts();
+    let mount = crate::mount::find_mount_for_path(&mounts, &canonical);
+    let ids = mount.map(crate::mount::filesystem_ids).unwrap_or_default();
+
+    Ok(VolumeInfo {
+        volume_key: read_volume_key(root, state_dir).ok(),
+        mount_point: mount.map(|m| m.mount_point.clone()),
+        fs_type: mount.map(|m| m.fs_type.clone()).filter(|t| !t.is_empty()),
+        source: mount.map(|m| m.source.clone()).filter(|s| !s.is_empty()),
+        uuid: ids.uuid,
+        label: ids.label,
+        total_bytes: fs4::total_space(root).ok(),
+        available_bytes: fs4::available_space(root).ok(),
+        online: true,
+    })
+}
+
+/// 只读地获取卷标识：依次读取根目录下的 `.videovault.volume-id`、只读模式下迁移到应用数据目录的卷标识，
+/// 都不存在时使用系统级标识，不写入任何文件。
+pub fn read_volume_key(root: &Path, state_dir: Option<&Path>) -> Result<String, String> {
+    let marker = read_marker(&root.join(VOLUME_ID_FILE))
+        .or_else(|| state_dir.and_then(|dir| read_marker(&dir.join(crate::policy::STATE_VOLUME_ID_FILE))));
+    match marker {
+        Some(id) => Ok(format!("custom:{}", id)),
+        None => get_system_volume_key(root),
+    }
+}
+
+/// 在当前挂载点中查找根文件夹的新路径，用于硬盘挂载到新路径后重新绑定根文件夹。
+/// 自定义卷标识：查找带有相同 `.videovault.volume-id` 的目录；
+/// 文件系统 UUID/卷标：在同一文件系统的新挂载点下取最深的已存在候选目录。
+pub fn find_relocated_root(volume_key: &str, old_path: &Path, mounts: &[crate::mount::MountEntry]) -> Option<PathBuf> {
+    if let Some(id) = volume_key.strip_prefix("custom:") {
+        return mounts
+            .iter()
+            .flat_map(|mount| crate::mount::relocation_candidates(&mount.mount_point, old_path))
+            .filter(|candidate| candidate != old_path)
+            .find(|candidate| {
+                fs::read_to_string(candidate.join(VOLUME_ID_FILE))
+                    .map(|data| data.trim() == id)
+                    .unwrap_or(false)
+            });
+    }
+
+    if volume_key.starts_with("uuid:") || volume_key.starts_with("label:") {
+        return mounts
+            .iter()
+            .filter(|mount| filesystem_key(crate::mount::filesystem_ids(mount)).as_deref() == Some(volume_key))
+            .flat_map(|mount| crate::mount::relocation_candidates(&mount.mount_point, old_path))
+            .rev()
+            .find(|candidate| candidate != old_path && candidate.is_dir());
+    }
+
+    None
+}
+
+/// 根文件夹重新绑定事件（root-relocated）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RootRelocatedEvent {
+    /// 根文件夹 ID（保持不变）
+    pub root_id: String,
+    /// 原路径
+    pub old_path: PathBuf,
+    /// 新路径
+    pub new_path: PathBuf,
+}
+
+/// 卷上线/离线事件（volume-online / volume-offline）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumeStatusEvent {
+    /// 受影响的根文件夹
+    pub root_id: String,
+    /// 卷标识
+    pub volume_key: Option<String>,
+    /// 根文件夹路径
+    pub path: PathBuf,
+    /// 是否在线
+    pub online: bool,
+}
+
+/// 已知卷的登记信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumeRecord {
+    /// 卷标识
+    pub key: String,
+    /// 卷标签（显示名称）
+    pub label: Option<String>,
+    /// 总容量（字节）
+    pub total_bytes: Option<u64>,
+    /// 可用空间（字节）
+    pub available_bytes: Option<u64>,
+    /// 最后一次在线的时间
+    pub last_seen: DateTime<Utc>,
+    /// 曾经挂载/访问过的路径
+    pub known_paths: Vec<PathBuf>,
+    /// 最后一次在线时用户数据路径的基准目录（见 [`volume_anchor`]），用于离线添加根文件夹
+    #[serde(default)]
+    pub anchor: Option<PathBuf>,
+    /// 当前是否在线
+    pub online: bool,
+}
+
+/// 卷登记表：记住每个卷的标识、标签、容量与最后在线时间，持久化到应用数据目录
+pub struct VolumeRegistry {
+    file_path: PathBuf,
+    volumes: BTreeMap<String, VolumeRecord>,
+}
+
+impl VolumeRegistry {
+    /// 从应用数据目录加载卷登记表
+    pub fn load(data_dir: &Path) -> Self {
+        let file_path = data_dir.join(VOLUMES_FILE);
+        let volumes = persist::read_json(&file_path).unwrap_or_else(|e| {
+            println!("加载卷登记表失败: {}", e);
+            BTreeMap::new()
+        });
+        Self { file_path, volumes }
+    }
+
+    /// 保存卷登记表
+    pub fn save(&self) -> Result<(), String> {
+        persist::write_json(&self.file_path, &self.volumes)
+    }
+
+    /// 记录卷在指定路径上线，刷新标签、容量与最后在线时间
+    pub fn mark_seen(&mut self, key: &str, path: &Path) -> &VolumeRecord {
+        let label = key
+            .strip_prefix("label:")
+            .map(str::to_string)
+            .or_else(|| path.file_name().map(|n| n.to_string_lossy().to_string()));
+        let total_bytes = fs4::total_space(path).ok();
+        let available_bytes = fs4::available_space(path).ok();
+
+        let record = self.volumes.entry(key.to_string()).or_insert_with(|| VolumeRecord {
+            key: key.to_string(),
+            label: None,
+            total_bytes: None,
+            available_bytes: None,
+            last_seen: Utc::now(),
+            known_paths: Vec::new(),
+            anchor: None,
+            online: true,
+        });
+        if record.label.is_none() {
+            record.label = label;
+        }
+        record.anchor = volume_anchor(key, path).or(record.anchor.take());
+        record.total_bytes = total_bytes.or(record.total_bytes);
+        record.available_bytes = available_bytes.or(record.available_bytes);
+        record.last_seen = Utc::now();
+        record.online = true;
+        if !record.known_paths.iter().any(|p| p == path) {
+            record.known_paths.push(path.to_path_buf());
+        }
+        record
+    }
+
+    /// 标记卷离线，返回状态是否发生变化
+    pub fn mark_offline(&mut self, key: &str) -> bool {
+        match self.volumes.get_mut(key) {
+            Some(record) if record.online => {
+                record.online = false;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 查找曾在该路径（或其上级目录）出现过、且该已知路径当前不存在的卷（即离线卷）
+    pub fn find_offline_by_path(&self, path: &Path) -> Option<&VolumeRecord> {
+        self.volumes
+            .values()
+            .filter(|record| {
+                record
+                    .known_paths
+                    .iter()
+                    .any(|known| path.starts_with(known) && !known.exists())
+            })
+            .max_by_key(|record| record.last_seen)
+    }
+
+    /// 根据登记信息构造离线卷的信息
+    pub fn offline_info(&self, key: &str) -> VolumeInfo {
+        let record = self.volumes.get(key);
+        VolumeInfo {
+            volume_key: Some(key.to_string()),
+            label: record.and_then(|r| r.label.clone()),
+            total_bytes: record.and_then(|r| r.total_bytes),
+            available_bytes: record.and_then(|r| r.available_bytes),
+            online: false,
+            ..Default::default()
+        }
+    }
+
+    /// 列出所有已知卷
+    pub fn list(&self) -> Vec<VolumeRecord> {
+        self.volumes.values().cloned().collect()
+    }
+}
+
 /// 将绝对路径转换为相对于根目录的相对路径。
 pub fn to_relative_path(root: &Path, absolute: &Path) -> Result<PathBuf, String> {
     let rel = absolute.strip_prefix(root).map_err(|e| e.to_string())?;
     Ok(rel.to_path_buf())
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_tracks_online_state() {
+        let data_dir = std::env::temp_dir().join(format!("vv-volume-test-{}", uuid::Uuid::new_v4()));
+        let mount = data_dir.join("disk");
+        fs::create_dir_all(&mount).unwrap();
+        let anchor = fs::canonicalize(&mount).ok();
+
+        let mut registry = VolumeRegistry::load(&data_dir);
+        assert!(registry.mark_seen("custom:abc", &mount).online);
+        assert!(registry.mark_offline("custom:abc"));
+        assert!(!registry.mark_offline("custom:abc"));
+        registry.save().unwrap();
+        // 已知路径仍然存在时，路径不存在只是子目录缺失，不按离线卷处理
+        assert!(registry.find_offline_by_path(&mount.join("movies")).is_none());
+
+        fs::remove_dir_all(&mount).unwrap();
+        let reloaded = VolumeRegistry::load(&data_dir);
+        let record = reloaded.find_offline_by_path(&mount.join("movies")).unwrap();
+        assert_eq!(record.key, "custom:abc");
+        assert!(!record.online);
+        assert_eq!(record.anchor, anchor);
+
+        fs::remove_dir_all(&data_dir).unwrap();
+    }
 
+    #[test]
+    fn test_volume_path_is_relative_to_anchor() {
+        let dir = std::env::temp_dir().join(format!("vv-volume-test-{}", uuid::Uuid::new_v4()));
+        let root = dir.join("shows");
+        fs::create_dir_all(&root).unwrap();
+
+        // 自定义卷标识以根目录为基准；文件系统标识以挂载点为基准，包含根文件夹在卷上的位置
+        assert_eq!(volume_path("custom:abc", &root).as_deref(), Some(""));
+        let anchor = volume_anchor("uuid:disk", &root).unwrap();
+        let location = volume_path("uuid:disk", &root).unwrap();
+        assert!(location.ends_with("shows"));
+        assert_eq!(persist::resolve_key(&anchor, &location), fs::canonicalize(&root).unwrap());
+        assert_eq!(volume_path("uuid:disk", &dir.join("missing")), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_find_relocated_root() {
+        let base = std::env::temp_dir().join(format!("vv-relocate-test-{}", uuid::Uuid::new_v4()));
+        let new_mount = base.join("Movies1");
+        fs::create_dir_all(new_mount.join("anime")).unwrap();
+        fs::write(new_mount.join("anime").join(VOLUME_ID_FILE), "abc-123\n").unwrap();
+
+        let mounts = vec![crate::mount::MountEntry {
+            mount_point: new_mount.clone(),
+            fs_type: "exfat".to_string(),
+            source: "/dev/sdb1".to_string(),
+            device: None,
+        }];
+        let old_path = base.join("Movies").join("anime");
+
+        assert_eq!(
+            find_relocated_root("custom:abc-123", &old_path, &mounts),
+            Some(new_mount.join("anime"))
+        );
+        assert!(find_relocated_root("custom:other", &old_path, &mounts).is_none());
+        assert!(find_relocated_root("unix:1:2", &old_path, &mounts).is_none());
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -286,6 +286,30 @@ function App() {
     setShowInfoToast(false);
   };
 
+  // 播放视频：换算为当前根文件夹下的相对路径后交给后端
+  const handlePlayVideo = async (videoPath: string) => {
+    const root = selectedFolder ? findFolderById(selectedFolder) : undefined;
+    if (!root) {
+      setDirectoryError('无法播放视频: 未选择根文件夹');
+      return;
+    }
+    const normalizePath = (p: string) => p.replace(/[\\/]+$/, '').replace(/\\/g, '/');
+    const rootPath = normalizePath(root.path);
+    const path = normalizePath(videoPath);
+    // Windows 路径（盘符或 UNC）不区分大小写
+    const isWindowsPath = /^([a-zA-Z]:|\/\/)/.test(rootPath);
+    const comparable = (p: string) => (isWindowsPath ? p.toLowerCase() : p);
+    if (!comparable(path).startsWith(`${comparable(rootPath)}/`)) {
+      setDirectoryError(`无法播放视频: 视频不在根文件夹 "${root.name}" 中`);
+      return;
+    }
+    try {
+      await playVideo(root.id, path.slice(rootPath.length + 1));
+    } catch (error) {
+      setDirectoryError(`${error instanceof Error ? error.message : error}`);
+    }
+  };
+
   // 预览视频
   const previewVideo = (video: VideoInfo, videoList: VideoInfo[]) => {
     const index = videoList.findIndex(v => v.path === video.path);
@@ -501,7 +525,7 @@ function App() {
           onNavigateToDirectory={navigateToDirectory}
           onRescanCurrentFolder={handleRescanCurrentFolder}
           onInvalidateCache={invalidateCache}
-          onPlayVideo={playVideo}
+          onPlayVideo={handlePlayVideo}
           onPreviewVideo={previewVideo}
         />
 
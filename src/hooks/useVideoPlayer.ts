@@ -1,38 +1,14 @@
 import { invoke } from '@tauri-apps/api/core';
+import type { PlayOptions } from '../types';
 
 export const useVideoPlayer = () => {
-  // 播放视频
-  const playVideo = async (videoPath: string) => {
+  // 播放视频：视频由根文件夹 ID 与相对路径（/ 分隔）指定；profile 为空时由后端按根文件夹、容器格式与默认配置选择播放器
+  const playVideo = async (rootId: string, relativePath: string, profile?: string, options?: PlayOptions) => {
     try {
-      // 使用系统默认播放器打开视频
-      await invoke('open_video', { path: videoPath });
+      await invoke('play_video', { rootId, relativePath, profile: profile ?? null, options: options ?? null });
     } catch (error) {
       console.error('播放视频失败:', error);
-      // 如果后端命令不存在，使用系统命令
-      try {
-        if (navigator.platform.includes('Mac')) {
-          // macOS
-          await invoke('execute_command', {
-            command: 'open',
-            args: [videoPath]
-          });
-        } else if (navigator.platform.includes('Win')) {
-          // Windows
-          await invoke('execute_command', {
-            command: 'start',
-            args: [videoPath]
-          });
-        } else {
-          // Linux
-          await invoke('execute_command', {
-            command: 'xdg-open',
-            args: [videoPath]
-          });
-        }
-      } catch (cmdError) {
-        console.error('执行系统命令失败:', cmdError);
-        throw new Error('无法打开视频文件');
-      }
+      throw new Error(`无法播放视频: ${error}`);
     }
   };
 
@@ -6,6 +6,23 @@ export interface RootFolder {
   enabled: boolean;
   max_depth: number;
   last_scan: string | null;
+  volume_key?: string | null;
+  volume_path?: string;
+  online?: boolean;
+  mount_type?: MountType;
+  fs_type?: string | null;
+  scan_options?: RootScanOptions;
+}
+
+// 根文件夹所在的挂载类型
+export type MountType = 'local' | 'network' | 'unknown';
+
+// 根文件夹的扫描选项（未设置的项使用全局配置）
+export interface RootScanOptions {
+  parallel?: boolean | null;
+  max_threads?: number | null;
+  probe_timeout_secs?: number | null;
+  header_only_probe?: boolean | null;
 }
 
 // 视频信息接口
@@ -25,6 +42,25 @@ export interface VideoInfo {
   container_format?: string | null;
   frame_rate?: number | null;
   bit_rate?: number | null;
+  embedded_cover?: EmbeddedCover | null;
+  title?: string | null;
+  annotation?: VideoAnnotation;
+}
+
+// 用户对视频的评分、收藏与备注
+export interface VideoAnnotation {
+  rating: number | null;
+  favorite: boolean;
+  notes: string | null;
+  updated_at: string | null;
+}
+
+// 视频内嵌封面
+export interface EmbeddedCover {
+  kind: 'AttachedPic' | 'Attachment';
+  stream_index: number;
+  mime_type: string;
+  filename: string | null;
 }
 
 // 时长接口
@@ -59,3 +95,321 @@ export interface FolderHistoryItem {
   volumeKey?: string;
   addedAt: number;
 }
+
+// 自定义封面设置（后端按根文件夹持久化）
+export interface CustomCoverEntry {
+  target_path: string;
+  cover_path: string;
+}
+
+// 视频故事板（联系表 + WebVTT 精灵图映射）
+export interface StoryboardInfo {
+  image_path: string;
+  vtt_path: string;
+  columns: number;
+  rows: number;
+  tile_width: number;
+  tile_height: number;
+  interval_secs: number;
+}
+
+// 悬停预览生成事件（preview-generated）
+export interface PreviewEvent {
+  video_path: string;
+  preview_path: string | null;
+  error: string | null;
+}
+
+// 悬停预览任务状态
+export interface PreviewJobStatus {
+  queued: number;
+  running: number;
+  completed: number;
+  failed: number;
+  max_concurrency: number;
+}
+
+// 已知卷的登记信息
+export interface VolumeRecord {
+  key: string;
+  label: string | null;
+  total_bytes: number | null;
+  available_bytes: number | null;
+  last_seen: string;
+  known_paths: string[];
+  anchor?: string | null;
+  online: boolean;
+}
+
+// 卷上线/离线事件（volume-online / volume-offline）
+export interface VolumeStatusEvent {
+  root_id: string;
+  volume_key: string | null;
+  path: string;
+  online: boolean;
+}
+
+// 根文件夹重新绑定事件（硬盘挂载到新路径）
+export interface RootRelocatedEvent {
+  root_id: string;
+  old_path: string;
+  new_path: string;
+}
+
+// 卷信息（挂载点、文件系统与容量）
+export interface VolumeInfo {
+  volume_key: string | null;
+  mount_point: string | null;
+  fs_type: string | null;
+  source: string | null;
+  uuid: string | null;
+  label: string | null;
+  total_bytes: number | null;
+  available_bytes: number | null;
+  online: boolean;
+}
+
+// 存储策略（只读模式）
+export interface StoragePolicy {
+  read_only: boolean;
+  roots: Record<string, boolean>;
+}
+
+// 根文件夹数据迁移结果
+export interface StateMigrationReport {
+  read_only: boolean;
+  moved_files: number;
+  state_dir: string;
+}
+
+// 新根文件夹与已有根文件夹的重叠关系
+export type RootOverlap =
+  | { kind: 'same_path'; root_id: string }
+  | { kind: 'inside_existing'; root_id: string }
+  | { kind: 'contains_existing'; root_ids: string[] };
+
+// 重叠时的处理方式：合并或嵌套
+export type OverlapMode = 'merge' | 'nest';
+
+// 目录导出结果
+export interface CatalogExportInfo {
+  catalog_path: string;
+  video_count: number;
+}
+
+// 目录导入结果（含与当前文件的校验情况）
+export interface CatalogImportReport {
+  exported_at: string;
+  video_count: number;
+  verified: number;
+  reprobed: string[];
+  missing: string[];
+  added: string[];
+  volume_mismatch: boolean;
+  imported_tags: number;
+}
+
+// 目录占用空间（包含子目录）
+export interface DirectoryUsage {
+  path: string;
+  name: string;
+  total_bytes: number;
+  video_count: number;
+  children: DirectoryUsage[];
+}
+
+// 按编码、分辨率或容器分组的占用空间
+export interface UsageBucket {
+  key: string;
+  total_bytes: number;
+  video_count: number;
+}
+
+// 存储报告中的大文件
+export interface LargeFile {
+  path: string;
+  size: number;
+  codec: string | null;
+  resolution: [number, number] | null;
+}
+
+// 根文件夹的存储报告
+export interface StorageReport {
+  root_id: string;
+  total_bytes: number;
+  video_count: number;
+  directories: DirectoryUsage;
+  by_codec: UsageBucket[];
+  by_resolution: UsageBucket[];
+  by_container: UsageBucket[];
+  largest_files: LargeFile[];
+  volume: VolumeInfo | null;
+}
+
+// 全局设置
+export interface AppSettings {
+  version: number;
+  scan: ScanSettings;
+  covers: CoverSettings;
+  preview: PreviewSettings;
+  playback: PlaybackSettings;
+}
+
+// 扫描设置
+export interface ScanSettings {
+  parallel: boolean;
+  max_threads: number;
+  max_files_per_dir: number;
+  max_depth: number;
+  probe_timeout_secs: number;
+  video_extensions: string[];
+  fallback_max_videos: number;
+  fallback_max_children: number;
+}
+
+// 封面设置
+export interface CoverSettings {
+  image_extensions: string[];
+  folder_cover_names: string[];
+}
+
+// 预览生成设置
+export interface PreviewSettings {
+  max_concurrency: number;
+}
+
+// 播放设置
+export interface PlaybackSettings {
+  backend: 'system' | 'mpv';
+  mpv_path: string;
+  resume: boolean;
+  profiles: PlayerProfile[];
+  default_profile: string | null;
+  root_profiles: Record<string, string>;
+  container_profiles: Record<string, string>;
+}
+
+// 外部播放器配置（参数模板支持 {path}、{start}、{subtitle} 占位符）
+export interface PlayerProfile {
+  name: string;
+  executable: string;
+  args: string[];
+}
+
+// 播放选项
+export interface PlayOptions {
+  start_secs?: number | null;
+  subtitle?: string | null;
+}
+
+// 全局搜索结果（按相关度排序）
+export interface SearchResults {
+  total: number;
+  videos: VideoInfo[];
+}
+
+// 查询结果的排序字段
+export type SortKey = 'name' | 'path' | 'size' | 'duration' | 'resolution' | 'bit_rate' | 'modified' | 'created' | 'rating';
+
+// 查询结果的排序方式
+export interface QuerySort {
+  key: SortKey;
+  descending: boolean;
+}
+
+// 智能合集（保存的查询与排序方式）
+export interface SmartCollection {
+  id: string;
+  name: string;
+  query: string;
+  sort: QuerySort;
+  created_at: string;
+  video_count: number;
+}
+
+// 子目录摘要（数量与大小包含子目录）
+export interface DirectorySummary {
+  name: string;
+  rel_path: string;
+  video_count: number;
+  cover_count: number;
+  cover_path: string | null;
+  modified_time: {
+    secs_since_epoch: number;
+    nanos_since_epoch: number;
+  } | null;
+  total_bytes: number;
+  child_count: number;
+}
+
+// 单层目录内容（分页）
+export interface DirectoryListing {
+  name: string;
+  rel_path: string;
+  directories: DirectorySummary[];
+  videos: VideoInfo[];
+  total_directories: number;
+  total_videos: number;
+  offset: number;
+}
+
+// 目录树大纲（只含数量）
+export interface TreeOutline {
+  name: string;
+  rel_path: string;
+  video_count: number;
+  cover_count: number;
+  children: TreeOutline[];
+}
+
+// 用户标签
+export interface Tag {
+  id: string;
+  name: string;
+  color: string;
+  created_at: string;
+}
+
+// 根文件夹内的标签分配（相对路径 → 标签 ID）
+export type TagAssignments = Record<string, string[]>;
+
+// 一次播放
+export interface PlayEvent {
+  played_at: string;
+  watched_secs: number | null;
+}
+
+// 视频的观看状态
+export interface WatchState {
+  watched: boolean;
+  position_secs: number | null;
+  duration_secs: number | null;
+  last_played_at: string | null;
+  play_count: number;
+  plays: PlayEvent[];
+}
+
+// 最近播放、继续观看列表中的视频
+export interface HistoryVideo {
+  path: string;
+  root_id: string;
+  video: VideoInfo | null;
+  state: WatchState;
+}
+
+// mpv 播放状态（playback-progress 事件）
+export interface PlaybackStatus {
+  video_path: string;
+  position_secs: number | null;
+  duration_secs: number | null;
+  paused: boolean;
+  eof_reached: boolean;
+  watched_secs: number;
+  finished: boolean;
+}
+
+// 视频章节
+export interface MpvChapter {
+  title: string | null;
+  time: number;
+}